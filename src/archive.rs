@@ -0,0 +1,258 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Streaming serialization of operation archives, i.e. append-only logs of contract operations.
+//!
+//! Each of the operations in the archive is strict-encoded and prefixed with the length of the
+//! encoded data, represented as a little-endian 32-bit unsigned integer. This allows writing and
+//! reading the archive one operation at a time, without holding the whole archive in memory.
+
+use std::io::{self, Read, Write};
+
+use amplify::confinement::Confined;
+use strict_encoding::{DeserializeError, SerializeError, StrictDeserialize, StrictSerialize};
+
+use crate::Operation;
+
+/// Maximal length of a single strict-encoded operation in the archive.
+pub const OPERATION_MAX_LEN: usize = u32::MAX as usize;
+
+/// Writer appending operations to an archive.
+#[derive(Debug)]
+pub struct OpArchiveWriter<W: Write> {
+    writer: W,
+    count: usize,
+}
+
+impl<W: Write> OpArchiveWriter<W> {
+    /// Constructs a new archive writer.
+    ///
+    /// If the `writer` is not empty (for instance, it is a file opened in append mode containing a
+    /// valid archive), the new operations are appended to the existing ones.
+    pub fn new(writer: W) -> Self { Self { writer, count: 0 } }
+
+    /// Returns the number of operations written by this writer.
+    pub fn count(&self) -> usize { self.count }
+
+    /// Appends an operation to the archive.
+    ///
+    /// # Errors
+    ///
+    /// On I/O errors, or if the operation is too large to be put into the archive.
+    pub fn write(&mut self, operation: &Operation) -> Result<(), SerializeError> {
+        let data = operation.to_strict_serialized::<OPERATION_MAX_LEN>()?;
+        self.writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&data)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Flushes the archive and returns the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// On I/O errors happening during the flush.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes all `operations` to an archive, returning the number of operations written.
+///
+/// # Errors
+///
+/// On I/O errors, or if some of the operations are too large to be put into the archive.
+pub fn write_operations<'op>(
+    writer: impl Write,
+    operations: impl IntoIterator<Item = &'op Operation>,
+) -> Result<usize, SerializeError> {
+    let mut writer = OpArchiveWriter::new(writer);
+    for operation in operations {
+        writer.write(operation)?;
+    }
+    let count = writer.count();
+    writer.finish()?;
+    Ok(count)
+}
+
+/// Streaming archive reader, decoding operations one by one.
+///
+/// The reader is an iterator over the operations read from the archive; it stops at the end of
+/// the archive data. If the archive data are truncated or invalid, the iterator returns an error
+/// and stops.
+#[derive(Debug)]
+pub struct OpArchiveReader<R: Read> {
+    reader: R,
+    failed: bool,
+}
+
+impl<R: Read> OpArchiveReader<R> {
+    /// Constructs a reader for the archive data.
+    pub fn new(reader: R) -> Self { Self { reader, failed: false } }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R { self.reader }
+
+    fn read_next(&mut self) -> Result<Option<Operation>, DeserializeError> {
+        let mut len = [0u8; 4];
+        let mut read = 0;
+        while read < len.len() {
+            match self.reader.read(&mut len[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => read += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        // The length prefix is not trusted to allocate the buffer upfront: the buffer grows only as
+        // the data are actually read.
+        let len = u32::from_le_bytes(len) as usize;
+        let mut data = Vec::new();
+        self.reader
+            .by_ref()
+            .take(len as u64)
+            .read_to_end(&mut data)?;
+        if data.len() < len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let data = Confined::<Vec<u8>, 0, OPERATION_MAX_LEN>::from_checked(data);
+        Operation::from_strict_serialized::<OPERATION_MAX_LEN>(data).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for OpArchiveReader<R> {
+    type Item = Result<Operation, DeserializeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = self.read_next().transpose();
+        self.failed = matches!(res, Some(Err(_)));
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use aluvm::fe256;
+    use amplify::confinement::{SmallBlob, SmallVec};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{CellAddr, Input, Opid, StateCell, StateData, StateValue};
+
+    fn synthetic_log(count: u16) -> Vec<Operation> {
+        (0..count)
+            .map(|no| {
+                let mut operation = Operation::strict_dumb();
+                operation.call_id = no;
+                operation.nonce = fe256::from(no as u64);
+                operation.destructible_in = small_vec![Input {
+                    addr: CellAddr::new(Opid::strict_dumb(), no),
+                    witness: StateValue::Single { first: fe256::from(no as u64) },
+                }];
+                operation.destructible_out =
+                    SmallVec::from_iter_checked((0..(no % 8)).map(|pos| StateCell {
+                        data: StateValue::new(pos, no),
+                        auth: fe256::from(pos as u64).into(),
+                        lock: None,
+                    }));
+                operation.immutable_out = small_vec![StateData::with_raw(
+                    no,
+                    no,
+                    SmallBlob::from_checked(vec![0xAC; no as usize])
+                )];
+                operation
+            })
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let log = synthetic_log(500);
+        let mut data = vec![];
+        assert_eq!(write_operations(&mut data, &log).unwrap(), log.len());
+
+        let mut count = 0;
+        for (read, orig) in OpArchiveReader::new(data.as_slice()).zip(&log) {
+            let read = read.unwrap();
+            assert_eq!(read.opid(), orig.opid());
+            assert_eq!(read.call_id, orig.call_id);
+            count += 1;
+        }
+        assert_eq!(count, log.len());
+        assert_eq!(OpArchiveReader::new(data.as_slice()).count(), log.len());
+    }
+
+    #[test]
+    fn append() {
+        let log = synthetic_log(10);
+        let mut data = vec![];
+        write_operations(&mut data, &log[..4]).unwrap();
+        let mut writer = OpArchiveWriter::new(&mut data);
+        for operation in &log[4..] {
+            writer.write(operation).unwrap();
+        }
+        assert_eq!(writer.count(), 6);
+        writer.finish().unwrap();
+
+        let read = OpArchiveReader::new(data.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read, log);
+    }
+
+    #[test]
+    fn empty() {
+        let mut reader = OpArchiveReader::new(&[][..]);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn truncated() {
+        let log = synthetic_log(3);
+        let mut data = vec![];
+        write_operations(&mut data, &log).unwrap();
+
+        for len in [data.len() - 1, data.len() - 200, 2] {
+            let mut reader = OpArchiveReader::new(&data[..len]);
+            let res = reader.by_ref().collect::<Vec<_>>();
+            assert!(res.last().unwrap().is_err());
+            assert!(reader.next().is_none());
+        }
+    }
+
+    #[test]
+    fn oversized_len() {
+        let mut data = u32::MAX.to_le_bytes().to_vec();
+        data.extend([0u8; 16]);
+        let mut reader = OpArchiveReader::new(data.as_slice());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
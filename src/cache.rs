@@ -0,0 +1,123 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeMap;
+use std::sync::{PoisonError, RwLock};
+
+use amplify::Bytes32;
+
+use crate::{CallError, CodexId, ContractId, Opid};
+
+/// Key of a [`VerifyCache`] entry: codex id, contract id, operation id and the memory root.
+type CacheKey = (CodexId, ContractId, Opid, Bytes32);
+
+/// Cache of the operation verification results, used by [`crate::Codex::verify_cached`].
+///
+/// The results are keyed by the codex id, contract id, operation id and a _memory root_: a value
+/// provided by the caller, which must change each time any part of the contract memory which may
+/// be read by the operation changes. For instance, this may be a hash of the state of the
+/// memory cells referenced by the operation (see [`crate::Operation::input_addrs`]), or an id of
+/// the most recent block defining the contract state after a re-org. If the memory root doesn't
+/// reflect a change in the memory, the cache will return outdated results.
+///
+/// Only the successful verification results and the failures of the verification or lock scripts
+/// ([`CallError::Script`], [`CallError::ScriptUnspecified`] and [`CallError::Lock`]) are cached,
+/// since they are fully defined by the cache key. Other errors depend on the environment (like a
+/// library missing from the repository, or the cancellation of the verification) and may change
+/// without the memory root change; they are never cached.
+///
+/// The cache can be shared between threads, for instance, verifying operations in parallel.
+#[derive(Debug, Default)]
+pub struct VerifyCache {
+    results: RwLock<BTreeMap<CacheKey, Result<(), CallError>>>,
+}
+
+impl VerifyCache {
+    /// Constructs an empty cache.
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns a cached verification result, if any.
+    pub fn get(
+        &self,
+        codex_id: CodexId,
+        contract_id: ContractId,
+        opid: Opid,
+        memory_root: Bytes32,
+    ) -> Option<Result<(), CallError>> {
+        self.results
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(codex_id, contract_id, opid, memory_root))
+            .copied()
+    }
+
+    /// Records a verification result, if it is cacheable (see [`VerifyCache`]).
+    ///
+    /// # Returns
+    ///
+    /// Whether the result was recorded.
+    pub fn insert(
+        &self,
+        codex_id: CodexId,
+        contract_id: ContractId,
+        opid: Opid,
+        memory_root: Bytes32,
+        result: Result<(), CallError>,
+    ) -> bool {
+        if !Self::is_cacheable(&result) {
+            return false;
+        }
+        self.results
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert((codex_id, contract_id, opid, memory_root), result);
+        true
+    }
+
+    /// Detects whether the verification result depends only on the cache key.
+    fn is_cacheable(result: &Result<(), CallError>) -> bool {
+        matches!(
+            result,
+            Ok(()) | Err(CallError::Script(_) | CallError::ScriptUnspecified | CallError::Lock(_))
+        )
+    }
+
+    /// Returns the number of cached results.
+    pub fn len(&self) -> usize {
+        self.results
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Detects whether the cache is empty.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        self.results
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear()
+    }
+}
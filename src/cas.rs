@@ -0,0 +1,197 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use aluvm::alu::{Lib, LibId};
+use amplify::confinement::Confined;
+use amplify::ByteArray;
+use strict_encoding::StrictDeserialize;
+
+use crate::{Codex, LibRepo};
+
+/// Maximal length of a blob holding a strict-serialized library.
+pub const LIB_BLOB_MAX_LEN: usize = 0xFF_FFFF;
+
+/// Content-addressed blob store holding strict-serialized AluVM libraries under their ids.
+pub trait BlobStore {
+    /// Fetches the blob stored under the `id`.
+    fn get_blob(&self, id: [u8; 32]) -> Option<Vec<u8>>;
+}
+
+/// Library repository fetching the libraries from a content-addressed blob store.
+///
+/// Since the store is not trusted, each of the fetched libraries is checked to have the requested
+/// id; the libraries which fail the check are not loaded, and are recorded as corrupted (see
+/// [`Self::corrupted`]). Thus, a corrupted store can't inject a wrong library into the
+/// verification, which will fail as if the library is absent.
+///
+/// Since the [`LibRepo`] returns references to the libraries, the libraries must be loaded from
+/// the store before the verification with [`Self::load`] or [`Self::load_codex`].
+#[derive(Clone, Debug)]
+pub struct CasLibRepo<S: BlobStore> {
+    store: S,
+    libs: BTreeMap<LibId, Lib>,
+    corrupted: BTreeSet<LibId>,
+}
+
+impl<S: BlobStore> CasLibRepo<S> {
+    /// Constructs a repository over the blob `store`, without loading any libraries.
+    pub fn new(store: S) -> Self { Self { store, libs: none!(), corrupted: none!() } }
+
+    /// Loads a library with the `lib_id` from the store, together with all the libraries it calls
+    /// (directly or through other libraries).
+    ///
+    /// Returns whether the library with the `lib_id` was loaded. Libraries which are absent from
+    /// the store, can't be deserialized or have an id different from the requested one are not
+    /// loaded.
+    pub fn load(&mut self, lib_id: LibId) -> bool {
+        let mut queue = vec![lib_id];
+        while let Some(id) = queue.pop() {
+            if self.libs.contains_key(&id) {
+                continue;
+            }
+            let Some(lib) = self.fetch(id) else {
+                continue;
+            };
+            queue.extend(lib.libs.iter().copied());
+            self.libs.insert(id, lib);
+        }
+        self.libs.contains_key(&lib_id)
+    }
+
+    /// Loads all the libraries used by the verifiers of the `codex` (see
+    /// [`Codex::lib_dependencies`]).
+    ///
+    /// Returns whether all the verifier libraries were loaded.
+    pub fn load_codex(&mut self, codex: &Codex) -> bool {
+        let lib_ids = codex
+            .verifiers
+            .values()
+            .map(|site| site.lib_id)
+            .collect::<BTreeSet<_>>();
+        let mut all = true;
+        for lib_id in lib_ids {
+            all &= self.load(lib_id);
+        }
+        all
+    }
+
+    /// Returns ids of the libraries which blobs were present in the store, but didn't match the
+    /// requested id or were not valid libraries.
+    pub fn corrupted(&self) -> &BTreeSet<LibId> { &self.corrupted }
+
+    /// Returns the underlying blob store.
+    pub fn store(&self) -> &S { &self.store }
+
+    fn fetch(&mut self, lib_id: LibId) -> Option<Lib> {
+        let blob = self.store.get_blob(lib_id.to_byte_array())?;
+        let lib = Confined::try_from(blob)
+            .ok()
+            .and_then(|blob| Lib::from_strict_serialized::<LIB_BLOB_MAX_LEN>(blob).ok())
+            .filter(|lib| lib.lib_id() == lib_id);
+        if lib.is_none() {
+            self.corrupted.insert(lib_id);
+        }
+        lib
+    }
+}
+
+impl<S: BlobStore> LibRepo for CasLibRepo<S> {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> { self.libs.get(&lib_id) }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use std::collections::HashMap;
+
+    use aluvm::alu::LibSite;
+    use strict_encoding::{StrictDumb, StrictSerialize};
+
+    use super::*;
+    use crate::memory::test_helpers::DumbMemory;
+    use crate::{uasm, CallError, ContractId, Operation};
+
+    impl BlobStore for HashMap<[u8; 32], Vec<u8>> {
+        fn get_blob(&self, id: [u8; 32]) -> Option<Vec<u8>> { self.get(&id).cloned() }
+    }
+
+    fn blob(lib: &Lib) -> Vec<u8> {
+        lib.to_strict_serialized::<LIB_BLOB_MAX_LEN>()
+            .unwrap()
+            .release()
+    }
+
+    #[test]
+    fn cas_repo() {
+        let leaf = Lib::assemble(&uasm! { ret; }).unwrap();
+        let leaf_id = leaf.lib_id();
+        let root = Lib::assemble(&uasm! {
+            call    leaf_id, 0;
+            stop;
+        })
+        .unwrap();
+        let root_id = root.lib_id();
+        let other = Lib::assemble(&uasm! { stop; }).unwrap();
+
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(root_id, 0) };
+        let operation = Operation::strict_dumb();
+        let contract_id = ContractId::strict_dumb();
+
+        let store = HashMap::from([
+            (root_id.to_byte_array(), blob(&root)),
+            (leaf_id.to_byte_array(), blob(&leaf)),
+        ]);
+        let mut repo = CasLibRepo::new(store);
+        assert!(repo.get_lib(root_id).is_none());
+        assert!(repo.load_codex(&codex));
+        assert!(repo.get_lib(leaf_id).is_some());
+        assert!(repo.corrupted().is_empty());
+        assert!(codex
+            .verify(contract_id, operation.clone(), &DumbMemory::default(), &repo)
+            .is_ok());
+
+        // Store returns a different library under the leaf id
+        let store = HashMap::from([
+            (root_id.to_byte_array(), blob(&root)),
+            (leaf_id.to_byte_array(), blob(&other)),
+        ]);
+        let mut repo = CasLibRepo::new(store);
+        assert!(repo.load(root_id));
+        assert!(repo.get_lib(leaf_id).is_none());
+        assert_eq!(repo.corrupted(), &bset![leaf_id]);
+        assert_eq!(
+            codex.verify(contract_id, operation, &DumbMemory::default(), &repo),
+            Err(CallError::LibNotFound(leaf_id))
+        );
+
+        // Invalid blob
+        let store = HashMap::from([(root_id.to_byte_array(), vec![0xFFu8; 3])]);
+        let mut repo = CasLibRepo::new(store);
+        assert!(!repo.load_codex(&codex));
+        assert_eq!(repo.corrupted(), &bset![root_id]);
+    }
+}
@@ -21,25 +21,44 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+#[cfg(feature = "test-utils")]
+use core::fmt::{self, Display, Formatter};
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 
 use aluvm::alu::regs::Status;
-use aluvm::alu::{CoreConfig, CoreExt, Lib, LibId, LibSite, Vm};
+use aluvm::alu::{CoreConfig, CoreExt, Lib, LibId, LibSite, Marshaller, Vm};
+use aluvm::isa::{Bytecode, BytecodeRead};
 use aluvm::{fe256, GfaConfig, RegE};
-use amplify::confinement::{SmallVec, TinyOrdMap, TinyString};
+use amplify::confinement::{SmallOrdSet, SmallVec, TinyOrdMap, TinyString};
 use amplify::num::u256;
 use amplify::Bytes32;
 use commit_verify::{CommitId, CommitmentId, DigestExt, ReservedBytes, Sha256};
+use strict_encoding::{StreamWriter, StrictDeserialize, StrictEncode, StrictSerialize};
 
+use crate::hash::HashWriter;
+use crate::memory::{OverlayMemory, PreparedMemory};
 use crate::{
-    CellAddr, ContractId, Identity, Instr, Operation, StateCell, StateValue, VerifiedOperation,
-    VmContext, LIB_NAME_ULTRASONIC,
+    CellAddr, CodexError, CodexPolicy, CollectedError, CommitHash, CommitmentMemory,
+    CommittedMemory, ContractId, DeveloperKey, DeveloperSig, HeightScopedMemory, HistoricMemory,
+    Identity, Input, Instr, MergeError, Operation, PostCondition, PreCondition, StateCell,
+    StateData, StateEffects, StateRoot, StateValue, UpgradeDivergence, UpgradeReport,
+    VerificationReport, VerifiedOperation, VerifyCache, VerifyOptions, VerifyPolicy, VmContext,
+    LIB_NAME_ULTRASONIC,
 };
+#[cfg(feature = "profiling")]
+use crate::{Profile, Profiler};
 
 /// Identifier of a contract method call.
 pub type CallId = u16;
 
+/// Result of the lock script of a destructible input, as recorded by
+/// [`Codex::verify_report_locks`].
+pub type LockOutcome = (CellAddr, Result<(), CallError>);
+
 /// Codex is a crucial part of a contract; it provides a set of commitments to the contract terms
 /// and conditions expressed as a deterministic program able to run in SONIC computer model.
 ///
@@ -58,6 +77,10 @@ pub struct Codex {
     ///
     /// For now, the only supported version is one; thus, a `ReservedBytes` is used.
     ///
+    /// The value can't be set to anything else than zero: conforming verifiers reject codices with
+    /// a non-zero version byte already at the deserialization (both strict and serde), such that
+    /// experimental codex extensions can't be signalled with it and require a new codex version.
+    ///
     /// In the future, with more versions coming, this should be replaced with an enum, where the
     /// first byte will encode (with standard strict encoding) a version number as an enum variant.
     /// For instance,
@@ -75,6 +98,12 @@ pub struct Codex {
     pub name: TinyString,
     /// Identity of the codex developer.
     pub developer: Identity,
+    /// Public key of the codex developer, if any, allowing to verify that the codex was published
+    /// by the developer (see [`Codex::verify_developer_signature`]).
+    ///
+    /// The [`Self::developer`] identity is kept for the display purposes and is not checked
+    /// against the key. The key commits to its signature scheme (see [`DeveloperKey::scheme`]).
+    pub developer_key: Option<DeveloperKey>,
     /// Timestamp of the codex creation.
     ///
     /// This field can be also used to "mine" a vanity codex id. While this feature is noa
@@ -90,8 +119,69 @@ pub struct Codex {
     pub input_config: CoreConfig,
     /// VM core configuration for the operation verification.
     pub verification_config: CoreConfig,
+    /// Bitmask of the `E`-registers the values of which are bridged from the input VM to the VM
+    /// running operation verification, where the least significant bit stands for `E1` and the
+    /// most significant for `EH` (in the order of [`RegE::ALL`]).
+    ///
+    /// This allows a lock script to compute an intermediate value (for instance, a validated
+    /// aggregate), which is then reused by the operation verification script without a
+    /// recomputation.
+    ///
+    /// # Determinism
+    ///
+    /// Since the input VM is reset after checking the lock conditions of each of the inputs, the
+    /// bridged values are taken from the lock script of the _last_ destructible input (in the
+    /// order of [`Operation::destructible_in`]) which has a lock. Thus, the order of inputs in the
+    /// operation affects the verification, and lock scripts intended for bridging must produce
+    /// values which depend only on the input data. A register left empty by the lock script is not
+    /// bridged and stays empty in the verification VM; if none of the inputs has a lock, no values
+    /// are bridged.
+    pub input_bridge: u16,
+    /// Defines how to treat operation immutable inputs ([`Operation::immutable_in`]) which are not
+    /// present in the memory.
+    ///
+    /// If not set, the operation referencing an absent immutable memory cell is invalid and fails
+    /// the verification with [`CallError::NoImmutableInput`]. If set, the absent cells are read as
+    /// empty (i.e. [`StateValue::None`]), which supports contracts where not all the operations
+    /// define immutable data.
+    pub absent_immutable_as_none: bool,
+    /// Component of the destructible output values summed by the host before running the
+    /// operation verification script, if any.
+    ///
+    /// Verifiers frequently need a total of some output value (like an amount) to compare it
+    /// against the inputs. If this field is set to `n`, the sum of the `n`-th elements (starting
+    /// from zero) of the [`StateCell::data`] of all [`Operation::destructible_out`] is put into
+    /// [`Codex::OUTPUT_SUM_REGISTER`] of the verification VM (see [`Codex::output_sum`]).
+    ///
+    /// # Overflows
+    ///
+    /// The sum is computed in the codex field, i.e. modulo [`Self::field_order`], in exactly the
+    /// same way as the VM field addition; it silently wraps on overflow. Verifiers requiring a
+    /// non-wrapping total must ensure that the summed elements are small enough (for instance, by
+    /// checking that each of them fits 64 bits, since the number of outputs is limited).
+    pub output_sum: Option<u8>,
+    /// Register banks into which the witnesses for the input lock scripts are loaded, defined for
+    /// each of the lock script libraries.
+    ///
+    /// Operations may combine inputs locked by the scripts authored by different contract
+    /// modules, which may expect witnesses in different registers. If a lock library is not
+    /// present in this map, its witness is loaded into [`WitnessBank::Primary`] registers.
+    pub witness_banks: TinyOrdMap<LibId, WitnessBank>,
     /// List of verifiers for each of the calls supported by the codex.
+    ///
+    /// Verifiers are always zk-AluVM libraries. Other execution backends (like WASM) are not
+    /// supported by design: the verification must remain deterministic across all hosts and
+    /// provable with zk-STARKs, which is guaranteed only by the zk-AluVM ISA subset.
     pub verifiers: TinyOrdMap<CallId, LibSite>,
+    /// Error code designating an indeterminate verification result, if any.
+    ///
+    /// If not set (the default), the verification is strictly binary: each failure of the
+    /// operation verification script rejects the operation. If set, a failure with the
+    /// `E1` register holding this code is reported as [`CallError::Indeterminate`], signalling
+    /// that the verifier requires more data to reach a decision (for instance, in a multi-round
+    /// interactive verification protocol). Such operations are still not valid, but the caller may
+    /// retry the verification once the data become available.
+    pub indeterminate_code: Option<fe256>,
 }
 
 impl PartialOrd for Codex {
@@ -107,7 +197,47 @@ impl Hash for Codex {
     fn hash<H: Hasher>(&self, state: &mut H) { state.write(&self.commit_id().to_byte_array()); }
 }
 
+impl StrictSerialize for Codex {}
+impl StrictDeserialize for Codex {}
+
 impl Codex {
+    /// Register of the input VM which receives the height/time value for the lock scripts of the
+    /// destructible inputs (see [`Self::verify_timed`]).
+    pub const TIME_REGISTER: RegE = RegE::E7;
+
+    /// Register of the input VM which receives the bitmap of the witness elements provided for the
+    /// destructible input, allowing lock scripts to distinguish an absent witness element from a
+    /// zero one.
+    ///
+    /// The register is set before running each of the lock scripts to a field element holding a
+    /// `u8` value, where bit `n` (starting from the least significant one) is set if the witness
+    /// element `n` is present and loaded into the corresponding register of the
+    /// [`WitnessBank`]. Bits 4 to 7 are always zero. For instance, a witness with two elements
+    /// gives a value of `0b0011`, and an empty witness - a zero value.
+    pub const WITNESS_PRESENCE_REGISTER: RegE = RegE::E6;
+
+    /// Register of the verification VM which receives the sum of the destructible output value
+    /// components, if [`Self::output_sum`] is set.
+    ///
+    /// The register is set after the values bridged from the input VM, overwriting the bridged
+    /// value, if any.
+    pub const OUTPUT_SUM_REGISTER: RegE = RegE::EH;
+
+    /// Registers of the verification VM which final values are returned to the host by
+    /// [`Self::verify_with_outputs_returned`], in the order of the returned values.
+    ///
+    /// The returned values are taken from these registers up to the first empty one, thus a
+    /// verifier returns `n` values by setting the first `n` registers and leaving the rest empty.
+    /// The registers are never written implicitly by the verification VM, unlike `EA`-`ED` (which
+    /// receive the state values loaded by the `ld*` instructions), `E1` (holding the error code)
+    /// and [`Self::OUTPUT_SUM_REGISTER`]. The registers bridged from the input VM (see
+    /// [`Self::bridged_registers`]) are skipped.
+    pub const RETURNED_OUTPUT_REGISTERS: [RegE; 7] =
+        [RegE::E2, RegE::E3, RegE::E4, RegE::E5, RegE::E6, RegE::E7, RegE::E8];
+
+    /// Cost of a single instruction used by [`Self::estimate_cost`].
+    pub const INSTR_COST_ESTIMATE: u64 = 1000;
+
     /// The codex id holds a commitment to all codex data.
     ///
     /// The codex is encoded using strict encoding into the hasher, which is provided by the
@@ -117,6 +247,468 @@ impl Codex {
     /// It is the same as the result of the [`CommitId::commit_id`] procedure.
     pub fn codex_id(&self) -> CodexId { self.commit_id() }
 
+    /// Computes the codex id using the hash function `H`.
+    ///
+    /// The id commits to the strict-serialized codex data in the same way as [`Self::codex_id`],
+    /// which it equals to when computed with [`crate::Sha256Hash`]. See [`CommitHash`] for the
+    /// security implications of choosing another hash function.
+    pub fn codex_id_with<H: CommitHash>(&self) -> CodexId {
+        let mut engine = HashWriter(H::from_tag(CodexId::TAG));
+        let ok = self
+            .strict_write(StreamWriter::new::<{ usize::MAX }>(&mut engine))
+            .is_ok();
+        debug_assert!(ok, "hashing can't fail");
+        CodexId::from(engine.0.finish())
+    }
+
+    /// Returns a summary of the VM configuration used by the codex, with all configuration
+    /// parameters represented as separate named fields.
+    ///
+    /// Comparing (or diffing the `Debug` output of) the summaries of two codices simplifies
+    /// spotting the configuration drift between codex versions.
+    pub fn config_summary(&self) -> ConfigSummary {
+        ConfigSummary {
+            field_order: self.field_order,
+            input_halt: self.input_config.halt,
+            input_complexity_lim: self.input_config.complexity_lim,
+            verification_halt: self.verification_config.halt,
+            verification_complexity_lim: self.verification_config.complexity_lim,
+        }
+    }
+
+    /// Produces the discovery metadata of the codex, which can be indexed by codex registries.
+    ///
+    /// The manifest lists all libraries used by the codex verifiers (see
+    /// [`Self::lib_dependencies`]). The manifest is not signed: since it includes the codex id,
+    /// its content can be checked against the codex, and a registry may sign it with its own key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::LibNotFound`] with the first (in the order of the ids) library used by
+    /// the verifiers which is not known to the `repo`, since its dependencies can't be listed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn manifest(&self, repo: &impl LibRepo) -> Result<CodexManifest, CallError> {
+        let libs = self.lib_dependencies(repo);
+        if let Some(lib_id) = libs.iter().find(|lib_id| repo.get_lib(**lib_id).is_none()) {
+            return Err(CallError::LibNotFound(*lib_id));
+        }
+        Ok(CodexManifest {
+            codex_id: self.codex_id(),
+            name: self.name.clone(),
+            developer: self.developer.clone(),
+            timestamp: self.timestamp,
+            field_bits: self.field_order.bits_required() as u16,
+            verifier_count: self.verifiers.len() as u16,
+            libs,
+        })
+    }
+
+    /// Returns the call ids of the verifiers which are not present in the `covered` set.
+    ///
+    /// The `covered` set is usually collected with [`CoverageTracker`] while verifying a corpus
+    /// of operations; the result lists verifier entries never exercised by the corpus, which may
+    /// be considered for pruning by the contract authors. This is an analysis tool, which is not
+    /// a part of the consensus.
+    pub fn unused_verifiers(&self, covered: &BTreeSet<CallId>) -> Vec<CallId> {
+        self.verifiers
+            .keys()
+            .filter(|call_id| !covered.contains(call_id))
+            .copied()
+            .collect()
+    }
+
+    /// Returns the verifiers of the codex sorted by their call ids.
+    ///
+    /// This is the order in which the verifiers are strict-encoded, and thus committed to by
+    /// [`Self::codex_id`]; user interfaces should render the verifiers in the same order.
+    pub fn verifiers_sorted(&self) -> Vec<(CallId, LibSite)> {
+        // The map is ordered by the keys, matching its strict encoding
+        self.verifiers
+            .iter()
+            .map(|(call_id, site)| (*call_id, *site))
+            .collect()
+    }
+
+    /// Returns machine-readable descriptions of all the calls supported by the codex, in the order
+    /// of their call ids.
+    ///
+    /// The descriptions are intended for generating typed client stubs for the contract calls.
+    /// They include only the information committed to by the codex; the call semantics defined by
+    /// the verifier code (like the expected witnesses or the error codes returned by the verifier)
+    /// are not a part of the codex and must be provided by the contract developer separately.
+    pub fn call_descriptors(&self) -> Vec<CallDescriptor> {
+        self.verifiers
+            .iter()
+            .map(|(call_id, site)| CallDescriptor { call_id: *call_id, verifier: *site })
+            .collect()
+    }
+
+    /// Returns the call ids of the verifiers which entry points are located in the library `lib`.
+    ///
+    /// Only the verifier entry points are considered; verifiers calling the library from other
+    /// libraries are not included (use [`Self::lib_dependencies`] for the analysis of the
+    /// transitive dependencies). This supports impact analysis when a library gets upgraded or
+    /// deprecated.
+    pub fn verifiers_using(&self, lib: LibId) -> Vec<CallId> {
+        self.verifiers
+            .iter()
+            .filter(|(_, site)| site.lib_id == lib)
+            .map(|(call_id, _)| *call_id)
+            .collect()
+    }
+
+    /// Checks whether two codices are the same by comparing their ids.
+    ///
+    /// Since the codex id is a commitment covering all the codex fields (see [`Self::codex_id`]),
+    /// the codices with the same id are semantically equal. The comparison is the same as the one
+    /// performed by the `PartialEq` implementation, and is useful for deduplication of codices
+    /// where the ids are cached.
+    pub fn eq_by_id(&self, other: &Codex) -> bool { self.codex_id() == other.codex_id() }
+
+    /// Constructs a new codex combining the verifiers of this codex with the verifiers of the
+    /// `other` codex, supporting the composition of contracts out of independently developed
+    /// modules.
+    ///
+    /// The codices must be compatible, i.e., have the same field order, VM configurations,
+    /// [`Self::input_bridge`], [`Self::absent_immutable_as_none`], [`Self::output_sum`] and
+    /// [`Self::indeterminate_code`]. The verifiers and [`Self::witness_banks`] of both codices are
+    /// united; an entry present in both codices must be the same. The rest of the codex metadata
+    /// (version, name, developer and timestamp) is taken from `self`; it can be updated after the
+    /// merge, but this changes the codex id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MergeError`] if the codices are not compatible, have conflicting entries, or the
+    /// number of the resulting verifiers exceeds the codex limit.
+    pub fn merge_verifiers(&self, other: &Codex) -> Result<Codex, MergeError> {
+        if self.field_order != other.field_order {
+            return Err(MergeError::FieldOrderMismatch);
+        }
+        if self.input_config != other.input_config
+            || self.verification_config != other.verification_config
+        {
+            return Err(MergeError::ConfigMismatch);
+        }
+        if self.input_bridge != other.input_bridge
+            || self.absent_immutable_as_none != other.absent_immutable_as_none
+            || self.output_sum != other.output_sum
+            || self.indeterminate_code != other.indeterminate_code
+        {
+            return Err(MergeError::FlagsMismatch);
+        }
+
+        let mut merged = self.clone();
+        for (call_id, site) in &other.verifiers {
+            match merged.verifiers.get(call_id) {
+                Some(existing) if existing != site => {
+                    return Err(MergeError::CallIdConflict(*call_id));
+                }
+                Some(_) => {}
+                None => {
+                    merged
+                        .verifiers
+                        .insert(*call_id, *site)
+                        .map_err(|_| MergeError::TooManyVerifiers)?;
+                }
+            }
+        }
+        for (lib_id, bank) in &other.witness_banks {
+            match merged.witness_banks.get(lib_id) {
+                Some(existing) if existing != bank => {
+                    return Err(MergeError::WitnessBankConflict(*lib_id));
+                }
+                Some(_) => {}
+                None => {
+                    merged
+                        .witness_banks
+                        .insert(*lib_id, *bank)
+                        .map_err(|_| MergeError::TooManyWitnessBanks)?;
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Tag used in computing [`Self::developer_sig_msg`].
+    pub const SIGNATURE_TAG: &'static str = "urn:ubideco:sonic:codex-signature#2026-10-15";
+
+    /// Computes the message signed by the codex developer, which is a SHA256 hash of the codex id
+    /// (see [`Self::codex_id`]) tagged with [`Self::SIGNATURE_TAG`].
+    ///
+    /// The tag separates the codex endorsements from other signatures made with the same key, such
+    /// that they can't be replayed as each other.
+    pub fn developer_sig_msg(&self) -> [u8; 32] {
+        let mut engine = Sha256::from_tag(Self::SIGNATURE_TAG);
+        engine.input_raw(self.codex_id().as_slice());
+        engine.finish()
+    }
+
+    /// Verifies the signature of the codex developer over the [`Self::developer_sig_msg`] with the
+    /// [`Self::developer_key`].
+    ///
+    /// Since the codex id commits to all the codex fields, including the developer key, the
+    /// signature confirms that the whole codex was published by the holder of the key.
+    ///
+    /// # Returns
+    ///
+    /// Whether the signature is valid; `false` if the codex has no developer key, or the key has a
+    /// scheme different from the signature one.
+    pub fn verify_developer_signature(&self, sig: &impl DeveloperSig) -> bool {
+        match self.developer_key {
+            Some(key) if key.scheme == sig.scheme() => {
+                sig.verify(&key.key, &self.developer_sig_msg())
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks whether a corpus of `operations` verifies in the same way under this codex and its
+    /// `newer` version, which is a safety tool for migrating a contract to an upgraded codex.
+    ///
+    /// Each of the operations is verified with [`Self::verify`] under both codices against the
+    /// same `memory`, which is not modified; the operations are not applied to it. All the
+    /// operations must belong to the contract with `contract_id`.
+    ///
+    /// # Returns
+    ///
+    /// Report listing the operations which verification results differ between the codices; the
+    /// upgrade is safe for the corpus if [`UpgradeReport::is_compatible`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn check_upgrade(
+        &self,
+        newer: &Codex,
+        contract_id: ContractId,
+        operations: &[Operation],
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> UpgradeReport {
+        let divergences = operations
+            .iter()
+            .enumerate()
+            .filter_map(|(index, operation)| {
+                let older = self
+                    .verify(contract_id, operation.clone(), memory, repo)
+                    .map(|_| ());
+                let newer = newer
+                    .verify(contract_id, operation.clone(), memory, repo)
+                    .map(|_| ());
+                (older != newer).then(|| UpgradeDivergence {
+                    index,
+                    opid: operation.opid(),
+                    older,
+                    newer,
+                })
+            })
+            .collect();
+        UpgradeReport { checked: operations.len(), divergences }
+    }
+
+    /// Computes the sum of the [`Self::output_sum`] component of the `outputs` values, which is
+    /// provided to the operation verification script.
+    ///
+    /// Outputs which values don't have the component (including components with index above 3)
+    /// contribute zero. Elements which are not below the field order are reduced modulo the field
+    /// order before summing.
+    ///
+    /// # Returns
+    ///
+    /// The sum in the codex field, or `None` if [`Self::output_sum`] is not set.
+    pub fn output_sum(&self, outputs: &[StateCell]) -> Option<fe256> {
+        let component = self.output_sum?;
+        let order = self.field_order;
+        let sum = outputs
+            .iter()
+            .filter_map(|cell| cell.data.get(component))
+            .map(|el| el.to_u256() % order)
+            .fold(u256::ZERO, |sum, el| {
+                // Avoids the overflow of `u256` for field orders above 2^255
+                if sum >= order - el {
+                    sum - (order - el)
+                } else {
+                    sum + el
+                }
+            });
+        Some(fe256::from(sum))
+    }
+
+    /// Returns the registers the values of which are bridged from the input VM to the operation
+    /// verification VM, as defined by [`Self::input_bridge`].
+    pub fn bridged_registers(&self) -> impl Iterator<Item = RegE> + '_ {
+        RegE::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(no, _)| self.input_bridge & (1 << no) != 0)
+            .map(|(_, reg)| reg)
+    }
+
+    /// Validates the codex against the libraries it uses, provided by a `repo`.
+    ///
+    /// Checks that all the verifier libraries are known to the `repo`, and that each of the
+    /// verifier entry points points at the beginning of some instruction within the library code.
+    ///
+    /// # Errors
+    ///
+    /// Returns with [`CodexError`] on the first verifier failing the validation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn validate(&self, repo: &impl LibRepo) -> Result<(), CodexError> {
+        self.validate_with_policy(repo, CodexPolicy::default())
+    }
+
+    /// Validates the codex in the same way as [`Self::validate`], additionally checking the limits
+    /// defined by the `policy`.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors returned by [`Self::validate`], returns
+    /// [`CodexError::TooManyLibs`] if the codex uses more libraries than allowed by
+    /// [`CodexPolicy::max_libs`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn validate_with_policy(
+        &self,
+        repo: &impl LibRepo,
+        policy: CodexPolicy,
+    ) -> Result<(), CodexError> {
+        for (call_id, site) in &self.verifiers {
+            validate_entry_point(*call_id, *site, repo)?;
+        }
+        if let Some(limit) = policy.max_libs {
+            let actual = self.lib_dependencies(repo).len();
+            if actual > limit {
+                return Err(CodexError::TooManyLibs { limit, actual });
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the set of all libraries used by the codex verifiers, including the libraries
+    /// called from them (directly or through other libraries).
+    ///
+    /// The libraries are resolved with the `repo`; libraries not known to the `repo` are included
+    /// into the returned set, but their own dependencies can't be discovered and are omitted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn lib_dependencies(&self, repo: &impl LibRepo) -> BTreeSet<LibId> {
+        lib_closure(self.verifiers.values().map(|site| site.lib_id), repo)
+    }
+
+    /// Checks that all the libraries which may be executed by the operation verifier (the
+    /// verifier library and all the libraries called from it, directly or through other
+    /// libraries) are known to the `repo`.
+    ///
+    /// This allows reporting all the missing libraries at once before the verification, which
+    /// otherwise fails with [`CallError::LibNotFound`] on the first missing library it tries to
+    /// call. Dependencies of the missing libraries can't be discovered, and thus are not checked.
+    ///
+    /// If the codex has no verifier for the operation, the check succeeds, since no library is
+    /// executed (the verification fails with [`CallError::NotFound`]).
+    ///
+    /// # Errors
+    ///
+    /// Ids of all the missing libraries, in the order of their ids.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn check_operation_libs(
+        &self,
+        operation: &Operation,
+        repo: &impl LibRepo,
+    ) -> Result<(), Vec<LibId>> {
+        let Ok(site) = self.validate_call(operation) else {
+            return Ok(());
+        };
+        let resolver = checked_resolver(repo);
+        let missing = lib_closure([site.lib_id], repo)
+            .into_iter()
+            .filter(|lib_id| resolver(*lib_id).is_none())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+        Ok(())
+    }
+
+    /// Estimates the cost of the operation verification without running the VM.
+    ///
+    /// The estimate is a heuristic and not an exact gas figure: it takes the number of
+    /// instructions in the operation verifier library and all libraries it depends on (see
+    /// [`Self::lib_dependencies`]), assumes that the verifier may run all of them once for each of
+    /// the operation inputs and outputs, plus once for the operation itself, and multiplies the
+    /// result by [`Self::INSTR_COST_ESTIMATE`]. Access conditions of the inputs are not taken into
+    /// account, since they require the knowledge of the contract memory.
+    ///
+    /// Libraries not known to the `repo` do not contribute to the estimate. If the operation
+    /// verifier is absent from the codex, the estimate is zero, since such an operation is
+    /// rejected without running any code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn estimate_cost(&self, operation: &Operation, repo: &impl LibRepo) -> u64 {
+        let Ok(site) = self.validate_call(operation) else {
+            return 0;
+        };
+        let resolver = checked_resolver(repo);
+        let instr_count = lib_closure([site.lib_id], repo)
+            .into_iter()
+            .filter_map(&resolver)
+            .map(lib_instr_count)
+            .fold(0u64, u64::saturating_add);
+        let items = operation.destructible_in.len()
+            + operation.immutable_in.len()
+            + operation.destructible_out.len()
+            + operation.immutable_out.len()
+            + 1;
+        instr_count
+            .saturating_mul(items as u64)
+            .saturating_mul(Self::INSTR_COST_ESTIMATE)
+    }
+
+    /// Counts the instructions in all the libraries used by the codex verifiers (see
+    /// [`Self::lib_dependencies`]), giving a rough metric of the codex logical size.
+    ///
+    /// Each library is counted once, regardless of the number of the verifiers using it. The
+    /// count is static: it doesn't reflect how many instructions are actually executed by the
+    /// verifiers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::LibNotFound`] with the first (in the order of the ids) library which
+    /// is not known to the `repo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn total_verifier_instructions(&self, repo: &impl LibRepo) -> Result<usize, CallError> {
+        let resolver = checked_resolver(repo);
+        self.lib_dependencies(repo)
+            .into_iter()
+            .map(|lib_id| resolver(lib_id).ok_or(CallError::LibNotFound(lib_id)))
+            .try_fold(0usize, |count, lib| Ok(count.saturating_add(lib_instr_count(lib?) as usize)))
+    }
+
     /// The main purpose of the codex is to verify the operation under the contract. This is the
     /// implementation of this verification procedure.
     ///
@@ -144,6 +736,13 @@ impl Codex {
     /// On any verification failure, the method does not proceed with further certification and
     /// instantly returns with one of [`CallError`] variants.
     ///
+    /// # Allocations
+    ///
+    /// The operation outputs are passed to the verification VM by reference. For the operations
+    /// without inputs no heap memory is allocated for the inputs: empty input collections do not
+    /// allocate, and the VM checking input access conditions is not constructed. The allocations
+    /// are measured by the `allocations` integration test.
+    ///
     /// # Panics
     ///
     /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
@@ -155,598 +754,5514 @@ impl Codex {
         memory: &impl Memory,
         repo: &impl LibRepo,
     ) -> Result<VerifiedOperation, CallError> {
-        let resolver = |lib_id: LibId| {
-            let lib = repo.get_lib(lib_id)?;
-            // We must have this verification to avoid hacking from the client libraries.
-            if lib.lib_id() != lib_id {
-                panic!(
-                    "The library returned by the `LibRepo` provided for the contract operation \
-                     verification doesn't match the requested library id. This error indicates \
-                     that the software using the consensus verification is invalid or compromised."
-                )
-            }
-            Some(lib)
-        };
+        self.verify_with_options(contract_id, operation, memory, repo, default!())
+    }
 
-        if operation.contract_id != contract_id {
-            return Err(CallError::WrongContract {
-                expected: contract_id,
-                found: operation.contract_id,
-            });
-        }
+    /// Verifies the operation in the same way as [`Self::verify`], with the verification
+    /// parameters and the additional checks defined by the `options`.
+    ///
+    /// This is the generic verification entry point: all the options can be combined, such that,
+    /// for instance, a time-locked operation of a contract with global parameters may be verified
+    /// under a strict policy with a possibility of a cancellation. The default options match the
+    /// behavior of [`Self::verify`].
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors returned by [`Self::verify`], returns the errors specific to the
+    /// provided options (see [`VerifyOptions`] fields for the details).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_options(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        options: VerifyOptions,
+    ) -> Result<VerifiedOperation, CallError> {
+        let resolver = checked_resolver(repo);
 
         // Phase 1: get inputs, verify their presence in the memory and access conditions
-        let mut vm_inputs = Vm::<aluvm::gfa::Instr<LibId>>::with(self.input_config, GfaConfig {
-            field_order: self.field_order,
-        });
-        let mut destructible_inputs = SmallVec::new();
-        for input in &operation.destructible_in {
-            // Read memory
-            let cell = memory
-                .destructible(input.addr)
-                .ok_or(CallError::NoReadOnceInput(input.addr))?;
-
-            // Verify that the lock script conditions are satisfied
-            if let Some(lock) = cell.lock {
-                // Put also token of authority into a register
-                vm_inputs.core.cx.set(RegE::E1, cell.auth.to_fe256());
-
-                // Put witness into input registers
-                for (no, reg) in [RegE::E2, RegE::E3, RegE::E4, RegE::E5]
-                    .into_iter()
-                    .enumerate()
-                {
-                    let Some(el) = input.witness.get(no as u8) else {
-                        break;
-                    };
-                    vm_inputs.core.cx.set(reg, el);
-                }
-                if vm_inputs.exec(lock, &(), resolver) == Status::Fail {
-                    // Read error code from output register
-                    return Err(CallError::Lock(vm_inputs.core.cx.get(RegE::E8)));
-                }
-                vm_inputs.reset();
-            }
+        let inputs =
+            self.load_phase(contract_id, &operation, memory, &resolver, options, default!())?;
 
-            // We have same-sized arrays, so we happily skip the result returned by the confined
-            // collection.
-            let _res = destructible_inputs.push(cell.data);
-            debug_assert!(_res.is_ok());
-        }
+        // Phase 2: Verify operation integrity
+        self.run_phase(&operation, &inputs, resolver, &CallIdSelector, options)?;
 
-        // Check that all read values are present in the memory.
-        let mut immutable_inputs = SmallVec::new();
-        for addr in &operation.immutable_in {
-            let data = memory
-                .immutable(*addr)
-                .ok_or(CallError::NoImmutableInput(*addr))?;
-            // We have same-sized arrays, so we happily skip the result returned by the confined
-            // collection.
-            let _res = immutable_inputs.push(data);
-            debug_assert!(_res.is_ok());
-        }
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
 
-        // Phase 2: Verify operation integrity
-        let entry_point = self
-            .verifiers
-            .get(&operation.call_id)
-            .ok_or(CallError::NotFound(operation.call_id))?;
-        let context = VmContext {
-            destructible_input: destructible_inputs.as_slice(),
-            immutable_input: immutable_inputs.as_slice(),
-            destructible_output: operation.destructible_out.as_slice(),
-            immutable_output: operation.immutable_out.as_slice(),
-        };
-        let mut vm_main = Vm::<Instr<LibId>>::with(self.verification_config, GfaConfig {
-            field_order: self.field_order,
-        });
-        match vm_main.exec(*entry_point, &context, resolver) {
-            Status::Ok => Ok(VerifiedOperation::new_unchecked(operation.opid(), operation)),
-            Status::Fail => {
-                if let Some(err_code) = vm_main.core.cx.get(RegE::E1) {
-                    Err(CallError::Script(err_code))
-                } else {
-                    Err(CallError::ScriptUnspecified)
-                }
-            }
-        }
+    /// Checks whether the operation is valid, i.e. whether [`Self::verify`] succeeds for it.
+    ///
+    /// The method is a shortcut discarding the verification error and the verified operation;
+    /// use [`Self::verify`] when the details of the failure are needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn is_valid(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> bool {
+        self.verify(contract_id, operation, memory, repo).is_ok()
     }
-}
 
-/// The trait, which must be implemented by a client library for a structure providing access to the
-/// valid and most recent contract state, consisting of two parts: *destructible* (also called
-/// *read-once*, or *owned*) and *immutable* (also called *read-only*, *append-only* or *global*).
-pub trait Memory {
-    /// Read a destructible memory cell created by a specific operation read-once output, which is
-    /// defined as a part of [`Operation::destructible`].
-    fn destructible(&self, addr: CellAddr) -> Option<StateCell>;
-    /// Read an immutable memory cell created by a specific operation immutable output, which is
-    /// defined as a part of [`Operation::immutable`].
-    fn immutable(&self, addr: CellAddr) -> Option<StateValue>;
-}
+    /// Verifies the operation in the same way as [`Self::verify`], additionally performing
+    /// the checks enabled by the `policy`.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors returned by [`Self::verify`], returns the errors specific to the
+    /// checks enabled by the `policy` (see [`VerifyPolicy`] fields for the details).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_policy(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        policy: VerifyPolicy,
+    ) -> Result<VerifiedOperation, CallError> {
+        let options = VerifyOptions { policy, ..default!() };
+        self.verify_with_options(contract_id, operation, memory, repo, options)
+    }
 
-/// The trait providing access to all the VM code libraries used by the contract, in both operation
-/// verification or state access conditions.
-pub trait LibRepo {
-    /// Get a specific library with the provided id.
+    /// Resolves the entry point of the verifier for the operation [`Operation::call_id`], allowing
+    /// to check the routing of the operation before running its verification.
     ///
-    /// If the library is not known and this method returns `None`, but the library is called by the
-    /// operation verification or state access script, the verification will fail with
-    /// [`CallError::Script`].
-    fn get_lib(&self, lib_id: LibId) -> Option<&Lib>;
-}
+    /// This is the verifier selection used by [`Self::verify`] (see [`CallIdSelector`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::NotFound`] if the codex has no verifier for the operation call id.
+    pub fn validate_call(&self, operation: &Operation) -> Result<LibSite, CallError> {
+        self.verifiers
+            .get(&operation.call_id)
+            .copied()
+            .ok_or(CallError::NotFound(operation.call_id))
+    }
 
-/// Contract operation verification errors returned by [`Codex::verify`].
-///
-/// The name of the error type is chose so since the operation "calls" to a contract method, and the
-/// codex verification verifies the integrity of the call.
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
-#[display(doc_comments)]
-pub enum CallError {
-    /// operation doesn't belong to the currecnt contract.
-    #[cfg_attr(
-        feature = "baid64",
-        display = "operation doesn't belong to the current contract {expected} (operation \
-                   contract is {found})."
-    )]
-    #[cfg_attr(
-        not(feature = "baid64"),
-        display = "operation doesn't belong to the current contract."
-    )]
-    WrongContract {
-        /// Expected contract id.
-        expected: ContractId,
-        /// The actual contract id of the operation.
-        found: ContractId,
-    },
+    /// Verifies the operation in the same way as [`Self::verify`], performing all the additional
+    /// checks of [`VerifyPolicy::strict`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::verify_with_policy`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    #[inline]
+    pub fn verify_strict(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        self.verify_with_policy(contract_id, operation, memory, repo, VerifyPolicy::strict())
+    }
 
-    /// operation verifier {0} is not present in the codex.
-    NotFound(CallId),
+    /// Performs the structural checks of the operation enabled by the `policy`, which do not
+    /// require running the VM or accessing the contract memory.
+    ///
+    /// The checks are performed by [`Self::verify_with_policy`] before the rest of the
+    /// verification, and can be also used for a cheap rejection of malformed operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::EmptyOperation`] if [`VerifyPolicy::reject_empty`] is set and the
+    /// operation has neither destructible inputs nor outputs, and [`CallError::UnexpectedBurn`] if
+    /// [`VerifyPolicy::burn_call`] is set and the operation is a burn using a different call id.
+    pub fn validate_operation(
+        &self,
+        operation: &Operation,
+        policy: VerifyPolicy,
+    ) -> Result<(), CallError> {
+        if policy.reject_non_field_elements {
+            operation.validate_field_membership(self.field_order)?;
+        }
+        if policy.reject_empty
+            && operation.destructible_in.is_empty()
+            && operation.destructible_out.is_empty()
+            && operation.immutable_out.is_empty()
+        {
+            return Err(CallError::EmptyOperation);
+        }
+        if let Some(burn_call) = policy.burn_call {
+            if operation.is_burn() && operation.call_id != burn_call {
+                return Err(CallError::UnexpectedBurn(operation.call_id));
+            }
+        }
+        Ok(())
+    }
 
-    /// operation references destructible memory cell which was not defined.
-    #[cfg_attr(
-        feature = "baid64",
-        display = "operation references destructible memory cell {0} which was not defined."
-    )]
-    #[cfg_attr(
-        not(feature = "baid64"),
-        display = "operation references destructible memory cell {0:?} which was not defined."
-    )]
-    NoReadOnceInput(CellAddr),
+    /// Verifies the genesis operation, which, by definition, has no inputs.
+    ///
+    /// Since genesis doesn't read any memory cells, the verification skips the first phase of
+    /// [`Self::verify`] (reading of the inputs and checking their access conditions), and runs
+    /// only the operation verification script over empty inputs. Thus, no [`Memory`] is required.
+    ///
+    /// The operation is usually constructed with [`crate::Genesis::to_operation`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::NotGenesis`] if the operation has any destructible or immutable
+    /// inputs. Other errors are the same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_genesis(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        let resolver = checked_resolver(repo);
 
-    /// operation references immutable memory cell which was not defined.
-    #[cfg_attr(
-        feature = "baid64",
-        display = "operation references immutable memory cell {0} which was not defined."
-    )]
-    #[cfg_attr(
-        not(feature = "baid64"),
-        display = "operation references immutable memory cell {0:?} which was not defined."
-    )]
-    /// operation references immutable memory cell {0} which was not defined.
-    NoImmutableInput(CellAddr),
+        self.check_contract(contract_id, &operation)?;
+        if !operation.destructible_in.is_empty() || !operation.immutable_in.is_empty() {
+            return Err(CallError::NotGenesis);
+        }
 
-    /// operation input access conditions are unsatisfied.
-    Lock(Option<fe256>),
+        self.run_phase(&operation, &Inputs::default(), resolver, &CallIdSelector, default!())?;
 
-    /// verification script failure with status code {0}.
-    Script(fe256),
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
 
-    /// verification script failure (no status code is returned from the verification script).
-    ScriptUnspecified,
-}
+    /// Verifies the operation against a memory of an ultra-light client, holding only commitments
+    /// to the immutable memory cells (see [`CommitmentMemory`] for the details).
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_committed(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl CommitmentMemory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        self.verify(contract_id, operation, &CommittedMemory(memory), repo)
+    }
 
-/// Unique codex identifier - a commitment to all the [`Codex`] data.
-#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
-#[wrapper(AsSlice, Deref, BorrowSlice, Hex, Index, RangeOps)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
-#[strict_type(lib = LIB_NAME_ULTRASONIC)]
-#[cfg_attr(
-    all(feature = "serde", not(feature = "baid64")),
-    derive(Serialize, Deserialize),
-    serde(transparent)
-)]
-pub struct CodexId(
-    #[from]
-    #[from([u8; 32])]
-    Bytes32,
-);
+    /// Verifies the operation in the same way as [`Self::verify`], using the `cache` to avoid
+    /// repeated verification of the same operation.
+    ///
+    /// The `memory_root` must change each time any part of the contract `memory` which may be
+    /// read by the operation changes; see [`VerifyCache`] for the details. A [`StateRoot`] of the
+    /// memory (see [`Memory::root`]) satisfies this requirement.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`]. Only the script failures are cached; other errors,
+    /// which depend on the environment, are re-checked on each call (see [`VerifyCache`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_cached(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        memory_root: Bytes32,
+        cache: &VerifyCache,
+    ) -> Result<VerifiedOperation, CallError> {
+        let codex_id = self.codex_id();
+        let opid = operation.opid();
+        let result = match cache.get(codex_id, contract_id, opid, memory_root) {
+            Some(result) => result,
+            None => {
+                let result = self
+                    .verify(contract_id, operation.clone(), memory, repo)
+                    .map(|_| ());
+                cache.insert(codex_id, contract_id, opid, memory_root, result);
+                result
+            }
+        };
+        result.map(|_| VerifiedOperation::new_unchecked(opid, operation))
+    }
 
-#[cfg(all(feature = "serde", feature = "baid64"))]
-impl_serde_str_bin_wrapper!(CodexId, Bytes32);
+    /// Verifies the operation in the same way as [`Self::verify`], providing the lock scripts of
+    /// the destructible inputs with a height/time value, allowing time-locked spending conditions.
+    ///
+    /// The `time` is put into the [`Self::TIME_REGISTER`] (`E7`) of the input VM before running
+    /// each of the lock scripts, encoded as a field element holding the `u64` value. Whether the
+    /// value is a block height or a timestamp is defined by the contract consensus layer (see
+    /// [`crate::ContractMeta::consensus`]); the host must derive it deterministically from the
+    /// consensus data (for instance, the height or the median time past of the block including the
+    /// operation), such that all hosts verifying the contract arrive at the same result.
+    ///
+    /// With [`Self::verify`], the register is left unset, which can be detected by the lock scripts
+    /// with the `test` instruction.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_timed(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        time: u64,
+    ) -> Result<VerifiedOperation, CallError> {
+        let options = VerifyOptions { time: Some(time), ..default!() };
+        self.verify_with_options(contract_id, operation, memory, repo, options)
+    }
 
-impl From<Sha256> for CodexId {
-    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
-}
+    /// Re-validates a historic operation against the contract state as of the `height`, at which
+    /// the operation was included.
+    ///
+    /// The operation inputs are resolved from the [`HeightScopedMemory`] over the historic
+    /// `memory`, such that the cells created after the `height` are not visible, and the cells
+    /// spent at the `height` or later are visible as unspent. The `height` is also provided to the
+    /// lock scripts in the same way as with [`Self::verify_timed`], such that time-locked spending
+    /// conditions are evaluated against the historic height.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_at_height(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl HistoricMemory,
+        repo: &impl LibRepo,
+        height: u64,
+    ) -> Result<VerifiedOperation, CallError> {
+        let memory = HeightScopedMemory::new(memory, height);
+        self.verify_timed(contract_id, operation, &memory, repo, height)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], providing the verifiers with
+    /// the contract-global parameters (see [`VmContext::globals`]).
+    ///
+    /// The `globals` must be a part of the committed contract definition (for instance, be
+    /// provided by the contract genesis), and all hosts verifying the contract must use the same
+    /// globals for all its operations; otherwise the verification results will diverge. The codex
+    /// doesn't check the origin of the globals.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_globals(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        globals: &[StateData],
+    ) -> Result<VerifiedOperation, CallError> {
+        let options = VerifyOptions { globals, ..default!() };
+        self.verify_with_options(contract_id, operation, memory, repo, options)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], using a custom `selector` to
+    /// choose the verifier for the operation.
+    ///
+    /// This enables polymorphic state transitions, where the verifier is chosen basing on the
+    /// operation content (for instance, its output data) rather than on the fixed
+    /// [`Operation::call_id`]. See [`VerifierSelector`] for the determinism requirements.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::NotFound`] if the selector doesn't provide a verifier. Other errors are
+    /// the same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_selector(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        selector: &impl VerifierSelector,
+    ) -> Result<VerifiedOperation, CallError> {
+        let resolver = checked_resolver(repo);
+
+        let inputs =
+            self.load_phase(contract_id, &operation, memory, &resolver, default!(), default!())?;
+        self.run_phase(&operation, &inputs, resolver, selector, default!())?;
+
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], running the lock scripts of the
+    /// destructible inputs in parallel.
+    ///
+    /// Since the lock scripts are independent of each other, they can be evaluated concurrently,
+    /// speeding up the verification of operations with many locked inputs. The results are
+    /// collected in the order of the inputs, such that the method returns exactly the same result
+    /// as [`Self::verify`] regardless of the thread scheduling. The operation verification script
+    /// still runs in a single thread, since it accesses the whole operation context.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    #[cfg(feature = "rayon")]
+    pub fn verify_parallel(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &(impl LibRepo + Sync),
+    ) -> Result<VerifiedOperation, CallError> {
+        let resolver = checked_resolver(repo);
+
+        self.check_contract(contract_id, &operation)?;
+        let inputs = self.load_inputs_parallel(&operation, memory, &resolver)?;
+        self.run_phase(&operation, &inputs, resolver, &CallIdSelector, default!())?;
+
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], checking the host
+    /// `preconditions` before running any VM.
+    ///
+    /// The pre-conditions are checked in the order they are provided, and the verification fails
+    /// on the first unsatisfied one. They are not a part of the consensus (see [`PreCondition`]).
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors returned by [`Self::verify`], returns the errors of the failing
+    /// pre-condition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_preconditions(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        preconditions: &[&dyn PreCondition],
+    ) -> Result<VerifiedOperation, CallError> {
+        self.check_contract(contract_id, &operation)?;
+        for precondition in preconditions {
+            precondition.check(self, &operation)?;
+        }
+        self.verify(contract_id, operation, memory, repo)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], checking the host
+    /// `postconditions` after a successful run of the operation verification script.
+    ///
+    /// The post-conditions are checked in the order they are provided, and the verification
+    /// fails on the first unsatisfied one. They are not a part of the consensus (see
+    /// [`PostCondition`]).
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors returned by [`Self::verify`], returns the errors of the failing
+    /// post-condition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_postconditions(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        postconditions: &[&dyn PostCondition],
+    ) -> Result<VerifiedOperation, CallError> {
+        let verified = self.verify(contract_id, operation, memory, repo)?;
+        for postcondition in postconditions {
+            postcondition.check(self, verified.as_operation(), repo)?;
+        }
+        Ok(verified)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], additionally returning the
+    /// witnesses which were used to satisfy the access conditions (locks) of the destructible
+    /// inputs.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns the verified operation together with the list of the addresses of the
+    /// locked destructible inputs and the witnesses accepted by their lock scripts, in the order
+    /// of [`Operation::destructible_in`]. Inputs without a lock are not included in the list. The
+    /// list can be used to build an authorization audit trail.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_detailed(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<(VerifiedOperation, Vec<(CellAddr, StateValue)>), CallError> {
+        let resolver = checked_resolver(repo);
+
+        let inputs =
+            self.load_phase(contract_id, &operation, memory, &resolver, default!(), default!())?;
+        self.run_phase(&operation, &inputs, resolver, &CallIdSelector, default!())?;
+
+        Ok((VerifiedOperation::new_unchecked(operation.opid(), operation), inputs.witnesses))
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], using the `witnesses` supplied
+    /// separately from the operation to satisfy the access conditions of the destructible inputs.
+    ///
+    /// This supports segregated-witness designs, where the operation body travels separately
+    /// from its witnesses. The witness for each of the destructible inputs is taken from the
+    /// `witnesses` by the input index (in the order of [`Operation::destructible_in`]), and the
+    /// [`Input::witness`] values of the operation are ignored. Since the witnesses do not
+    /// contribute to the operation id, they should be committed to by other means (for instance,
+    /// by the consensus layer).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::WitnessCountMismatch`] if the number of the `witnesses` doesn't match
+    /// the number of the destructible inputs. Other errors are the same as for the
+    /// [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_witnesses(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        witnesses: &[StateValue],
+    ) -> Result<VerifiedOperation, CallError> {
+        let options = VerifyOptions { witnesses: Some(witnesses), ..default!() };
+        self.verify_with_options(contract_id, operation, memory, repo, options)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], using the memory cells
+    /// gathered by the caller in advance instead of reading them from a [`Memory`].
+    ///
+    /// The `read_once` cells must be listed in the order of [`Operation::destructible_in`], and
+    /// the `immutable` cells in the order of [`Operation::immutable_in`]. The cells are trusted to
+    /// be the ones defined at the input addresses; the lock scripts of the destructible inputs
+    /// and the operation verification script are run as usual. If the operation references the
+    /// same address more than once, all the cells provided for it must be the same, as they are
+    /// when read from a [`Memory`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::PreparedInputMismatch`] if the number of the provided cells doesn't
+    /// match the number of the operation inputs of the same kind, and
+    /// [`CallError::PreparedInputConflict`] if different cells are provided for the same address.
+    /// Other errors are the same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_prepared(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        read_once: &[StateCell],
+        immutable: &[StateData],
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        for (expected, found) in [
+            (operation.destructible_in.len(), read_once.len()),
+            (operation.immutable_in.len(), immutable.len()),
+        ] {
+            if expected != found {
+                return Err(CallError::PreparedInputMismatch {
+                    expected: expected as u16,
+                    found: found.try_into().unwrap_or(u16::MAX),
+                });
+            }
+        }
+        let memory = PreparedMemory {
+            read_once: PreparedMemory::collect(
+                operation.destructible_in.iter().map(|input| input.addr),
+                read_once,
+            )?,
+            immutable: PreparedMemory::collect(operation.immutable_in.iter().copied(), immutable)?,
+        };
+        self.verify(contract_id, operation, &memory, repo)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], additionally returning the
+    /// changes to the contract memory produced by the operation (see [`StateEffects`]).
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_effects(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<(VerifiedOperation, StateEffects), CallError> {
+        let verified = self.verify(contract_id, operation, memory, repo)?;
+        let effects = verified.effects();
+        Ok((verified, effects))
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], additionally returning the
+    /// values which the operation verification script has left in the
+    /// [`Self::RETURNED_OUTPUT_REGISTERS`].
+    ///
+    /// This allows a verifier to provide the host with derived values (like balances or counters)
+    /// for indexing, such that the host doesn't need to re-implement the contract logic.
+    ///
+    /// # Determinism
+    ///
+    /// The returned values are not committed to by the operation and are not a part of the
+    /// consensus: other hosts verifying the same operation with [`Self::verify`] never see them.
+    /// Thus, the verifiers must compute them only from the operation and its inputs, such that all
+    /// hosts indexing the contract derive the same values; the values must not be used to decide
+    /// the operation validity.
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_outputs_returned(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<(VerifiedOperation, Vec<fe256>), CallError> {
+        let resolver = checked_resolver(repo);
+
+        let inputs =
+            self.load_phase(contract_id, &operation, memory, &resolver, default!(), default!())?;
+        let vm_main = self.run_phase(&operation, &inputs, resolver, &CallIdSelector, default!())?;
+        let bridged = self.bridged_registers().collect::<Vec<_>>();
+        let outputs = Self::RETURNED_OUTPUT_REGISTERS
+            .iter()
+            .filter(|reg| !bridged.contains(reg))
+            .map_while(|reg| vm_main.core.cx.get(*reg))
+            .collect();
+
+        Ok((VerifiedOperation::new_unchecked(operation.opid(), operation), outputs))
+    }
+
+    /// Localizes the failure of a bundle of operations, returning the index of the first operation
+    /// in `operations` which fails the verification.
+    ///
+    /// The operations are verified in their order with [`Self::verify`] against the `memory`
+    /// overlaid with the effects (see [`StateEffects`]) of the already verified operations of the
+    /// bundle, such that an operation may spend the outputs of the preceding ones. Since the
+    /// effects are applied incrementally, the returned index defines the minimal failing prefix of
+    /// the bundle: all the operations before it pass the verification. The `memory` itself is not
+    /// modified.
+    ///
+    /// This is a debugging aid; the verification error of the failing operation may be obtained
+    /// by verifying it against the same prefix of the bundle.
+    ///
+    /// # Returns
+    ///
+    /// The index of the first failing operation, or the number of the operations if all of them
+    /// pass the verification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn bisect_bundle(
+        &self,
+        contract_id: ContractId,
+        operations: &[Operation],
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> usize {
+        let mut overlay = OverlayMemory::new(memory);
+        for (no, operation) in operations.iter().enumerate() {
+            match self.verify(contract_id, operation.clone(), &overlay, repo) {
+                Ok(verified) => overlay.apply(&verified),
+                Err(_) => return no,
+            }
+        }
+        operations.len()
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], allowing to cancel the
+    /// verification from another thread by setting the `cancel` flag.
+    ///
+    /// The flag is checked before reading each of the destructible inputs (and running its lock
+    /// script) and before running the operation verification script. The cancellation is
+    /// cooperative: a running script is not interrupted, but no further verification is performed
+    /// once the flag is seen set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::Cancelled`] if the verification was cancelled. Other errors are the
+    /// same as for the [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_cancellable(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        cancel: &AtomicBool,
+    ) -> Result<VerifiedOperation, CallError> {
+        let options = VerifyOptions { cancel: Some(cancel), ..default!() };
+        self.verify_with_options(contract_id, operation, memory, repo, options)
+    }
+
+    /// Runs the operation verification script over the actual operation inputs read from the
+    /// memory, without checking the access conditions (locks) of the destructible inputs.
+    ///
+    /// # Not for consensus
+    ///
+    /// This method bypasses the spending authorization and thus **must not be used for the
+    /// consensus verification**, since it accepts operations spending memory cells without
+    /// a proof of the ownership. It is intended for simulation and contract analysis tools, which
+    /// assume that the authorization is handled elsewhere. Since the lock scripts are not run, no
+    /// values are bridged from the input VM (see [`Self::input_bridge`]).
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`], except [`CallError::Lock`], which is never returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_unlocked(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        let resolver = checked_resolver(repo);
+
+        let locks = LoadOptions { skip_locks: true, ..default!() };
+        let inputs =
+            self.load_phase(contract_id, &operation, memory, &resolver, default!(), locks)?;
+        self.run_phase(&operation, &inputs, resolver, &CallIdSelector, default!())?;
+
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
+
+    /// Runs the operation verification script in the same way as [`Self::verify`], recording the
+    /// results of the lock scripts of the destructible inputs instead of enforcing them.
+    ///
+    /// # Not for consensus
+    ///
+    /// An unsatisfied lock doesn't abort the verification, thus this method **must not be used
+    /// for the consensus verification**. It is intended for analysis tools answering whether the
+    /// memory cells would be spendable with the provided witnesses. The values bridged from the
+    /// input VM (see [`Self::input_bridge`]) are taken from the last satisfied lock script.
+    ///
+    /// # Returns
+    ///
+    /// The result of the verification, ignoring the lock failures, together with the results of
+    /// the lock scripts, listed in the order of the locked destructible inputs. The lock results
+    /// are empty if the verification fails before the inputs are read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_report_locks(
+        &self,
+        contract_id: ContractId,
+        operation: &Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> (Result<(), CallError>, Vec<LockOutcome>) {
+        let resolver = checked_resolver(repo);
+
+        let locks = LoadOptions { record_locks: true, ..default!() };
+        let mut inputs =
+            match self.load_phase(contract_id, operation, memory, &resolver, default!(), locks) {
+                Ok(inputs) => inputs,
+                Err(err) => return (Err(err), none!()),
+            };
+        let locks = std::mem::take(&mut inputs.locks);
+        let res = self
+            .run_phase(operation, &inputs, resolver, &CallIdSelector, default!())
+            .map(|_| ());
+        (res, locks)
+    }
+
+    /// Checks the structural validity of an operation which may not yet have all the witnesses
+    /// attached, as in the interactive construction of an operation by multiple parties.
+    ///
+    /// The operation is verified in the same way as [`Self::verify`], except that unsatisfied
+    /// lock scripts of the destructible inputs without a witness (i.e. having
+    /// [`StateValue::None`] witness) are treated as pending instead of failing the verification.
+    /// The locks of the inputs which have a witness attached are enforced, and the operation
+    /// verification script is run over all the inputs and outputs.
+    ///
+    /// # Not for consensus
+    ///
+    /// Pending locks don't abort the verification, thus this method **must not be used for the
+    /// consensus verification**; once all the witnesses are attached, the operation must be
+    /// verified with [`Self::verify`]. The values bridged from the input VM (see
+    /// [`Self::input_bridge`]) are taken from the last satisfied lock script.
+    ///
+    /// # Returns
+    ///
+    /// Addresses of the destructible inputs still needing witnesses, in the order of
+    /// [`Operation::destructible_in`].
+    ///
+    /// # Errors
+    ///
+    /// The same as for the [`Self::verify`], except [`CallError::Lock`] failures of the inputs
+    /// without a witness.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_structure(
+        &self,
+        contract_id: ContractId,
+        operation: &Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<Vec<CellAddr>, CallError> {
+        let (res, locks) = self.verify_report_locks(contract_id, operation, memory, repo);
+
+        let mut pending = vec![];
+        for (addr, lock_res) in locks {
+            let no_witness = operation
+                .destructible_in
+                .iter()
+                .any(|input| input.addr == addr && input.witness == StateValue::None);
+            match lock_res {
+                Err(CallError::Lock(_)) if no_witness => pending.push(addr),
+                lock_res => lock_res?,
+            }
+        }
+        res?;
+
+        Ok(pending)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], but evaluating the lock scripts
+    /// of all the destructible inputs before failing, such that all the authorization failures
+    /// are reported at once.
+    ///
+    /// Unlike [`Self::verify`], which fails fast on the first unsatisfied lock with
+    /// [`CallError::Lock`], this method collects the addresses of all the inputs with unsatisfied
+    /// locks, together with the error codes returned by their lock scripts. The operation
+    /// verification script is run only if all the locks are satisfied; thus the method accepts
+    /// exactly the same operations as [`Self::verify`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CollectedError::Locks`] with all the unsatisfied locks, in the order of
+    /// [`Operation::destructible_in`]. Other errors are the same as for the [`Self::verify`],
+    /// wrapped into [`CollectedError::Call`]; errors of the lock scripts other than
+    /// [`CallError::Lock`] (like an absent lock library) are returned immediately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_collect_locks(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CollectedError> {
+        let (res, locks) = self.verify_report_locks(contract_id, &operation, memory, repo);
+
+        let mut failed = vec![];
+        for (addr, lock_res) in locks {
+            match lock_res {
+                Ok(()) => {}
+                Err(CallError::Lock(code)) => failed.push((addr, code)),
+                Err(err) => return Err(CollectedError::Call(err)),
+            }
+        }
+        if !failed.is_empty() {
+            return Err(CollectedError::Locks(failed));
+        }
+        res?;
+
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], calling `on_lock` with the
+    /// result of each of the destructible input lock scripts as soon as it is evaluated.
+    ///
+    /// The callback is called in the order of the locked destructible inputs, and allows to report
+    /// the verification progress of operations with many inputs. Since the verification stops on
+    /// the first unsatisfied lock, the callback receives at most one failed result, which is the
+    /// last one.
+    ///
+    /// # Errors
+    ///
+    /// The same as for [`Self::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_streaming_locks(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        on_lock: impl FnMut(CellAddr, Result<(), CallError>),
+    ) -> Result<VerifiedOperation, CallError> {
+        let resolver = checked_resolver(repo);
+        let on_lock = RefCell::new(on_lock);
+        let on_lock =
+            |addr: CellAddr, res: Result<(), CallError>| (on_lock.borrow_mut())(addr, res);
+
+        let locks = LoadOptions { on_lock: Some(LockHook(&on_lock)), ..default!() };
+        let inputs =
+            self.load_phase(contract_id, &operation, memory, &resolver, default!(), locks)?;
+        self.run_phase(&operation, &inputs, resolver, &CallIdSelector, default!())?;
+
+        Ok(VerifiedOperation::new_unchecked(operation.opid(), operation))
+    }
+
+    /// Runs the lock script of a memory `cell` (usually, a destructible output of an operation
+    /// being constructed) with a `candidate_witness`, checking that the cell can be spent later.
+    ///
+    /// The lock script is run in the same way as when the cell is accessed as a destructible
+    /// input during the verification, except that no height/time value is provided (see
+    /// [`Self::verify_timed`]). Cells without a lock are always spendable.
+    ///
+    /// # Not for consensus
+    ///
+    /// The method helps contract authors to avoid creating unspendable outputs, and **must not be
+    /// used for the consensus verification**.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::Lock`] if the lock script fails, and [`CallError::FieldMembership`]
+    /// if the witness element doesn't fit the codex field. Since the cell is not yet spent by any
+    /// operation, the latter error references the witness of the input number zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn dry_run_output_lock(
+        &self,
+        cell: &StateCell,
+        candidate_witness: StateValue,
+        repo: &impl LibRepo,
+    ) -> Result<(), CallError> {
+        let Some(lock) = cell.lock else {
+            return Ok(());
+        };
+        let resolver = checked_resolver(repo);
+        let input = Input { addr: CellAddr::from([0u8; 34]), witness: candidate_witness };
+        self.exec_lock(&mut self.input_vm(), 0, &input, cell, lock, &resolver, None)
+    }
+
+    /// Checks offline whether a memory `cell` (for instance, an output of a prior operation) can
+    /// be spent with a candidate `witness`, without accessing the contract memory.
+    ///
+    /// The witness elements are loaded into the registers and the cell lock script is run in the
+    /// same way as with [`Self::dry_run_output_lock`]. Cells without a lock are always spendable.
+    ///
+    /// # Not for consensus
+    ///
+    /// The method is a spendability check for wallets, and **must not be used for the consensus
+    /// verification**.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::WitnessTooLong`] if the `witness` has more than four elements, and
+    /// other errors in the same way as [`Self::dry_run_output_lock`]: [`CallError::Lock`] with the
+    /// error code set by the failed lock script, or [`CallError::FieldMembership`] if a witness
+    /// element doesn't fit the codex field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn test_witness(
+        &self,
+        cell: &StateCell,
+        witness: &[fe256],
+        repo: &impl LibRepo,
+    ) -> Result<(), CallError> {
+        if witness.len() > 4 {
+            return Err(CallError::WitnessTooLong(witness.len()));
+        }
+        let witness = witness.iter().copied().collect::<StateValue>();
+        self.dry_run_output_lock(cell, witness, repo)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], additionally metering the
+    /// resources consumed by the verification script.
+    ///
+    /// The cycles spent on each of the libraries are attributed by tracking which library the VM
+    /// is executing: each time the VM switches to a library code (by calling it or returning to
+    /// it), the complexity accumulated since the previous switch is attributed to the previously
+    /// executed library.
+    ///
+    /// Only the execution of the operation verification script is metered; the input access
+    /// conditions are checked by a separate VM and do not contribute to the report.
+    ///
+    /// The report also lists the immutable inputs which were actually loaded by the verification
+    /// script (see [`VerificationReport::consulted_immutable`]), allowing to minimize the set of
+    /// the immutable memory cells provided in the proofs to light clients.
+    /// Together with the verification failure, the report records the results of the lock scripts
+    /// of the destructible inputs (see [`VerificationReport::locks`]), such that the reports
+    /// cover the divergences in the input access conditions as well.
+    ///
+    /// The operation is verified with the `options` in the same way as with
+    /// [`Self::verify_with_options`], such that the report covers the verification with the same
+    /// parameters (like the contract-global parameters) as used by the host for the consensus.
+    ///
+    /// # Returns
+    ///
+    /// The verification result (the same as returned by [`Self::verify`]), together with the
+    /// report on the consumed resources. The report is returned even if the verification fails,
+    /// and then covers the part of the verification performed up to the failure.
+    pub fn verify_metered(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        options: VerifyOptions,
+    ) -> (Result<VerifiedOperation, CallError>, VerificationReport) {
+        let resolver = checked_resolver(repo);
+        let meter = Meter::default();
+        let lock_results = RefCell::new(Vec::new());
+        let on_lock = |addr: CellAddr, res: Result<(), CallError>| {
+            lock_results.borrow_mut().push((addr, res))
+        };
+
+        let locks = LoadOptions { on_lock: Some(LockHook(&on_lock)), ..default!() };
+        let res = self
+            .load_phase(contract_id, &operation, memory, &resolver, options, locks)
+            .and_then(|inputs| {
+                check_cancelled(options.cancel)?;
+                let options = RunOptions { meter: Some(&meter), ..RunOptions::from(options) };
+                self.verify_main(&operation, &inputs, resolver, &CallIdSelector, options)
+            });
+
+        let mut report = meter.into_report(&operation);
+        if report.executed_verifier.is_some() {
+            report.deprecated_call = options.deprecated.contains(&operation.call_id);
+        }
+        report.failure = res.as_ref().err().copied();
+        report.locks = lock_results.into_inner();
+        (res.map(|_| VerifiedOperation::new_unchecked(operation.opid(), operation)), report)
+    }
+
+    /// Verifies the operation in the same way as [`Self::verify`], additionally profiling the
+    /// execution of the verification script.
+    ///
+    /// The profile records the cycles spent on each of the executed instructions and routines,
+    /// and can be dumped in the folded-stack format with [`Profile::to_folded`] for building a
+    /// flamegraph. Only the execution of the operation verification script is profiled; the input
+    /// access conditions are checked by a separate VM and do not contribute to the profile.
+    ///
+    /// Profiling significantly slows down the verification (see [`Profiler`]), and must be used
+    /// only for the verifier performance tuning. The operation is verified with the `options` in
+    /// the same way as with [`Self::verify_with_options`].
+    ///
+    /// # Returns
+    ///
+    /// The verification result (the same as returned by [`Self::verify`]), together with the
+    /// profile. The profile is returned even if the verification fails, and then covers the part
+    /// of the verification performed up to the failure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    #[cfg(feature = "profiling")]
+    pub fn verify_profile(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        options: VerifyOptions,
+    ) -> (Result<VerifiedOperation, CallError>, Profile) {
+        let resolver = checked_resolver(repo);
+        let profiler = Profiler::new();
+
+        let res = self
+            .load_phase(contract_id, &operation, memory, &resolver, options, default!())
+            .and_then(|inputs| {
+                check_cancelled(options.cancel)?;
+                let options = RunOptions { profiler: Some(&profiler), ..RunOptions::from(options) };
+                self.verify_main(&operation, &inputs, resolver, &CallIdSelector, options)
+            })
+            .map(|_| VerifiedOperation::new_unchecked(operation.opid(), operation));
+        (res, profiler.into_profile())
+    }
+
+    fn check_contract(
+        &self,
+        contract_id: ContractId,
+        operation: &Operation,
+    ) -> Result<(), CallError> {
+        if operation.contract_id != contract_id {
+            return Err(CallError::WrongContract {
+                expected: contract_id,
+                found: operation.contract_id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads the operation inputs from the memory, verifying the satisfaction of the access
+    /// conditions of the destructible memory cells (unless [`LoadOptions::skip_locks`] is set).
+    fn load_inputs<'r>(
+        &self,
+        operation: &Operation,
+        memory: &impl Memory,
+        resolver: impl Fn(LibId) -> Option<&'r Lib>,
+        options: LoadOptions,
+    ) -> Result<Inputs, CallError> {
+        let LoadOptions {
+            skip_locks,
+            record_locks,
+            policy,
+            cancel,
+            time,
+            witnesses: detached,
+            on_lock,
+        } = options;
+        // The VM is constructed only when having a locked input; so the operations without such
+        // inputs (including operations without inputs at all) do not allocate memory for it.
+        let mut vm_inputs = None;
+        let mut destructible_inputs = Vec::with_capacity(operation.destructible_in.len());
+        let mut bridge = Vec::new();
+        let mut witnesses = Vec::new();
+        let mut locks = Vec::new();
+        for (no, input) in operation.destructible_in.iter().enumerate() {
+            check_cancelled(cancel)?;
+            // See `Codex::verify_with_witnesses` docs
+            let input = &match detached {
+                Some(witnesses) => Input { addr: input.addr, witness: witnesses[no] },
+                None => *input,
+            };
+
+            // Read memory
+            let cell = memory
+                .try_destructible(input.addr)?
+                .ok_or(CallError::NoReadOnceInput(input.addr))?;
+
+            if policy.reject_unexpected_witness
+                && cell.lock.is_none()
+                && input.witness != StateValue::None
+            {
+                return Err(CallError::UnexpectedWitness(input.addr));
+            }
+
+            // Verify that the lock script conditions are satisfied
+            if let Some(lock) = cell.lock.filter(|_| !skip_locks) {
+                let vm_inputs = vm_inputs.get_or_insert_with(|| self.input_vm());
+
+                let res = self.exec_lock(vm_inputs, no as u16, input, &cell, lock, &resolver, time);
+                if res.is_ok() {
+                    // Values are taken from the last lock script, see `Codex::input_bridge` docs
+                    bridge = self
+                        .bridged_registers()
+                        .filter_map(|reg| vm_inputs.core.cx.get(reg).map(|val| (reg, val)))
+                        .collect();
+                    witnesses.push((input.addr, input.witness));
+                }
+                vm_inputs.reset();
+                if let Some(LockHook(on_lock)) = on_lock {
+                    on_lock(input.addr, res);
+                }
+                if record_locks {
+                    locks.push((input.addr, res));
+                } else {
+                    res?;
+                }
+            }
+
+            destructible_inputs.push(cell.data);
+        }
+
+        let immutable_inputs = self.load_immutable(operation, memory)?;
+
+        if policy.reject_immutable_redefinition {
+            let opid = operation.opid();
+            for pos in 0..operation.immutable_out.len() as u16 {
+                let addr = CellAddr::new(opid, pos);
+                if memory.try_immutable(addr)?.is_some() {
+                    return Err(CallError::ImmutableRedefined(addr));
+                }
+            }
+        }
+
+        Ok(Inputs {
+            // There is exactly one value per operation input, so the bounds are the same
+            destructible: SmallVec::from_checked(destructible_inputs),
+            immutable: immutable_inputs,
+            bridge,
+            witnesses,
+            locks,
+        })
+    }
+
+    /// Reads the operation inputs in the same way as [`Self::load_inputs`] with the default
+    /// options, running the lock scripts of the destructible inputs in parallel.
+    ///
+    /// The memory is read sequentially up to the first absent destructible input; then the lock
+    /// scripts of the read inputs are run in parallel, each in its own VM, and the results are
+    /// processed in the order of the inputs. Thus, the returned inputs and errors are the same as
+    /// for the sequential evaluation, regardless of the thread scheduling.
+    #[cfg(feature = "rayon")]
+    fn load_inputs_parallel<'r>(
+        &self,
+        operation: &Operation,
+        memory: &impl Memory,
+        resolver: &(impl Fn(LibId) -> Option<&'r Lib> + Sync),
+    ) -> Result<Inputs, CallError> {
+        use rayon::prelude::*;
+
+        let mut cells = Vec::with_capacity(operation.destructible_in.len());
+        let mut read_err = None;
+        for (no, input) in operation.destructible_in.iter().enumerate() {
+            match memory.try_destructible(input.addr) {
+                Ok(Some(cell)) => cells.push((no as u16, input, cell)),
+                Ok(None) => {
+                    read_err = Some(CallError::NoReadOnceInput(input.addr));
+                    break;
+                }
+                Err(err) => {
+                    read_err = Some(err);
+                    break;
+                }
+            }
+        }
+
+        let results = cells
+            .par_iter()
+            .map(|(no, input, cell)| {
+                let lock = cell.lock?;
+                let mut vm_inputs = self.input_vm();
+                let res = self
+                    .exec_lock(&mut vm_inputs, *no, input, cell, lock, resolver, None)
+                    .map(|_| {
+                        self.bridged_registers()
+                            .filter_map(|reg| vm_inputs.core.cx.get(reg).map(|val| (reg, val)))
+                            .collect::<Vec<_>>()
+                    });
+                Some(res)
+            })
+            .collect::<Vec<_>>();
+
+        let mut destructible_inputs = Vec::with_capacity(operation.destructible_in.len());
+        let mut bridge = Vec::new();
+        let mut witnesses = Vec::new();
+        for ((_, input, cell), res) in cells.iter().zip(results) {
+            if let Some(res) = res {
+                // Values are taken from the last lock script, see `Codex::input_bridge` docs
+                bridge = res?;
+                witnesses.push((input.addr, input.witness));
+            }
+            destructible_inputs.push(cell.data);
+        }
+        if let Some(err) = read_err {
+            return Err(err);
+        }
+
+        Ok(Inputs {
+            // There is exactly one value per operation input, so the bounds are the same
+            destructible: SmallVec::from_checked(destructible_inputs),
+            immutable: self.load_immutable(operation, memory)?,
+            bridge,
+            witnesses,
+            locks: none!(),
+        })
+    }
+
+    /// Reads the immutable inputs of the operation, checking that all of them are present in the
+    /// memory (unless [`Self::absent_immutable_as_none`] is set).
+    fn load_immutable(
+        &self,
+        operation: &Operation,
+        memory: &impl Memory,
+    ) -> Result<SmallVec<StateValue>, CallError> {
+        let mut immutable_inputs = Vec::with_capacity(operation.immutable_in.len());
+        for addr in &operation.immutable_in {
+            let data = match memory.try_immutable(*addr)? {
+                Some(data) => data,
+                None if self.absent_immutable_as_none => StateValue::None,
+                None => return Err(CallError::NoImmutableInput(*addr)),
+            };
+            immutable_inputs.push(data);
+        }
+        // There is exactly one value per operation input, so the bounds are the same
+        Ok(SmallVec::from_checked(immutable_inputs))
+    }
+
+    fn input_vm(&self) -> Vm<aluvm::gfa::Instr<LibId>> {
+        Vm::<aluvm::gfa::Instr<LibId>>::with(self.input_config, GfaConfig {
+            field_order: self.field_order,
+        })
+    }
+
+    /// Checks the satisfaction of the access conditions of a single destructible input number `no`
+    /// by running its lock script.
+    ///
+    /// Leaves the registers after the script execution to be read by the caller; the VM must be
+    /// reset afterward.
+    #[allow(clippy::too_many_arguments)]
+    fn exec_lock<'r>(
+        &self,
+        vm_inputs: &mut Vm<aluvm::gfa::Instr<LibId>>,
+        no: u16,
+        input: &Input,
+        cell: &StateCell,
+        lock: LibSite,
+        resolver: &impl Fn(LibId) -> Option<&'r Lib>,
+        time: Option<u64>,
+    ) -> Result<(), CallError> {
+        if let Some(index) = input.witness.non_member(self.field_order) {
+            let location = ElementLocation::Witness(no, index);
+            return Err(CallError::FieldMembership { location });
+        }
+
+        // Put also token of authority into a register
+        vm_inputs.core.cx.set(RegE::E1, cell.auth.to_fe256());
+        // Put the height/time value, if provided, see `Codex::verify_timed` docs
+        if let Some(time) = time {
+            vm_inputs
+                .core
+                .cx
+                .set(Self::TIME_REGISTER, fe256::from(time));
+        }
+
+        // Put witness into input registers
+        let bank = self
+            .witness_banks
+            .get(&lock.lib_id)
+            .copied()
+            .unwrap_or_default();
+        let mut presence = 0u8;
+        for (no, reg) in bank.registers().into_iter().enumerate() {
+            let Some(el) = input.witness.get(no as u8) else {
+                break;
+            };
+            vm_inputs.core.cx.set(reg, el);
+            presence |= 1 << no;
+        }
+        // See `Codex::WITNESS_PRESENCE_REGISTER` docs for the encoding
+        vm_inputs
+            .core
+            .cx
+            .set(Self::WITNESS_PRESENCE_REGISTER, fe256::from(presence));
+        let missing = Cell::new(None);
+        if vm_inputs.exec(lock, &(), tracking_resolver(resolver, &missing)) == Status::Fail {
+            if let Some(lib_id) = missing.get() {
+                return Err(CallError::LibNotFound(lib_id));
+            }
+            // Read error code from output register
+            return Err(CallError::Lock(vm_inputs.core.cx.get(RegE::E8)));
+        }
+        Ok(())
+    }
+
+    /// Performs the first phase of the verification with the `options`: checks the operation and
+    /// reads its inputs from the memory, checking their access conditions.
+    ///
+    /// The `locks` define the handling of the input access conditions; the rest of its fields are
+    /// overridden by the `options`.
+    fn load_phase<'r>(
+        &self,
+        contract_id: ContractId,
+        operation: &Operation,
+        memory: &impl Memory,
+        resolver: impl Fn(LibId) -> Option<&'r Lib>,
+        options: VerifyOptions,
+        locks: LoadOptions,
+    ) -> Result<Inputs, CallError> {
+        let VerifyOptions { policy, time, globals: _, witnesses, cancel, deprecated: _ } = options;
+
+        check_cancelled(cancel)?;
+        self.check_contract(contract_id, operation)?;
+        self.validate_operation(operation, policy)?;
+        if let Some(witnesses) = witnesses {
+            if witnesses.len() != operation.destructible_in.len() {
+                return Err(CallError::WitnessCountMismatch {
+                    expected: operation.destructible_in.len() as u16,
+                    found: witnesses.len().try_into().unwrap_or(u16::MAX),
+                });
+            }
+        }
+        self.load_inputs(operation, memory, resolver, LoadOptions {
+            policy,
+            cancel,
+            time,
+            witnesses,
+            ..locks
+        })
+    }
+
+    /// Performs the second phase of the verification with the `options`: runs the operation
+    /// verification script selected by the `selector` over the operation and its `inputs`.
+    fn run_phase<'r>(
+        &self,
+        operation: &Operation,
+        inputs: &Inputs,
+        resolver: impl Fn(LibId) -> Option<&'r Lib>,
+        selector: &impl VerifierSelector,
+        options: VerifyOptions,
+    ) -> Result<Vm<Instr<LibId>>, CallError> {
+        check_cancelled(options.cancel)?;
+        self.verify_main(operation, inputs, resolver, selector, RunOptions::from(options))
+    }
+
+    /// Runs the operation verification script over the operation outputs and the provided inputs.
+    fn verify_main<'r>(
+        &self,
+        operation: &Operation,
+        inputs: &Inputs,
+        resolver: impl Fn(LibId) -> Option<&'r Lib>,
+        selector: &impl VerifierSelector,
+        options: RunOptions,
+    ) -> Result<Vm<Instr<LibId>>, CallError> {
+        let RunOptions {
+            globals,
+            policy,
+            meter,
+            #[cfg(feature = "profiling")]
+            profiler,
+        } = options;
+        let context = VmContext {
+            destructible_input: inputs.destructible.as_slice(),
+            immutable_input: inputs.immutable.as_slice(),
+            destructible_output: operation.destructible_out.as_slice(),
+            immutable_output: operation.immutable_out.as_slice(),
+            globals,
+            complexity: none!(),
+            max_call_depth: policy.max_call_depth,
+            call_depth_exceeded: none!(),
+            immutable_loaded: none!(),
+            #[cfg(feature = "profiling")]
+            profiler,
+        };
+        let entry_point = selector
+            .select(self, operation, &context)
+            .ok_or(CallError::NotFound(operation.call_id))?;
+        let missing = Cell::new(None);
+        let metered_resolver = |lib_id: LibId| {
+            if let Some(meter) = meter {
+                meter.switch_to(Some(lib_id), &context);
+            }
+            resolver(lib_id)
+        };
+        let mut vm_main = self.main_vm(&inputs.bridge, &operation.destructible_out);
+        let status =
+            vm_main.exec(entry_point, &context, tracking_resolver(metered_resolver, &missing));
+        if let Some(meter) = meter {
+            meter.finish(entry_point, &context);
+        }
+        self.script_result(status, &vm_main, &context, missing.get())?;
+        Ok(vm_main)
+    }
+
+    /// Constructs the operation verification VM, putting the values bridged from the input VM
+    /// into its registers.
+    fn main_vm(&self, bridge: &[(RegE, fe256)], outputs: &[StateCell]) -> Vm<Instr<LibId>> {
+        let mut vm = Vm::<Instr<LibId>>::with(self.verification_config, GfaConfig {
+            field_order: self.field_order,
+        });
+        for (reg, val) in bridge {
+            vm.core.cx.set(*reg, *val);
+        }
+        if let Some(sum) = self.output_sum(outputs) {
+            vm.core.cx.set(Self::OUTPUT_SUM_REGISTER, sum);
+        }
+        vm
+    }
+
+    /// Interprets the status of the operation verification script.
+    ///
+    /// A failure is attributed to exceeding the call depth limit (see
+    /// [`VmContext::max_call_depth`]) or to the library which the VM failed to resolve
+    /// (`missing`), if any, since the script failed to run to the completion; otherwise it is a
+    /// script failure.
+    fn script_result(
+        &self,
+        status: Status,
+        vm_main: &Vm<Instr<LibId>>,
+        context: &VmContext,
+        missing: Option<LibId>,
+    ) -> Result<(), CallError> {
+        match (status, missing) {
+            (Status::Ok, _) => Ok(()),
+            (Status::Fail, _) if context.call_depth_exceeded.get() => {
+                Err(CallError::CallDepthExceeded)
+            }
+            (Status::Fail, Some(lib_id)) => Err(CallError::LibNotFound(lib_id)),
+            (Status::Fail, None) => {
+                let err_code = vm_main.core.cx.get(RegE::E1);
+                if err_code.is_some() && err_code == self.indeterminate_code {
+                    Err(CallError::Indeterminate)
+                } else if let Some(err_code) = err_code {
+                    Err(CallError::Script(err_code))
+                } else {
+                    Err(CallError::ScriptUnspecified)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl Codex {
+    /// Runs the verifier for the `call_id` over a supplied VM `context`, which is usually
+    /// assembled with [`crate::VmContextBuilder`].
+    ///
+    /// This is a testing aid allowing to unit-test the verifiers in isolation, without
+    /// constructing operations and memory. It doesn't perform any checks of the operation inputs
+    /// and their access conditions, and thus **must not be used for the consensus verification**.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::NotFound`] if the codex has no verifier for the `call_id`, or
+    /// [`CallError::Script`]/[`CallError::ScriptUnspecified`] on the verifier failure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn run_verifier(
+        &self,
+        call_id: CallId,
+        context: &VmContext,
+        repo: &impl LibRepo,
+    ) -> Result<(), CallError> {
+        let (status, vm_main) = self.exec_verifier(call_id, context, repo)?;
+        self.script_result(status, &vm_main, context, None)
+    }
+
+    /// Runs the verifier for the `call_id` over a supplied VM `context` (like
+    /// [`Self::run_verifier`]) and compares the final values of the VM registers with the
+    /// `expected` ones, which is useful for debugging the verifiers.
+    ///
+    /// The registers are compared independently of whether the verifier has succeeded or failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a list of all registers which values differ from the expected ones. If the codex has
+    /// no verifier for the `call_id`, or the verifier fails due to an absent library, all the
+    /// `expected` registers are reported as mismatching.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn expect_regs(
+        &self,
+        call_id: CallId,
+        context: &VmContext,
+        repo: &impl LibRepo,
+        expected: &[(RegE, fe256)],
+    ) -> Result<(), Vec<RegMismatch>> {
+        let vm_main = self
+            .exec_verifier(call_id, context, repo)
+            .ok()
+            .map(|(_, vm)| vm);
+        let mismatches = expected
+            .iter()
+            .map(|(reg, val)| RegMismatch {
+                reg: *reg,
+                expected: *val,
+                actual: vm_main.as_ref().and_then(|vm| vm.core.cx.get(*reg)),
+            })
+            .filter(|mismatch| mismatch.actual != Some(mismatch.expected))
+            .collect::<Vec<_>>();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+
+    /// Verifies the operation with [`Self::verify`] and checks that it is rejected with exactly
+    /// the `expected` error (including the status code returned by the verification script).
+    ///
+    /// This is a testing aid for the negative testing of contracts.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing the actual verification result if the operation is accepted
+    /// or rejected with a different error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn assert_rejected(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        expected: &CallError,
+    ) -> Result<(), String> {
+        match self.verify(contract_id, operation, memory, repo) {
+            Err(err) if err == *expected => Ok(()),
+            Err(err) => Err(format!("operation is rejected with {err:?} instead of {expected:?}")),
+            Ok(_) => {
+                Err(format!("operation is accepted, while expected to fail with {expected:?}"))
+            }
+        }
+    }
+
+    fn exec_verifier(
+        &self,
+        call_id: CallId,
+        context: &VmContext,
+        repo: &impl LibRepo,
+    ) -> Result<(Status, Vm<Instr<LibId>>), CallError> {
+        let entry_point = self
+            .verifiers
+            .get(&call_id)
+            .ok_or(CallError::NotFound(call_id))?;
+        let missing = Cell::new(None);
+        let resolver = tracking_resolver(checked_resolver(repo), &missing);
+        let mut vm_main = self.main_vm(&[], context.destructible_output);
+        let status = vm_main.exec(*entry_point, context, resolver);
+        if let Some(lib_id) = missing.get().filter(|_| status == Status::Fail) {
+            return Err(CallError::LibNotFound(lib_id));
+        }
+        Ok((status, vm_main))
+    }
+}
+
+/// Register which final value differs from the expected one, reported by [`Codex::expect_regs`].
+#[cfg(feature = "test-utils")]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RegMismatch {
+    /// The register.
+    pub reg: RegE,
+    /// The value expected in the register.
+    pub expected: fe256,
+    /// The actual value of the register, or `None` if the register is not set.
+    pub actual: Option<fe256>,
+}
+
+#[cfg(feature = "test-utils")]
+impl Display for RegMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected {}, found ", self.reg, self.expected)?;
+        match self.actual {
+            Some(val) => write!(f, "{val}"),
+            None => f.write_str("~"),
+        }
+    }
+}
+
+/// Bank of four registers into which a witness for an input lock script is loaded (see
+/// [`Codex::witness_banks`]).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[repr(u8)]
+pub enum WitnessBank {
+    /// Registers `E2`-`E5` (the default).
+    #[default]
+    Primary = 0,
+
+    /// Registers `EA`-`ED`.
+    Secondary = 1,
+
+    /// Registers `EE`-`EH`.
+    Tertiary = 2,
+}
+
+impl WitnessBank {
+    /// Returns the registers of the bank, in the order of the witness elements loaded into them.
+    pub const fn registers(self) -> [RegE; 4] {
+        match self {
+            WitnessBank::Primary => [RegE::E2, RegE::E3, RegE::E4, RegE::E5],
+            WitnessBank::Secondary => [RegE::EA, RegE::EB, RegE::EC, RegE::ED],
+            WitnessBank::Tertiary => [RegE::EE, RegE::EF, RegE::EG, RegE::EH],
+        }
+    }
+}
+
+/// Location of a field element within an operation, reported by [`CallError::FieldMembership`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(doc_comments)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum ElementLocation {
+    /// element {1} of the witness of the destructible input #{0}
+    Witness(u16, u8),
+
+    /// element {1} of the destructible output #{0}
+    DestructibleOut(u16, u8),
+
+    /// element {1} of the immutable output #{0}
+    ImmutableOut(u16, u8),
+}
+
+/// Summary of the VM configuration used by a codex, returned by [`Codex::config_summary`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ConfigSummary {
+    /// The order of the field used by VM for all scripts (see [`Codex::field_order`]).
+    pub field_order: u256,
+    /// Whether the VM checking input lock conditions halts on failures (see
+    /// [`Codex::input_config`]).
+    pub input_halt: bool,
+    /// Complexity limit of the VM checking input lock conditions (see [`Codex::input_config`]).
+    pub input_complexity_lim: Option<u64>,
+    /// Whether the VM verifying the operation halts on failures (see
+    /// [`Codex::verification_config`]).
+    pub verification_halt: bool,
+    /// Complexity limit of the VM verifying the operation (see [`Codex::verification_config`]).
+    pub verification_complexity_lim: Option<u64>,
+}
+
+/// Discovery metadata of a codex, returned by [`Codex::manifest`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct CodexManifest {
+    /// Id of the codex (see [`Codex::codex_id`]).
+    pub codex_id: CodexId,
+    /// Human-readable name of the codex (see [`Codex::name`]).
+    pub name: TinyString,
+    /// Identity of the codex developer (see [`Codex::developer`]).
+    pub developer: Identity,
+    /// Timestamp of the codex creation (see [`Codex::timestamp`]).
+    pub timestamp: i64,
+    /// Number of bits in the order of the field used by the VM (see [`Codex::field_order`]).
+    pub field_bits: u16,
+    /// Number of the codex verifiers (see [`Codex::verifiers`]).
+    pub verifier_count: u16,
+    /// Ids of all the libraries used by the codex verifiers, including the libraries called by
+    /// them.
+    pub libs: BTreeSet<LibId>,
+}
+
+/// Description of a call supported by a codex, returned by [`Codex::call_descriptors`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct CallDescriptor {
+    /// Call id used by the operations (see [`Operation::call_id`]).
+    pub call_id: CallId,
+    /// Entry point of the verifier for the call (see [`Codex::verifiers`]).
+    pub verifier: LibSite,
+}
+
+/// Builder constructing a [`Codex`], which validates the verifier entry points on their insertion.
+#[derive(Clone, Debug)]
+pub struct CodexBuilder {
+    codex: Codex,
+}
+
+impl CodexBuilder {
+    /// Starts construction of a codex with a given name, developer identity, creation timestamp and
+    /// VM field order.
+    ///
+    /// Both VM core configurations are initialized with their default values, and the codex
+    /// doesn't contain any register bridging or verifiers.
+    pub fn new(name: TinyString, developer: Identity, timestamp: i64, field_order: u256) -> Self {
+        Self {
+            codex: Codex {
+                version: default!(),
+                name,
+                developer,
+                developer_key: None,
+                timestamp,
+                field_order,
+                input_config: default!(),
+                verification_config: default!(),
+                input_bridge: 0,
+                absent_immutable_as_none: false,
+                output_sum: None,
+                witness_banks: none!(),
+                verifiers: none!(),
+                indeterminate_code: None,
+            },
+        }
+    }
+
+    /// Sets VM core configuration used to verify the satisfaction of the input lock conditions.
+    pub fn input_config(mut self, config: CoreConfig) -> Self {
+        self.codex.input_config = config;
+        self
+    }
+
+    /// Sets VM core configuration used for the operation verification.
+    pub fn verification_config(mut self, config: CoreConfig) -> Self {
+        self.codex.verification_config = config;
+        self
+    }
+
+    /// Sets the registers bridged from the input VM to the operation verification VM (see
+    /// [`Codex::input_bridge`]).
+    pub fn input_bridge(mut self, bridge: u16) -> Self {
+        self.codex.input_bridge = bridge;
+        self
+    }
+
+    /// Sets whether the absent immutable inputs are read as empty values (see
+    /// [`Codex::absent_immutable_as_none`]).
+    pub fn absent_immutable_as_none(mut self, flag: bool) -> Self {
+        self.codex.absent_immutable_as_none = flag;
+        self
+    }
+
+    /// Sets the component of the destructible output values summed for the operation verification
+    /// script (see [`Codex::output_sum`]).
+    pub fn output_sum(mut self, component: Option<u8>) -> Self {
+        self.codex.output_sum = component;
+        self
+    }
+
+    /// Sets the error code designating an indeterminate verification result (see
+    /// [`Codex::indeterminate_code`]).
+    pub fn indeterminate_code(mut self, code: Option<fe256>) -> Self {
+        self.codex.indeterminate_code = code;
+        self
+    }
+
+    /// Sets the register banks used for the witnesses of the input lock scripts (see
+    /// [`Codex::witness_banks`]).
+    pub fn witness_banks(mut self, banks: TinyOrdMap<LibId, WitnessBank>) -> Self {
+        self.codex.witness_banks = banks;
+        self
+    }
+
+    /// Adds a verifier for a given call id, replacing the previous verifier for the same call, if
+    /// any.
+    ///
+    /// The verifier library is resolved using the `repo`, and the entry point is checked to point
+    /// at the beginning of some instruction within the library code.
+    ///
+    /// # Errors
+    ///
+    /// If the verifier library is not known to the `repo`, or the entry point is invalid, or the
+    /// codex already has the maximum number of verifiers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn add_verifier(
+        mut self,
+        call_id: CallId,
+        site: LibSite,
+        repo: &impl LibRepo,
+    ) -> Result<Self, CodexError> {
+        validate_entry_point(call_id, site, repo)?;
+        self.codex
+            .verifiers
+            .insert(call_id, site)
+            .map_err(|_| CodexError::TooManyVerifiers)?;
+        Ok(self)
+    }
+
+    /// Sets the public key of the codex developer (see [`Codex::developer_key`]).
+    pub fn developer_key(mut self, key: DeveloperKey) -> Self {
+        self.codex.developer_key = Some(key);
+        self
+    }
+
+    /// Completes the codex construction.
+    pub fn finish(self) -> Codex { self.codex }
+}
+
+#[cfg(feature = "cbor")]
+impl Codex {
+    /// Serializes the codex into a compact binary CBOR representation.
+    ///
+    /// The serialization uses the same serde data model as other serde formats (like JSON), but is
+    /// much more compact, and thus is suitable for on-wire codex exchange. Deserializing the result
+    /// with [`Self::from_cbor`] produces a codex with the same [`Self::codex_id`].
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data)
+            .expect("CBOR serialization of a codex into memory buffer can't fail");
+        data
+    }
+
+    /// Deserializes the codex from a CBOR representation produced by [`Self::to_cbor`].
+    ///
+    /// # Errors
+    ///
+    /// If the data are not a valid CBOR, or do not represent a codex.
+    pub fn from_cbor(data: &[u8]) -> Result<Self, ciborium::de::Error<std::io::Error>> {
+        ciborium::from_reader(data)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Codex {
+    /// Verifies the operation in the same way as [`Self::verify_metered`], returning the result as
+    /// a JSON object, for the use by command-line tools.
+    ///
+    /// The object has the following fields:
+    /// - `ok`: boolean indicating whether the operation is valid;
+    /// - `error`: serialized [`CallError`] if the verification has failed, or `null` otherwise;
+    /// - `cycles`: total complexity of the verification script execution (see
+    ///   [`VerificationReport::cycles`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_json(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> String {
+        #[derive(Serialize)]
+        struct JsonResult {
+            ok: bool,
+            error: Option<CallError>,
+            cycles: u64,
+        }
+
+        let (result, report) =
+            self.verify_metered(contract_id, operation, memory, repo, default!());
+        let json = JsonResult {
+            ok: result.is_ok(),
+            error: result.err(),
+            cycles: report.cycles,
+        };
+        serde_json::to_string(&json)
+            .expect("JSON serialization of a verification result can't fail")
+    }
+}
+
+/// Checks that the verifier entry point points at the beginning of an instruction within the
+/// library code.
+fn validate_entry_point(
+    call_id: CallId,
+    site: LibSite,
+    repo: &impl LibRepo,
+) -> Result<(), CodexError> {
+    let lib = checked_resolver(repo)(site.lib_id)
+        .ok_or(CodexError::LibAbsent { call_id, lib: site.lib_id })?;
+    let mut marshaller = Marshaller::with(&lib.code, &lib.data, &lib.libs);
+    while !marshaller.is_eof() && marshaller.pos() < site.offset {
+        if Instr::<LibId>::decode_instr(&mut marshaller).is_err() {
+            break;
+        }
+    }
+    if marshaller.is_eof() || marshaller.pos() != site.offset {
+        return Err(CodexError::InvalidEntryPoint {
+            call_id,
+            lib: site.lib_id,
+            offset: site.offset,
+        });
+    }
+    Ok(())
+}
+
+/// Collects the `roots` libraries together with all the libraries called from them (directly or
+/// through other libraries) and known to the `repo`.
+pub(crate) fn lib_closure(
+    roots: impl IntoIterator<Item = LibId>,
+    repo: &impl LibRepo,
+) -> BTreeSet<LibId> {
+    let resolver = checked_resolver(repo);
+    let mut libs = BTreeSet::new();
+    let mut queue = roots.into_iter().collect::<Vec<_>>();
+    while let Some(lib_id) = queue.pop() {
+        if !libs.insert(lib_id) {
+            continue;
+        }
+        if let Some(lib) = resolver(lib_id) {
+            queue.extend(lib.libs.iter().copied());
+        }
+    }
+    libs
+}
+
+/// Counts the instructions in the library code, stopping at the first non-decodable one.
+fn lib_instr_count(lib: &Lib) -> u64 {
+    let mut marshaller = Marshaller::with(&lib.code, &lib.data, &lib.libs);
+    let mut count = 0u64;
+    while !marshaller.is_eof() && Instr::<LibId>::decode_instr(&mut marshaller).is_ok() {
+        count += 1;
+    }
+    count
+}
+
+/// Options for reading the operation inputs with [`Codex::load_inputs`].
+#[derive(Copy, Clone, Debug, Default)]
+struct LoadOptions<'c> {
+    /// Do not check the access conditions of the destructible inputs.
+    skip_locks: bool,
+    /// Record the results of the access condition checks in [`Inputs::locks`] instead of failing
+    /// on the first unsatisfied lock.
+    record_locks: bool,
+    /// Policy for the additional checks of the inputs.
+    policy: VerifyPolicy,
+    /// Flag requesting cancellation of the verification.
+    cancel: Option<&'c AtomicBool>,
+    /// Height/time value provided to the lock scripts.
+    time: Option<u64>,
+    /// Detached witnesses used instead of [`Input::witness`], indexed by the input number.
+    witnesses: Option<&'c [StateValue]>,
+    /// Callback receiving the results of the access condition checks.
+    on_lock: Option<LockHook<'c>>,
+}
+
+/// Callback receiving the results of the access condition checks of the destructible inputs (see
+/// [`Codex::verify_streaming_locks`]).
+#[derive(Copy, Clone)]
+struct LockHook<'c>(&'c dyn Fn(CellAddr, Result<(), CallError>));
+
+impl core::fmt::Debug for LockHook<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("LockHook(..)")
+    }
+}
+
+/// Options for running the operation verification script with [`Codex::verify_main`].
+#[derive(Copy, Clone, Debug, Default)]
+struct RunOptions<'c> {
+    /// Contract-global parameters.
+    globals: &'c [StateData],
+    /// Policy providing the limits for the verification script.
+    policy: VerifyPolicy,
+    /// Meter recording the resources consumed by the verification script.
+    meter: Option<&'c Meter>,
+    /// Profiler recording the verification script execution.
+    #[cfg(feature = "profiling")]
+    profiler: Option<&'c Profiler>,
+}
+
+impl<'c> From<VerifyOptions<'c>> for RunOptions<'c> {
+    fn from(options: VerifyOptions<'c>) -> Self {
+        Self {
+            globals: options.globals,
+            policy: options.policy,
+            ..default!()
+        }
+    }
+}
+
+/// Meter of the resources consumed by the operation verification script (see
+/// [`Codex::verify_metered`]).
+#[derive(Debug, Default)]
+struct Meter {
+    /// The library which code is executed at the moment, and the complexity accumulated when the
+    /// VM has switched to it.
+    current: Cell<(Option<LibId>, u64)>,
+    /// Complexity accumulated by each of the executed libraries.
+    lib_cycles: RefCell<BTreeMap<LibId, u64>>,
+    /// Total complexity of the verification script execution.
+    complexity: Cell<u64>,
+    /// Number of the immutable inputs loaded by the verification script.
+    immutable_loaded: Cell<u16>,
+    /// Entry point of the executed verifier, set once the script execution has finished.
+    entry_point: Cell<Option<LibSite>>,
+}
+
+impl Meter {
+    /// Attributes the complexity accumulated since the previous switch to the previously executed
+    /// library, and switches to the `next` library.
+    fn switch_to(&self, next: Option<LibId>, context: &VmContext) {
+        let now = context.complexity.get();
+        let (prev, since) = self.current.replace((next, now));
+        if let Some(lib_id) = prev {
+            *self.lib_cycles.borrow_mut().entry(lib_id).or_default() += now - since;
+        }
+    }
+
+    /// Records the results of the finished execution of the verifier at the `entry_point`.
+    fn finish(&self, entry_point: LibSite, context: &VmContext) {
+        self.switch_to(None, context);
+        self.complexity.set(context.complexity.get());
+        self.immutable_loaded.set(context.immutable_loaded.get());
+        self.entry_point.set(Some(entry_point));
+    }
+
+    /// Produces the report on the verification of the `operation`, which is empty if the
+    /// verification script was not run.
+    fn into_report(self, operation: &Operation) -> VerificationReport {
+        let Some(entry_point) = self.entry_point.get() else {
+            return VerificationReport::default();
+        };
+        let mut report =
+            VerificationReport::with(self.complexity.get(), self.lib_cycles.into_inner());
+        report.executed_verifier = Some(entry_point);
+        report.consulted_immutable = SmallOrdSet::from_iter_checked(
+            operation
+                .immutable_in
+                .iter()
+                .take(self.immutable_loaded.get() as usize)
+                .copied(),
+        );
+        report
+    }
+}
+
+/// Returns [`CallError::Cancelled`] if the cancellation flag is set.
+fn check_cancelled(cancel: Option<&AtomicBool>) -> Result<(), CallError> {
+    match cancel {
+        Some(flag) if flag.load(AtomicOrdering::Relaxed) => Err(CallError::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Operation inputs read from the memory.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+struct Inputs {
+    destructible: SmallVec<StateValue>,
+    immutable: SmallVec<StateValue>,
+    /// Register values bridged from the input VM to the operation verification VM.
+    bridge: Vec<(RegE, fe256)>,
+    /// Witnesses accepted by the lock scripts of the destructible inputs.
+    witnesses: Vec<(CellAddr, StateValue)>,
+    /// Results of the lock script checks, collected if [`LoadOptions::record_locks`] is set.
+    locks: Vec<LockOutcome>,
+}
+
+/// Constructs a library resolver for the VM out of the library repository, checking the integrity
+/// of the returned libraries.
+///
+/// # Panics
+///
+/// The resolver panics if the `repo` returns a library which id doesn't match the requested one.
+fn checked_resolver<'r>(repo: &'r impl LibRepo) -> impl Fn(LibId) -> Option<&'r Lib> + 'r {
+    move |lib_id: LibId| {
+        let lib = repo.get_lib(lib_id)?;
+        // We must have this verification to avoid hacking from the client libraries.
+        if lib.lib_id() != lib_id {
+            panic!(
+                "The library returned by the `LibRepo` provided for the contract operation \
+                 verification doesn't match the requested library id. This error indicates that \
+                 the software using the consensus verification is invalid or compromised."
+            )
+        }
+        Some(lib)
+    }
+}
+
+/// Wraps the library `resolver`, recording into `missing` the id of the first library which it
+/// fails to resolve (see [`CallError::LibNotFound`]).
+fn tracking_resolver<'r, 'm>(
+    resolver: impl Fn(LibId) -> Option<&'r Lib> + 'm,
+    missing: &'m Cell<Option<LibId>>,
+) -> impl Fn(LibId) -> Option<&'r Lib> + 'm {
+    move |lib_id: LibId| {
+        let lib = resolver(lib_id);
+        if lib.is_none() && missing.get().is_none() {
+            missing.set(Some(lib_id));
+        }
+        lib
+    }
+}
+
+/// The trait, which must be implemented by a client library for a structure providing access to the
+/// valid and most recent contract state, consisting of two parts: *destructible* (also called
+/// *read-once*, or *owned*) and *immutable* (also called *read-only*, *append-only* or *global*).
+pub trait Memory {
+    /// Read a destructible memory cell created by a specific operation read-once output, which is
+    /// defined as a part of [`Operation::destructible_out`].
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell>;
+    /// Read an immutable memory cell created by a specific operation immutable output, which is
+    /// defined as a part of [`Operation::immutable_out`].
+    ///
+    /// Only the verifiable part of the cell data ([`StateData::value`]) is requested: the
+    /// raw data are not accessible to the verifiers and are never read or copied during the
+    /// verification, so the cost of reading an immutable input doesn't depend on the size of the
+    /// raw data kept in the cell. Since [`StateValue`] has a fixed size, it is returned by value.
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue>;
+
+    /// Reads a destructible memory cell in the same way as [`Self::destructible`], distinguishing
+    /// a cell which is present in the memory but can't be read (for instance, due to a corrupted
+    /// storage) from an absent one.
+    ///
+    /// The verification reads the memory with this method, thus a memory which may fail to read a
+    /// cell must implement it, since otherwise the failed cell is taken for an absent one. By
+    /// default, the read never fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::CorruptedCell`] if the cell can't be read.
+    fn try_destructible(&self, addr: CellAddr) -> Result<Option<StateCell>, CallError> {
+        Ok(self.destructible(addr))
+    }
+
+    /// Reads an immutable memory cell in the same way as [`Self::immutable`], distinguishing a cell
+    /// which can't be read from an absent one (see [`Self::try_destructible`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::CorruptedCell`] if the cell can't be read.
+    fn try_immutable(&self, addr: CellAddr) -> Result<Option<StateValue>, CallError> {
+        Ok(self.immutable(addr))
+    }
+
+    /// Returns the root committing to all the memory cells (see [`StateRoot`] for the details of
+    /// its construction), if the memory supports it.
+    ///
+    /// The memory is not required to hold all its cells, thus by default the root is not provided.
+    /// Implementations holding the full memory may compute the root with [`StateRoot::merklize`];
+    /// light clients may provide the root they have verified the memory cells against.
+    fn root(&self) -> Option<StateRoot> { None }
+}
+
+/// The trait providing access to all the VM code libraries used by the contract, in both operation
+/// verification or state access conditions.
+pub trait LibRepo {
+    /// Get a specific library with the provided id.
+    ///
+    /// If the library is not known and this method returns `None`, but the library is called by the
+    /// operation verification or state access script, the verification will fail with
+    /// [`CallError::LibNotFound`].
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib>;
+}
+
+/// Selector of the verifier used to verify an operation.
+///
+/// # Determinism
+///
+/// The verifier selection is a part of the contract consensus: all parties verifying the contract
+/// must use the same selector, and the selector must be a pure function of the codex, the
+/// operation and the VM context, producing the same result on all platforms and at all times. It
+/// must not depend on any external data (like time, randomness, or the state of the host), and
+/// must not use any floating-point arithmetic or iteration over collections with unspecified
+/// order. Failure to satisfy these requirements leads to the contract state divergence between
+/// the parties.
+pub trait VerifierSelector {
+    /// Selects the entry point of the verifier for the `operation`, basing on the `codex`, the
+    /// operation data and the VM `context`, which contains both operation inputs and outputs.
+    ///
+    /// If no verifier is selected, the operation verification fails with
+    /// [`CallError::NotFound`].
+    fn select(&self, codex: &Codex, operation: &Operation, context: &VmContext) -> Option<LibSite>;
+}
+
+/// The default verifier selector, which uses [`Codex::verifiers`] map, selecting the verifier
+/// matching the operation [`Operation::call_id`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct CallIdSelector;
+
+impl VerifierSelector for CallIdSelector {
+    fn select(&self, codex: &Codex, operation: &Operation, _: &VmContext) -> Option<LibSite> {
+        codex.validate_call(operation).ok()
+    }
+}
+
+/// Verifier selector recording call ids of the verifiers exercised by the verified operations.
+///
+/// The selection itself is performed in the same way as by [`CallIdSelector`]; the tracker is
+/// intended to be used with [`Codex::verify_with_selector`] for collecting the verifier coverage
+/// over a corpus of operations, which can be then analyzed with [`Codex::unused_verifiers`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct CoverageTracker {
+    covered: RefCell<BTreeSet<CallId>>,
+}
+
+impl CoverageTracker {
+    /// Constructs a tracker with an empty coverage.
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns the set of call ids of the exercised verifiers.
+    pub fn covered(&self) -> BTreeSet<CallId> { self.covered.borrow().clone() }
+
+    /// Consumes the tracker, returning the set of call ids of the exercised verifiers.
+    pub fn into_covered(self) -> BTreeSet<CallId> { self.covered.into_inner() }
+}
+
+impl VerifierSelector for CoverageTracker {
+    fn select(&self, codex: &Codex, operation: &Operation, context: &VmContext) -> Option<LibSite> {
+        let site = CallIdSelector.select(codex, operation, context)?;
+        self.covered.borrow_mut().insert(operation.call_id);
+        Some(site)
+    }
+}
+
+/// Contract operation verification errors returned by [`Codex::verify`].
+///
+/// The name of the error type is chose so since the operation "calls" to a contract method, and the
+/// codex verification verifies the integrity of the call.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum CallError {
+    /// operation doesn't belong to the currecnt contract.
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation doesn't belong to the current contract {expected} (operation \
+                   contract is {found})."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation doesn't belong to the current contract."
+    )]
+    WrongContract {
+        /// Expected contract id.
+        expected: ContractId,
+        /// The actual contract id of the operation.
+        found: ContractId,
+    },
+
+    /// operation verifier {0} is not present in the codex.
+    NotFound(CallId),
+
+    /// operation references destructible memory cell which was not defined.
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation references destructible memory cell {0} which was not defined."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation references destructible memory cell {0:?} which was not defined."
+    )]
+    NoReadOnceInput(CellAddr),
+
+    /// operation references immutable memory cell which was not defined.
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation references immutable memory cell {0} which was not defined."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation references immutable memory cell {0:?} which was not defined."
+    )]
+    /// operation references immutable memory cell {0} which was not defined.
+    NoImmutableInput(CellAddr),
+
+    /// operation input access conditions are unsatisfied.
+    Lock(Option<fe256>),
+
+    /// verification script failure with status code {0}.
+    Script(fe256),
+
+    /// verification script failure (no status code is returned from the verification script).
+    ScriptUnspecified,
+
+    /// genesis operation must not contain inputs.
+    NotGenesis,
+
+    /// operation provides a witness for the destructible memory cell which has no lock.
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation provides a witness for the destructible memory cell {0} which has no \
+                   lock."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation provides a witness for the destructible memory cell {0:?} which has \
+                   no lock."
+    )]
+    UnexpectedWitness(CellAddr),
+
+    /// operation verification was cancelled.
+    Cancelled,
+
+    /// witness has {0} elements, while a state value can hold at most four.
+    WitnessTooLong(usize),
+
+    /// operation has neither destructible inputs nor outputs.
+    EmptyOperation,
+
+    /// operation call {0} destroys destructible memory cells without creating new ones, while not
+    /// being a burn call.
+    UnexpectedBurn(CallId),
+
+    /// operation redefines immutable memory cell which is already present in the memory.
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation redefines immutable memory cell {0} which is already present in the \
+                   memory."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation redefines immutable memory cell {0:?} which is already present in \
+                   the memory."
+    )]
+    ImmutableRedefined(CellAddr),
+
+    /// operation has less inputs than required by the host pre-condition.
+    TooFewInputs,
+
+    /// operation call {0} is not allowed by the host pre-condition.
+    CallNotAllowed(CallId),
+
+    /// lock script library of the operation destructible output #{0} is not known to the library
+    /// repository.
+    OutputLockUnresolved(u16),
+
+    /// operation destructible output #{0} has the same token of authority as one of the previous
+    /// outputs.
+    DuplicateOutputToken(u16),
+
+    /// operation verification is indeterminate, requiring more data to reach a decision.
+    Indeterminate,
+
+    /// operation verifier exceeds the maximum depth of the nested calls.
+    CallDepthExceeded,
+
+    /// operation verifier requires library {0} which is not known to the library repository.
+    LibNotFound(LibId),
+
+    /// the number of pre-gathered inputs {found} doesn't match the number of operation inputs
+    /// {expected}.
+    PreparedInputMismatch {
+        /// Number of the operation inputs.
+        expected: u16,
+        /// Number of the provided memory cells.
+        found: u16,
+    },
+
+    /// pre-gathered inputs provide different memory cells for the same address.
+    #[cfg_attr(
+        feature = "baid64",
+        display = "pre-gathered inputs provide different memory cells for the same address {0}."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "pre-gathered inputs provide different memory cells for the same address {0:?}."
+    )]
+    PreparedInputConflict(CellAddr),
+
+    /// the number of detached witnesses {found} doesn't match the number of operation
+    /// destructible inputs {expected}.
+    WitnessCountMismatch {
+        /// Number of the operation destructible inputs.
+        expected: u16,
+        /// Number of the provided witnesses.
+        found: u16,
+    },
+
+    /// {location} is not a member of the codex field.
+    FieldMembership {
+        /// Location of the element in the operation.
+        location: ElementLocation,
+    },
+
+    /// Memory cell can't be read from the memory (see [`Memory::try_destructible`]).
+    #[cfg_attr(
+        feature = "baid64",
+        display = "memory cell {0} is present in the memory, but can't be read."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "memory cell {0:?} is present in the memory, but can't be read."
+    )]
+    CorruptedCell(CellAddr),
+}
+
+/// Unique codex identifier - a commitment to all the [`Codex`] data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(AsSlice, Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "baid64")),
+    derive(Serialize, Deserialize),
+    serde(transparent)
+)]
+pub struct CodexId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+#[cfg(all(feature = "serde", feature = "baid64"))]
+impl_serde_str_bin_wrapper!(CodexId, Bytes32);
+
+impl From<Sha256> for CodexId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for CodexId {
+    const TAG: &'static str = "urn:ubideco:sonic:codex#2026-10-14";
+}
+
+#[cfg(feature = "baid64")]
+mod _baid4 {
+    use core::fmt::{self, Display, Formatter};
+    use core::str::FromStr;
+
+    use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+
+    use super::*;
+
+    impl DisplayBaid64 for CodexId {
+        const HRI: &'static str = "codex";
+        const CHUNKING: bool = true;
+        const PREFIX: bool = false;
+        const EMBED_CHECKSUM: bool = false;
+        const MNEMONIC: bool = true;
+        fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+    }
+    impl FromBaid64Str for CodexId {}
+    impl FromStr for CodexId {
+        type Err = Baid64ParseError;
+        fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+    }
+    impl Display for CodexId {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use core::str::FromStr;
+    use std::collections::HashMap;
+
+    use aluvm::alu::aluasm;
+    use aluvm::{zk_aluasm, FIELD_ORDER_SECP};
+    use amplify::ByteArray;
+    use commit_verify::Digest;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::memory::test_helpers::DumbMemory;
+    use crate::{
+        uasm, AuthToken, Genesis, Input, MinInputs, Opid, OutputLocksResolve, PrevOutputMemory,
+        RequiredCallIds, SigScheme, UniqueOutputTokens,
+    };
+
+    #[test]
+    fn codex_id_display() {
+        let id = CodexId::from_byte_array(Sha256::digest(b"test"));
+        assert_eq!(
+            format!("{id}"),
+            "n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
+        );
+        assert_eq!(
+            format!("{id:-}"),
+            "codex:n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
+        );
+        assert_eq!(format!("{id:#}"), "n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg");
+    }
+
+    #[test]
+    #[cfg(feature = "chf-sha256")]
+    fn codex_id_with() {
+        struct Untagged(Sha256);
+        impl CommitHash for Untagged {
+            fn from_tag(_: &str) -> Self { Self(Sha256::default()) }
+            fn input_raw(&mut self, data: &[u8]) { self.0.input_raw(data) }
+            fn finish(self) -> [u8; 32] { self.0.finish() }
+        }
+
+        let mut codex = Codex::strict_dumb();
+        assert_eq!(codex.codex_id_with::<crate::Sha256Hash>(), codex.codex_id());
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib_success().lib_id(), 0) };
+        codex.indeterminate_code = Some(fe256::from(7u8));
+        assert_eq!(codex.codex_id_with::<crate::Sha256Hash>(), codex.codex_id());
+        assert_ne!(codex.codex_id_with::<Untagged>(), codex.codex_id());
+    }
+
+    #[test]
+    fn codex_id_vector() {
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.developer_key =
+            Some(DeveloperKey::new(SigScheme::Ed25519, Bytes32::from_byte_array([0xD0; 32])));
+        codex.input_bridge = 0b1010;
+        codex.absent_immutable_as_none = true;
+        codex.output_sum = Some(2);
+        codex.witness_banks =
+            tiny_bmap! { LibId::from_byte_array([0x1B; 32]) => WitnessBank::Tertiary };
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(LibId::from_byte_array([0x1A; 32]), 0) };
+        codex.indeterminate_code = Some(fe256::from(7u8));
+        assert_eq!(
+            codex.codex_id().to_string(),
+            "qWr6O8Do-HcZsCkT-rq~Sre6-FPbOPgW-~p4kssv-nEoF_Ow#maxwell-france-everest"
+        );
+    }
+
+    #[test]
+    fn codex_id_from_str() {
+        let id = CodexId::from_byte_array(Sha256::digest(b"test"));
+        assert_eq!(
+            CodexId::from_str(
+                "n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
+            )
+            .unwrap(),
+            id
+        );
+        assert_eq!(
+            CodexId::from_str(
+                "codex:n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
+            )
+            .unwrap(),
+            id
+        );
+        assert_eq!(
+            CodexId::from_str("codex:n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg").unwrap(),
+            id
+        );
+        assert_eq!(
+            CodexId::from_str("n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg").unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn manifest() {
+        let leaf = lib_success();
+        let leaf_id = leaf.lib_id();
+        let root = Lib::assemble(&zk_aluasm! {
+            call    leaf_id, 0;
+            stop;
+        })
+        .unwrap();
+        let root_id = root.lib_id();
+        let repo = HashMap::from([(leaf_id, leaf), (root_id, root.clone())]);
+
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.verifiers =
+            tiny_bmap! { 0 => LibSite::new(root_id, 0), 1 => LibSite::new(root_id, 0) };
+        let manifest = codex.manifest(&repo).unwrap();
+        assert_eq!(manifest, CodexManifest {
+            codex_id: codex.codex_id(),
+            name: codex.name.clone(),
+            developer: codex.developer.clone(),
+            timestamp: codex.timestamp,
+            field_bits: 256,
+            verifier_count: 2,
+            libs: bset![leaf_id, root_id],
+        });
+        assert_eq!(codex.manifest(&root), Err(CallError::LibNotFound(leaf_id)));
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&manifest).unwrap();
+            let decoded: CodexManifest = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, manifest);
+        }
+    }
+
+    #[test]
+    fn config_summary() {
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.input_config = CoreConfig { halt: false, complexity_lim: None };
+        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(1000) };
+        let summary = codex.config_summary();
+        assert_eq!(summary, ConfigSummary {
+            field_order: FIELD_ORDER_SECP,
+            input_halt: false,
+            input_complexity_lim: None,
+            verification_halt: true,
+            verification_complexity_lim: Some(1000),
+        });
+
+        let mut other = codex.clone();
+        other.name = tiny_s!("Other");
+        assert_eq!(other.config_summary(), summary);
+        other.verification_config.complexity_lim = Some(2000);
+        assert_ne!(other.config_summary(), summary);
+    }
+
+    #[test]
+    fn eq_by_id() {
+        let codex = Codex::strict_dumb();
+        let mut other = codex.clone();
+        assert!(codex.eq_by_id(&other));
+        other.timestamp = 1;
+        assert!(!codex.eq_by_id(&other));
+        assert_eq!(codex.eq_by_id(&other), codex == other);
+    }
+
+    #[test]
+    fn merge_verifiers() {
+        let site1 = LibSite::new(lib_success().lib_id(), 0);
+        let site2 = LibSite::new(lib_failure_one().lib_id(), 0);
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! { 0 => site1, 1 => site2 };
+        let mut other = Codex::strict_dumb();
+        other.name = tiny_s!("Other");
+        other.verifiers = tiny_bmap! { 1 => site2, 2 => site1 };
+        other.witness_banks = tiny_bmap! { site2.lib_id => WitnessBank::Secondary };
+
+        let merged = codex.merge_verifiers(&other).unwrap();
+        assert_eq!(merged.verifiers, tiny_bmap! { 0 => site1, 1 => site2, 2 => site1 });
+        assert_eq!(merged.witness_banks, other.witness_banks);
+        assert_eq!(merged.name, codex.name);
+
+        other.verifiers = tiny_bmap! { 0 => site2 };
+        assert_eq!(codex.merge_verifiers(&other), Err(MergeError::CallIdConflict(0)));
+
+        codex.witness_banks = tiny_bmap! { site2.lib_id => WitnessBank::Tertiary };
+        other.verifiers.clear();
+        assert_eq!(
+            codex.merge_verifiers(&other),
+            Err(MergeError::WitnessBankConflict(site2.lib_id))
+        );
+
+        other.field_order = FIELD_ORDER_SECP;
+        assert_eq!(codex.merge_verifiers(&other), Err(MergeError::FieldOrderMismatch));
+        other.field_order = codex.field_order;
+        other.verification_config.halt = !codex.verification_config.halt;
+        assert_eq!(codex.merge_verifiers(&other), Err(MergeError::ConfigMismatch));
+        other.verification_config = codex.verification_config;
+        other.input_bridge = 1;
+        assert_eq!(codex.merge_verifiers(&other), Err(MergeError::FlagsMismatch));
+
+        let mut full = Codex::strict_dumb();
+        full.verifiers = TinyOrdMap::from_iter_checked((0..255).map(|call_id| (call_id, site1)));
+        other = Codex::strict_dumb();
+        other.verifiers = tiny_bmap! { 255 => site1 };
+        assert_eq!(full.merge_verifiers(&other), Err(MergeError::TooManyVerifiers));
+    }
+
+    fn lib_success() -> Lib { Lib::assemble(&aluasm! { stop; }).unwrap() }
+    fn lib_failure_none() -> Lib {
+        Lib::assemble(&zk_aluasm! {
+            clr     E1;
+            test    E2;
+            chk     CO;
+        })
+        .unwrap()
+    }
+    fn lib_failure_one() -> Lib {
+        Lib::assemble(&zk_aluasm! {
+            put     E1, 1;
+            test    E2;
+            chk     CO;
+        })
+        .unwrap()
+    }
+    const SECRET: u8 = 48;
+    fn lib_lock() -> Lib {
+        assert_eq!(SECRET, 48);
+        Lib::assemble(&uasm! {
+            stop;
+            put     EA, 48; // Secret value
+
+            eq      EA, E1; // Must be provided as a token of authority
+            put     E8, 1;  // Failure #1
+            chk     CO;
+
+            eq      EA, E2; // Must be provided in witness
+            put     E8, 2;  // Failure #2
+            chk     CO;
+
+            put     E8, 3;  // Failure #3
+            test    E3;     // The rest must be empty
+            not     CO;
+            chk     CO;
+            test    E4;
+            not     CO;
+            chk     CO;
+            test    E5;
+            not     CO;
+            chk     CO;
+        })
+        .unwrap()
+    }
+
+    /// Constructs a codex running the `verifier` for the call 0, and an operation of the test
+    /// contract performing the call.
+    fn fixture(verifier: LibSite) -> (Codex, ContractId, Operation) {
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.verifiers = tiny_bmap! { 0 => verifier };
+        let contract_id = ContractId::from_byte_array(Sha256::digest(b"test"));
+        let mut operation = Operation::strict_dumb();
+        operation.contract_id = contract_id;
+        (codex, contract_id, operation)
+    }
+
+    fn test_stand(modify: impl FnOnce(&mut Codex, &mut Operation, &mut DumbMemory)) {
+        test_stand_script(lib_success(), modify)
+    }
+
+    fn test_stand_script(
+        repo: Lib,
+        modify: impl FnOnce(&mut Codex, &mut Operation, &mut DumbMemory),
+    ) {
+        test_stand_repo(repo.lib_id(), repo, modify)
+    }
+
+    fn test_stand_repo(
+        lib_id: LibId,
+        repo: impl LibRepo,
+        modify: impl FnOnce(&mut Codex, &mut Operation, &mut DumbMemory),
+    ) {
+        let (mut codex, contract_id, mut operation) = fixture(LibSite::new(lib_id, 0));
+        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+        codex.input_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+        operation.call_id = 0;
+        let mut memory = DumbMemory::default();
+
+        modify(&mut codex, &mut operation, &mut memory);
+
+        codex
+            .verify(contract_id, operation, &memory, &repo)
+            .unwrap();
+    }
+
+    #[test]
+    fn verify_dumb() { test_stand(|_codex, _operation, _memory| {}); }
+
+    #[test]
+    #[should_panic(
+        expected = "WrongContract { expected: ContractId(Array<32>(9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08)), found: ContractId(Array<32>(8810ad581e59f2bc3928b261707a71308f7e139eb04820366dc4d5c18d980225))"
+    )]
+    fn verify_contract_id() {
+        test_stand(|_codex, operation, _memory| {
+            operation.contract_id = ContractId::from_byte_array(Sha256::digest(b"wrong"));
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "NoImmutableInput(CellAddr { opid: Opid(Array<32>(0000000000000000000000000000000000000000000000000000000000000000)), pos: 0 })"
+    )]
+    fn verify_no_immutable() {
+        test_stand(|_codex, operation, _memory| {
+            operation.immutable_in = small_vec![CellAddr::strict_dumb()];
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = " NoReadOnceInput(CellAddr { opid: Opid(Array<32>(0000000000000000000000000000000000000000000000000000000000000000)), pos: 0 })"
+    )]
+    fn verify_no_destructible() {
+        test_stand(|_codex, operation, _memory| {
+            operation.destructible_in =
+                small_vec![Input { addr: CellAddr::strict_dumb(), witness: none!() }];
+        });
+    }
+
+    #[test]
+    fn verify_immutable() {
+        test_stand(|_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.immutable.insert(addr, StateValue::strict_dumb());
+            operation.immutable_in = small_vec![addr];
+        });
+    }
+
+    #[test]
+    fn verify_immutable_not_destroyed() {
+        test_stand(|_codex, operation, memory| {
+            let spent = CellAddr::new(Opid::from_byte_array([1u8; 32]), 0);
+            let shared = CellAddr::new(Opid::from_byte_array([2u8; 32]), 3);
+            memory.destructible.insert(spent, StateCell::strict_dumb());
+            memory.immutable.insert(shared, StateValue::strict_dumb());
+            operation.destructible_in = small_vec![Input { addr: spent, witness: none!() }];
+            operation.immutable_in = small_vec![shared];
+        });
+    }
+
+    #[test]
+    fn verify_absent_immutable_as_none() {
+        let check = Lib::assemble(&uasm! {
+            ldi     immutable;
+            chk     CO;
+            test    EA;
+            not     CO;
+            chk     CO;
+        })
+        .unwrap();
+        test_stand_script(check, |codex, operation, _memory| {
+            codex.absent_immutable_as_none = true;
+            operation.immutable_in = small_vec![CellAddr::strict_dumb()];
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "NoImmutableInput")]
+    fn verify_absent_immutable_strict() {
+        test_stand(|codex, operation, _memory| {
+            codex.absent_immutable_as_none = false;
+            operation.immutable_in = small_vec![CellAddr::strict_dumb()];
+        });
+    }
+
+    #[test]
+    fn verify_destructible() {
+        test_stand(|_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell::strict_dumb());
+            operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+        });
+    }
+
+    #[test]
+    fn verify_protected_dumb() {
+        test_stand_script(lib_lock(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::strict_dumb(),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 0)),
+            });
+            operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+        });
+    }
+
+    #[test]
+    fn dry_run_output_lock() {
+        let lib = lib_lock();
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        let mut cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: None,
+        };
+        let witness = StateValue::Single { first: fe256::from(SECRET) };
+        assert_eq!(codex.dry_run_output_lock(&cell, StateValue::None, &lib), Ok(()));
+
+        cell.lock = Some(LibSite::new(lib.lib_id(), 1));
+        assert_eq!(codex.dry_run_output_lock(&cell, witness, &lib), Ok(()));
+        assert_eq!(
+            codex.dry_run_output_lock(&cell, StateValue::None, &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+        cell.auth = AuthToken::strict_dumb();
+        assert_eq!(
+            codex.dry_run_output_lock(&cell, witness, &lib),
+            Err(CallError::Lock(Some(fe256::from(1u8))))
+        );
+    }
+
+    #[test]
+    fn verify_protected() {
+        test_stand_script(lib_lock(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::from(fe256::from(SECRET)),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
+            });
+            operation.destructible_in = small_vec![Input {
+                addr,
+                witness: StateValue::Single { first: fe256::from(SECRET) }
+            }];
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000001)))"
+    )]
+    fn verify_protected_failure1() {
+        test_stand_script(lib_lock(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::strict_dumb(),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
+            });
+            operation.destructible_in = small_vec![Input {
+                addr,
+                witness: StateValue::Single { first: fe256::from(SECRET) }
+            }];
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000002)))"
+    )]
+    fn verify_protected_failure2() {
+        test_stand_script(lib_lock(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::from(fe256::from(SECRET)),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
+            });
+            operation.destructible_in = small_vec![Input { addr, witness: StateValue::None }];
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000003)))"
+    )]
+    fn verify_protected_failure3() {
+        test_stand_script(lib_lock(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::from(fe256::from(SECRET)),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
+            });
+            operation.destructible_in = small_vec![Input {
+                addr,
+                witness: StateValue::Double {
+                    first: fe256::from(SECRET),
+                    second: fe256::from(SECRET)
+                }
+            }];
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "FieldMembership { location: Witness(0, 1) }")]
+    fn verify_witness_out_of_field() {
+        test_stand_script(lib_lock(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::from(fe256::from(SECRET)),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
+            });
+            operation.destructible_in = small_vec![Input {
+                addr,
+                witness: StateValue::Double {
+                    first: fe256::from(SECRET),
+                    second: fe256::from(FIELD_ORDER_SECP),
+                }
+            }];
+        });
+    }
+
+    #[test]
+    fn verify_witness_bank() {
+        let lock = Lib::assemble(&zk_aluasm! {
+            put     E8, 1;
+            test    E2;
+            not     CO;
+            chk     CO;
+            put     E8, 2;
+            put     E7, 48;
+            eq      EE, E7;
+            chk     CO;
+        })
+        .unwrap();
+        let lock_id = lock.lib_id();
+        let check = lib_success();
+        let check_id = check.lib_id();
+        let repo = HashMap::from([(lock_id, lock), (check_id, check)]);
+        test_stand_repo(check_id, repo, |codex, operation, memory| {
+            codex.witness_banks = tiny_bmap! { lock_id => WitnessBank::Tertiary };
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::strict_dumb(),
+                lock: Some(LibSite::new(lock_id, 0)),
+            });
+            operation.destructible_in = small_vec![Input {
+                addr,
+                witness: StateValue::Single { first: fe256::from(48u8) }
+            }];
+        });
+    }
+
+    #[test]
+    fn witness_bank_registers() {
+        assert_eq!(WitnessBank::default(), WitnessBank::Primary);
+        assert_eq!(WitnessBank::Primary.registers(), [RegE::E2, RegE::E3, RegE::E4, RegE::E5]);
+        assert_eq!(WitnessBank::Secondary.registers()[0], RegE::EA);
+        assert_eq!(WitnessBank::Tertiary.registers()[3], RegE::EH);
+    }
+
+    #[test]
+    #[should_panic(expected = "ScriptUnspecified")]
+    fn verify_script_failure_unspecified() {
+        test_stand_script(lib_failure_none(), |_codex, _operation, _memory| {});
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Script(fe256(0x0000000000000000000000000000000000000000000000000000000000000001))"
+    )]
+    fn verify_script_failure_code() {
+        test_stand_script(lib_failure_one(), |_codex, _operation, _memory| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "NotFound(0)")]
+    fn verify_no_verifier() {
+        test_stand_script(lib_success(), |codex, _operation, _memory| {
+            codex.verifiers.clear();
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "LibNotFound")]
+    fn verify_lib_absent() {
+        test_stand_script(lib_success(), |codex, _operation, _memory| {
+            codex
+                .verifiers
+                .insert(0, LibSite::new(lib_failure_one().lib_id(), 0))
+                .unwrap();
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "LibNotFound")]
+    fn verify_lock_lib_absent() {
+        test_stand_script(lib_success(), |_codex, operation, memory| {
+            let addr = CellAddr::strict_dumb();
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::from(fe256::from(SECRET)),
+                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
+            });
+            operation.destructible_in = small_vec![Input {
+                addr,
+                witness: StateValue::Single { first: fe256::from(SECRET) }
+            }];
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ScriptUnspecified")]
+    fn verify_lib_wrong_pos() {
+        test_stand_script(lib_success(), |codex, _operation, _memory| {
+            codex
+                .verifiers
+                .insert(0, LibSite::new(lib_success().lib_id(), 1))
+                .unwrap();
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "The library returned by the `LibRepo` provided for the contract \
+                               operation verification doesn't match the requested library id. \
+                               This error indicates that the software using the consensus \
+                               verification is invalid or compromised.")]
+    fn verify_wrong_lib_id() {
+        struct InvalidRepo(Lib);
+        impl LibRepo for InvalidRepo {
+            fn get_lib(&self, _lib_id: LibId) -> Option<&Lib> { Some(&self.0) }
+        }
+        let repo = InvalidRepo(lib_failure_one());
+        test_stand_repo(lib_success().lib_id(), repo, |_codex, _operation, _memory| {});
+    }
+
+    fn test_metered(repo: &impl LibRepo, entry_point: LibSite) -> VerificationReport {
+        let (mut codex, contract_id, operation) = fixture(entry_point);
+        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+
+        let memory = DumbMemory::default();
+        let expected = codex.verify(contract_id, operation.clone(), &memory, repo);
+        let (res, report) = codex.verify_metered(contract_id, operation, &memory, repo, default!());
+        assert_eq!(report.failure, expected.as_ref().err().copied());
+        assert_eq!(res, expected);
+        assert_eq!(report.lib_cycles.values().sum::<u64>(), report.cycles);
+        assert_eq!(report.executed_verifier, Some(entry_point));
+        report
+    }
+
+    #[test]
+    fn verify_metered_single_lib() {
+        let lib = lib_failure_one();
+        let report = test_metered(&lib, LibSite::new(lib.lib_id(), 0));
+        assert_ne!(report.cycles, 0);
+        assert_eq!(report.lib_cycles.keys().copied().collect::<Vec<_>>(), vec![lib.lib_id()]);
+    }
+
+    #[test]
+    fn verify_metered_deprecated() {
+        let lib = lib_success();
+        let (mut codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        codex
+            .verifiers
+            .insert(1, LibSite::new(lib.lib_id(), 0))
+            .unwrap();
+        let memory = DumbMemory::default();
+        let options = VerifyOptions { deprecated: &[1], ..default!() };
+
+        let (res, report) =
+            codex.verify_metered(contract_id, operation.clone(), &memory, &lib, options);
+        assert!(res.is_ok());
+        assert!(!report.deprecated_call);
+
+        operation.call_id = 1;
+        let (res, report) =
+            codex.verify_metered(contract_id, operation.clone(), &memory, &lib, options);
+        assert!(res.is_ok());
+        assert!(report.deprecated_call);
+        let (res, report) =
+            codex.verify_metered(contract_id, operation.clone(), &memory, &lib, default!());
+        assert!(res.is_ok());
+        assert!(!report.deprecated_call);
+
+        operation.call_id = 2;
+        let (res, report) = codex.verify_metered(contract_id, operation, &memory, &lib, options);
+        assert_eq!(res, Err(CallError::NotFound(2)));
+        assert_eq!(report.executed_verifier, None);
+    }
+
+    #[test]
+    fn verify_metered_per_lib() {
+        let callee = Lib::assemble(&zk_aluasm! {
+            put     E2, 7;
+            put     E3, 8;
+            add     E2, E3;
+            ret;
+        })
+        .unwrap();
+        let callee_id = callee.lib_id();
+        let caller = Lib::assemble(&zk_aluasm! {
+            call    callee_id, 0;
+            put     E4, 1;
+            stop;
+        })
+        .unwrap();
+        let caller_id = caller.lib_id();
+        let repo = HashMap::from([(callee_id, callee), (caller_id, caller)]);
+
+        let report = test_metered(&repo, LibSite::new(caller_id, 0));
+        assert_eq!(report.lib_cycles.len(), 2);
+        assert_ne!(report.lib_cycles[&callee_id], 0);
+        assert_ne!(report.lib_cycles[&caller_id], 0);
+    }
+
+    #[test]
+    fn verify_at_height() {
+        #[derive(Default)]
+        struct History(DumbMemory, HashMap<CellAddr, (u64, Option<u64>)>);
+        impl Memory for History {
+            fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+                self.0.destructible(addr)
+            }
+            fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.0.immutable(addr) }
+        }
+        impl HistoricMemory for History {
+            fn created_at(&self, addr: CellAddr) -> Option<u64> {
+                self.1.get(&addr).map(|(created, _)| *created)
+            }
+            fn spent_at(&self, addr: CellAddr) -> Option<u64> {
+                self.1.get(&addr).and_then(|(_, spent)| *spent)
+            }
+        }
+
+        let lock = Lib::assemble(&zk_aluasm! {
+            put     E8, 1;
+            test    E7;
+            chk     CO;
+        })
+        .unwrap();
+        let lock_id = lock.lib_id();
+        let lib = lib_success();
+        let lib_id = lib.lib_id();
+        let repo = HashMap::from([(lock_id, lock), (lib_id, lib)]);
+
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib_id, 0));
+
+        let mut memory = History::default();
+        let addr = CellAddr::new(Opid::strict_dumb(), 0);
+        let shared = CellAddr::new(Opid::strict_dumb(), 1);
+        memory.0.destructible.insert(addr, StateCell {
+            data: StateValue::None,
+            auth: AuthToken::strict_dumb(),
+            lock: Some(LibSite::new(lock_id, 0)),
+        });
+        memory.0.immutable.insert(shared, StateValue::None);
+        memory.1.insert(addr, (50, Some(120)));
+        memory.1.insert(shared, (110, None));
+        operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+        operation.immutable_in = small_vec![shared];
+
+        let verify = |memory: &History, height| {
+            codex
+                .verify_at_height(contract_id, operation.clone(), memory, &repo, height)
+                .map(|_| ())
+        };
+        assert_eq!(verify(&memory, 120), Ok(()));
+        assert_eq!(verify(&memory, 110), Ok(()));
+        assert_eq!(verify(&memory, 121), Err(CallError::NoReadOnceInput(addr)));
+        assert_eq!(verify(&memory, 109), Err(CallError::NoImmutableInput(shared)));
+        memory.1.insert(shared, (40, None));
+        assert_eq!(verify(&memory, 49), Err(CallError::NoReadOnceInput(addr)));
+        assert_eq!(
+            codex
+                .verify(contract_id, operation, &HeightScopedMemory::new(&memory, 100), &repo)
+                .map(|_| ()),
+            Err(CallError::Lock(Some(fe256::from(1u8))))
+        );
+    }
+
+    #[test]
+    fn prev_output_memory() {
+        let lib = lib_success();
+        let (codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let mut prev = Operation::strict_dumb();
+        prev.contract_id = contract_id;
+        prev.destructible_out = small_vec![StateCell::strict_dumb(), StateCell::strict_dumb()];
+        prev.immutable_out = small_vec![StateData::new(1u8, 2u8)];
+        let memory = PrevOutputMemory::new(&prev);
+        assert_eq!(memory.opid(), prev.opid());
+        assert_eq!(memory.immutable(memory.addr(0)), Some(prev.immutable_out[0].value));
+
+        let mut operation = Operation::strict_dumb();
+        operation.contract_id = contract_id;
+        operation.destructible_in = small_vec![Input { addr: memory.addr(1), witness: none!() }];
+        operation.immutable_in = small_vec![memory.addr(0)];
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+
+        operation.destructible_in = small_vec![Input { addr: memory.addr(2), witness: none!() }];
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::NoReadOnceInput(memory.addr(2)))
+        );
+        let other = CellAddr::new(Opid::strict_dumb(), 0);
+        operation.destructible_in = none!();
+        operation.immutable_in = small_vec![other];
+        assert_eq!(
+            codex.verify(contract_id, operation, &memory, &lib),
+            Err(CallError::NoImmutableInput(other))
+        );
+    }
+
+    #[test]
+    fn test_witness() {
+        let lib = lib_lock();
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        let mut cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        };
+
+        assert_eq!(codex.test_witness(&cell, &[fe256::from(SECRET)], &lib), Ok(()));
+        assert_eq!(
+            codex.test_witness(&cell, &[], &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+        assert_eq!(
+            codex.test_witness(&cell, &[fe256::from(1u8)], &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+        let out_of_field = fe256::from(FIELD_ORDER_SECP);
+        assert_eq!(
+            codex.test_witness(&cell, &[out_of_field], &lib),
+            Err(CallError::FieldMembership { location: ElementLocation::Witness(0, 0) })
+        );
+        assert_eq!(
+            codex.test_witness(&cell, &[fe256::from(SECRET); 5], &lib),
+            Err(CallError::WitnessTooLong(5))
+        );
+
+        cell.lock = None;
+        assert_eq!(codex.test_witness(&cell, &[], &lib), Ok(()));
+    }
+
+    #[test]
+    fn verify_metered_consulted_immutable() {
+        let lib = Lib::assemble(&uasm! {
+            ldi     immutable;
+            chk     CO;
+            stop;
+        })
+        .unwrap();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let mut memory = DumbMemory::default();
+        let addrs = [1u16, 2].map(|pos| CellAddr::new(Opid::strict_dumb(), pos));
+        for addr in addrs {
+            memory.immutable.insert(addr, StateValue::None);
+        }
+        operation.immutable_in = small_vec![addrs[0], addrs[1]];
+
+        let (res, report) =
+            codex.verify_metered(contract_id, operation.clone(), &memory, &lib, default!());
+        assert!(res.is_ok());
+        assert_eq!(report.consulted_immutable, small_bset![addrs[0]]);
+
+        operation.immutable_in = none!();
+        let (res, report) = codex.verify_metered(contract_id, operation, &memory, &lib, default!());
+        assert!(res.is_err());
+        assert!(report.consulted_immutable.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "profiling")]
+    fn verify_profile() {
+        let callee = Lib::assemble(&zk_aluasm! {
+            put     E2, 7;
+            put     E3, 8;
+            add     E2, E3;
+            ret;
+        })
+        .unwrap();
+        let callee_id = callee.lib_id();
+        let caller = Lib::assemble(&zk_aluasm! {
+            call    callee_id, 0;
+            put     E4, 1;
+            stop;
+        })
+        .unwrap();
+        let caller_id = caller.lib_id();
+        let repo = HashMap::from([(callee_id, callee), (caller_id, caller)]);
+        let entry_point = LibSite::new(caller_id, 0);
+        let report = test_metered(&repo, entry_point);
+
+        let (mut codex, contract_id, operation) = fixture(entry_point);
+        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+
+        let (res, profile) =
+            codex.verify_profile(contract_id, operation, &DumbMemory::default(), &repo, default!());
+        assert!(res.is_ok());
+        assert_eq!(profile.cycles(), report.cycles);
+        assert_eq!(profile.sites().len(), 7);
+        assert_eq!(profile.sites().values().sum::<u64>(), report.cycles);
+
+        let caller_frame = entry_point.to_string();
+        let callee_frame = LibSite::new(callee_id, 0).to_string();
+        assert_eq!(profile.stacks().len(), 2);
+        assert_eq!(profile.stacks()[&vec![caller_frame.clone()]], report.lib_cycles[&caller_id]);
+        assert_eq!(
+            profile.stacks()[&vec![caller_frame.clone(), callee_frame.clone()]],
+            report.lib_cycles[&callee_id]
+        );
+        assert_eq!(
+            profile.to_folded(),
+            format!(
+                "{caller_frame} {}\n{caller_frame};{callee_frame} {}\n",
+                report.lib_cycles[&caller_id], report.lib_cycles[&callee_id]
+            )
+        );
+    }
+
+    #[test]
+    fn verify_metered_lib_absent() {
+        let lib = lib_success();
+        let report = test_metered(&lib, LibSite::new(lib_failure_one().lib_id(), 0));
+        assert_eq!(report.cycles, 0);
+    }
+
+    #[test]
+    fn verify_genesis() {
+        let lib = lib_success();
+        let (codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        let operation = Genesis::strict_dumb().to_operation(contract_id);
+        let opid = operation.opid();
+        let verified = codex.verify_genesis(contract_id, operation, &lib).unwrap();
+        assert_eq!(verified.opid(), opid);
+    }
+
+    #[test]
+    fn verify_genesis_inputs() {
+        let lib = lib_success();
+        let (codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        let mut operation = Genesis::strict_dumb().to_operation(contract_id);
+        operation.immutable_in = small_vec![CellAddr::strict_dumb()];
+        assert_eq!(
+            codex.verify_genesis(contract_id, operation.clone(), &lib),
+            Err(CallError::NotGenesis)
+        );
+        operation.immutable_in.clear();
+        operation.destructible_in =
+            small_vec![Input { addr: CellAddr::strict_dumb(), witness: none!() }];
+        assert_eq!(codex.verify_genesis(contract_id, operation, &lib), Err(CallError::NotGenesis));
+    }
+
+    fn lib_bridge_lock() -> Lib {
+        Lib::assemble(&zk_aluasm! {
+            put     E6, 5;
+            stop;
+        })
+        .unwrap()
+    }
+    fn lib_bridge_check() -> Lib {
+        Lib::assemble(&zk_aluasm! {
+            put     E7, 5;
+            eq      E7, E6;
+            chk     CO;
+        })
+        .unwrap()
+    }
+
+    fn test_timed(time: Option<u64>) {
+        let lock = Lib::assemble(&zk_aluasm! {
+            put     E8, 1;
+            test    E7;
+            chk     CO;
+            put     E8, 2;
+            put     E6, 500000;
+            eq      E6, E7;
+            chk     CO;
+        })
+        .unwrap();
+        let lock_id = lock.lib_id();
+        let lib = lib_success();
+        let lib_id = lib.lib_id();
+        let repo = HashMap::from([(lock_id, lock), (lib_id, lib)]);
+
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib_id, 0));
+        let mut memory = DumbMemory::default();
+        let addr = CellAddr::new(Opid::strict_dumb(), 0);
+        memory.destructible.insert(addr, StateCell {
+            data: StateValue::None,
+            auth: AuthToken::strict_dumb(),
+            lock: Some(LibSite::new(lock_id, 0)),
+        });
+        operation
+            .destructible_in
+            .push(Input { addr, witness: none!() })
+            .unwrap();
+
+        match time {
+            Some(time) => codex.verify_timed(contract_id, operation, &memory, &repo, time),
+            None => codex.verify(contract_id, operation, &memory, &repo),
+        }
+        .unwrap();
+    }
+
+    fn test_witness_presence(witness: StateValue, presence: u8) {
+        let lock = Lib::assemble(&zk_aluasm! {
+            put     E8, 1;
+            put     EA, presence;
+            eq      EA, E6;
+            chk     CO;
+        })
+        .unwrap();
+        let lock_id = lock.lib_id();
+        let lib = lib_success();
+        let repo = HashMap::from([(lock_id, lock), (lib.lib_id(), lib.clone())]);
+        test_stand_repo(lib.lib_id(), repo, |_codex, operation, memory| {
+            let addr = CellAddr::new(Opid::strict_dumb(), 0);
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::strict_dumb(),
+                lock: Some(LibSite::new(lock_id, 0)),
+            });
+            operation
+                .destructible_in
+                .push(Input { addr, witness })
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn verify_witness_presence() {
+        let zero = fe256::from(0u8);
+        test_witness_presence(StateValue::None, 0b0000);
+        test_witness_presence(StateValue::Single { first: zero }, 0b0001);
+        test_witness_presence(StateValue::Double { first: zero, second: zero }, 0b0011);
+        test_witness_presence(
+            StateValue::Quadripple { first: zero, second: zero, third: zero, fourth: zero },
+            0b1111,
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000001)))"
+    )]
+    fn verify_witness_presence_mismatch() {
+        test_witness_presence(StateValue::Single { first: fe256::from(0u8) }, 0b0011);
+    }
+
+    #[test]
+    fn verify_timed() { test_timed(Some(500000)); }
+
+    #[test]
+    #[should_panic(
+        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000002)))"
+    )]
+    fn verify_timed_mismatch() { test_timed(Some(499999)); }
+
+    #[test]
+    #[should_panic(
+        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000001)))"
+    )]
+    fn verify_timed_absent() { test_timed(None); }
+
+    #[test]
+    fn verify_report_locks() {
+        let lock = Lib::assemble(&zk_aluasm! {
+            put     E8, 1;
+            put     EA, 48;
+            eq      EA, E2;
+            chk     CO;
+        })
+        .unwrap();
+        let lock_id = lock.lib_id();
+        let lib = lib_success();
+        let lib_id = lib.lib_id();
+        let repo = HashMap::from([(lock_id, lock), (lib_id, lib)]);
+
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib_id, 0));
+        let mut memory = DumbMemory::default();
+        let valid = CellAddr::new(Opid::strict_dumb(), 0);
+        let invalid = CellAddr::new(Opid::strict_dumb(), 1);
+        for (addr, witness) in [(valid, 48u8), (invalid, 49u8)] {
+            memory.destructible.insert(addr, StateCell {
+                data: StateValue::None,
+                auth: AuthToken::strict_dumb(),
+                lock: Some(LibSite::new(lock_id, 0)),
+            });
+            operation
+                .destructible_in
+                .push(Input {
+                    addr,
+                    witness: StateValue::Single { first: fe256::from(witness) },
+                })
+                .unwrap();
+        }
+
+        let (res, locks) = codex.verify_report_locks(contract_id, &operation, &memory, &repo);
+        assert_eq!(res, Ok(()));
+        assert_eq!(locks, vec![
+            (valid, Ok(())),
+            (invalid, Err(CallError::Lock(Some(fe256::from(1u8))))),
+        ]);
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &repo)
+            .is_err());
+
+        let (res, locks) =
+            codex.verify_report_locks(ContractId::strict_dumb(), &operation, &memory, &repo);
+        assert!(matches!(res, Err(CallError::WrongContract { .. })));
+        assert!(locks.is_empty());
+    }
+
+    fn test_bridge(input_bridge: u16, locks: usize) {
+        let lock = lib_bridge_lock();
+        let lock_id = lock.lib_id();
+        let check = lib_bridge_check();
+        let check_id = check.lib_id();
+        let repo = HashMap::from([(lock_id, lock), (check_id, check)]);
+        test_stand_repo(check_id, repo, |codex, operation, memory| {
+            codex.input_bridge = input_bridge;
+            for pos in 0..locks as u16 {
+                let addr = CellAddr::new(Opid::strict_dumb(), pos);
+                memory.destructible.insert(addr, StateCell {
+                    data: StateValue::None,
+                    auth: AuthToken::strict_dumb(),
+                    lock: Some(LibSite::new(lock_id, 0)),
+                });
+                operation
+                    .destructible_in
+                    .push(Input { addr, witness: none!() })
+                    .unwrap();
+            }
+        });
+    }
+
+    fn output(first: u256) -> StateCell {
+        StateCell {
+            data: StateValue::Single { first: fe256::from(first) },
+            auth: AuthToken::strict_dumb(),
+            lock: None,
+        }
+    }
+
+    #[test]
+    fn verify_indeterminate() {
+        let failure = lib_failure_one();
+        let unspecified = lib_failure_none();
+        let repo = HashMap::from([
+            (failure.lib_id(), failure.clone()),
+            (unspecified.lib_id(), unspecified.clone()),
+        ]);
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.verifiers = tiny_bmap! {
+            0 => LibSite::new(failure.lib_id(), 0),
+            1 => LibSite::new(unspecified.lib_id(), 0)
+        };
+        let contract_id = ContractId::strict_dumb();
+        let mut operation = Operation::strict_dumb();
+        operation.contract_id = contract_id;
+        let memory = DumbMemory::default();
+
+        let err = Err(CallError::Script(fe256::from(1u8)));
+        assert_eq!(codex.verify(contract_id, operation.clone(), &memory, &repo), err);
+        codex.indeterminate_code = Some(fe256::from(2u8));
+        assert_eq!(codex.verify(contract_id, operation.clone(), &memory, &repo), err);
+        codex.indeterminate_code = Some(fe256::from(1u8));
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &repo),
+            Err(CallError::Indeterminate)
+        );
+
+        operation.call_id = 1;
+        assert_eq!(
+            codex.verify(contract_id, operation, &memory, &repo),
+            Err(CallError::ScriptUnspecified)
+        );
+    }
+
+    #[test]
+    fn output_sum() {
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        let outputs = [output(u256::from(10u8)), output(u256::from(20u8)), StateCell {
+            data: StateValue::None,
+            auth: AuthToken::strict_dumb(),
+            lock: None,
+        }];
+        assert_eq!(codex.output_sum(&outputs), None);
+
+        codex.output_sum = Some(0);
+        assert_eq!(codex.output_sum(&outputs), Some(fe256::from(30u8)));
+        assert_eq!(codex.output_sum(&[]), Some(fe256::from(0u8)));
+        codex.output_sum = Some(1);
+        assert_eq!(codex.output_sum(&outputs), Some(fe256::from(0u8)));
+        codex.output_sum = Some(4);
+        assert_eq!(codex.output_sum(&outputs), Some(fe256::from(0u8)));
+
+        codex.output_sum = Some(0);
+        let wrapping = [output(FIELD_ORDER_SECP - u256::ONE), output(u256::from(2u8))];
+        assert_eq!(codex.output_sum(&wrapping), Some(fe256::from(1u8)));
+        let unreduced = [output(FIELD_ORDER_SECP + u256::from(5u8))];
+        assert_eq!(codex.output_sum(&unreduced), Some(fe256::from(5u8)));
+    }
+
+    #[test]
+    fn verify_output_sum() {
+        let lib = Lib::assemble(&zk_aluasm! {
+            put     E1, 1;
+            put     EA, 30;
+            eq      EA, EH;
+            chk     CO;
+        })
+        .unwrap();
+        let outputs = [output(u256::from(10u8)), output(u256::from(20u8))];
+        test_stand_script(lib, |codex, operation, _| {
+            codex.output_sum = Some(0);
+            operation.destructible_out = SmallVec::from_checked(outputs.to_vec());
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Script(fe256(0x0000000000000000000000000000000000000000000000000000000000000001))"
+    )]
+    fn verify_output_sum_absent() {
+        let lib = Lib::assemble(&zk_aluasm! {
+            put     E1, 1;
+            put     EA, 30;
+            eq      EA, EH;
+            chk     CO;
+        })
+        .unwrap();
+        let outputs = [output(u256::from(10u8)), output(u256::from(20u8))];
+        test_stand_script(lib, |_, operation, _| {
+            operation.destructible_out = SmallVec::from_checked(outputs.to_vec());
+        });
+    }
+
+    #[test]
+    fn bridged_registers() {
+        let mut codex = Codex::strict_dumb();
+        assert_eq!(codex.bridged_registers().count(), 0);
+        codex.input_bridge = 0b1000_0000_0010_0001;
+        assert_eq!(codex.bridged_registers().collect::<Vec<_>>(), vec![
+            RegE::E1,
+            RegE::E6,
+            RegE::EH
+        ]);
+    }
+
+    #[test]
+    fn verify_bridge() {
+        test_bridge(1 << 5, 1);
+        test_bridge(1 << 5, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ScriptUnspecified")]
+    fn verify_bridge_disabled() { test_bridge(0, 1); }
+
+    #[test]
+    #[should_panic(expected = "ScriptUnspecified")]
+    fn verify_bridge_no_lock() { test_bridge(1 << 5, 0); }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn verify_json() {
+        let lib = lib_failure_one();
+        let (mut codex, contract_id, mut operation) =
+            fixture(LibSite::new(lib_success().lib_id(), 0));
+        let memory = DumbMemory::default();
+
+        let repo = HashMap::from([(lib_success().lib_id(), lib_success())]);
+        let json = codex.verify_json(contract_id, operation.clone(), &memory, &repo);
+        let report = codex
+            .verify_metered(contract_id, operation.clone(), &memory, &repo, default!())
+            .1;
+        assert_eq!(json, format!(r#"{{"ok":true,"error":null,"cycles":{}}}"#, report.cycles));
+
+        operation.call_id = 1;
+        let json = codex.verify_json(contract_id, operation.clone(), &memory, &repo);
+        assert_eq!(json, r#"{"ok":false,"error":{"notFound":1},"cycles":0}"#);
+
+        codex.verifiers = tiny_bmap! { 1 => LibSite::new(lib.lib_id(), 0) };
+        let json = codex.verify_json(contract_id, operation, &memory, &lib);
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["ok"], false);
+        assert!(value["error"]["script"].is_string());
+    }
+
+    #[cfg(feature = "cbor")]
+    fn codex_sample() -> Codex {
+        let mut codex = Codex::strict_dumb();
+        codex.name = tiny_s!("Sample codex");
+        codex.timestamp = 1732529307;
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+        codex.input_bridge = 0b11;
+        codex.verifiers = tiny_bmap! {
+            0 => LibSite::new(lib_success().lib_id(), 0),
+            1 => LibSite::new(lib_failure_one().lib_id(), 2),
+        };
+        codex
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn cbor_roundtrip() {
+        let codex = codex_sample();
+        let cbor = codex.to_cbor();
+        let decoded = Codex::from_cbor(&cbor).unwrap();
+        assert_eq!(decoded.codex_id(), codex.codex_id());
+        assert_eq!(decoded.to_cbor(), cbor);
+
+        let strict = codex
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .unwrap();
+        let from_strict = Codex::from_strict_serialized::<{ u16::MAX as usize }>(strict).unwrap();
+        assert_eq!(from_strict.codex_id(), decoded.codex_id());
+        assert_eq!(from_strict.to_cbor(), cbor);
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn cbor_invalid() {
+        let cbor = codex_sample().to_cbor();
+        assert!(Codex::from_cbor(&cbor[..cbor.len() - 1]).is_err());
+        assert!(Codex::from_cbor(b"not a codex").is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let lib = lib_failure_one();
+        let lib_id = lib.lib_id();
+        let end = lib.code.len() as u16;
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers =
+            tiny_bmap! { 0 => LibSite::new(lib_id, 0), 1 => LibSite::new(lib_id, end - 1) };
+        assert_eq!(codex.validate(&lib), Ok(()));
+
+        for offset in [1, end] {
+            codex
+                .verifiers
+                .insert(1, LibSite::new(lib_id, offset))
+                .unwrap();
+            assert_eq!(
+                codex.validate(&lib),
+                Err(CodexError::InvalidEntryPoint { call_id: 1, lib: lib_id, offset })
+            );
+        }
+
+        let other_id = lib_success().lib_id();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(other_id, 0) };
+        assert_eq!(codex.validate(&lib), Err(CodexError::LibAbsent { call_id: 0, lib: other_id }));
+    }
+
+    #[test]
+    fn builder() {
+        let lib = lib_failure_one();
+        let lib_id = lib.lib_id();
+        let config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+        let codex = CodexBuilder::new(tiny_s!("Test"), Identity::default(), 1, FIELD_ORDER_SECP)
+            .verification_config(config)
+            .input_bridge(1)
+            .add_verifier(0, LibSite::new(lib_id, 0), &lib)
+            .unwrap()
+            .finish();
+        assert_eq!(codex.verification_config, config);
+        assert_eq!(codex.input_bridge, 1);
+        assert_eq!(codex.verifiers.get(&0), Some(&LibSite::new(lib_id, 0)));
+        assert_eq!(codex.validate(&lib), Ok(()));
+
+        let err = CodexBuilder::new(tiny_s!("Test"), Identity::default(), 1, FIELD_ORDER_SECP)
+            .add_verifier(0, LibSite::new(lib_id, 1), &lib)
+            .unwrap_err();
+        assert_eq!(err, CodexError::InvalidEntryPoint { call_id: 0, lib: lib_id, offset: 1 });
+    }
+
+    #[test]
+    fn verify_unlocked() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let addr = CellAddr::strict_dumb();
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(addr, StateCell {
+            data: StateValue::None,
+            auth: AuthToken::strict_dumb(),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        });
+        operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::Lock(Some(fe256::from(1u8))))
+        );
+        let opid = operation.opid();
+        let verified = codex
+            .verify_unlocked(contract_id, operation.clone(), &memory, &lib)
+            .unwrap();
+        assert_eq!(verified.opid(), opid);
+
+        memory.destructible.clear();
+        assert_eq!(
+            codex.verify_unlocked(contract_id, operation, &memory, &lib),
+            Err(CallError::NoReadOnceInput(addr))
+        );
+    }
+
+    #[test]
+    fn verify_unexpected_witness() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let addr = CellAddr::strict_dumb();
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(addr, StateCell::strict_dumb());
+        operation.destructible_in = small_vec![Input {
+            addr,
+            witness: StateValue::Single { first: fe256::from(1u8) }
+        }];
+
+        let strict = VerifyPolicy { reject_unexpected_witness: true, ..default!() };
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation.clone(), &memory, &lib, strict),
+            Err(CallError::UnexpectedWitness(addr))
+        );
+
+        operation.destructible_in = small_vec![Input { addr, witness: StateValue::None }];
+        assert!(codex
+            .verify_with_policy(contract_id, operation, &memory, &lib, strict)
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_non_field_witness() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let addr = CellAddr::strict_dumb();
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(addr, StateCell::strict_dumb());
+        let canonical = fe256::from(FIELD_ORDER_SECP - u256::ONE);
+        operation.destructible_in = small_vec![Input {
+            addr,
+            witness: StateValue::Double { first: canonical, second: fe256::from(FIELD_ORDER_SECP) }
+        }];
+
+        let strict = VerifyPolicy { reject_non_field_elements: true, ..default!() };
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation.clone(), &memory, &lib, strict),
+            Err(CallError::FieldMembership { location: ElementLocation::Witness(0, 1) })
+        );
+
+        operation.destructible_in =
+            small_vec![Input { addr, witness: StateValue::Single { first: canonical } }];
+        assert!(codex
+            .verify_with_policy(contract_id, operation, &memory, &lib, strict)
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_cached() {
+        let lib = lib_success();
+        let (mut codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        let addr = CellAddr::strict_dumb();
+        let mut operation = Operation::strict_dumb();
+        operation.contract_id = contract_id;
+        operation.immutable_in = small_vec![addr];
+        let mut memory = DumbMemory::default();
+        memory.immutable.insert(addr, StateValue::None);
+
+        fn assert_sync<T: Sync>(_: &T) {}
+        let cache = VerifyCache::new();
+        assert_sync(&cache);
+        let root1 = Bytes32::from_byte_array([1u8; 32]);
+        let root2 = Bytes32::from_byte_array([2u8; 32]);
+        assert!(codex
+            .verify_cached(contract_id, operation.clone(), &memory, &lib, root1, &cache)
+            .is_ok());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(codex.codex_id(), contract_id, operation.opid(), root1), Some(Ok(())));
+
+        // The memory change is not detected without changing the memory root
+        memory.immutable.clear();
+        assert!(codex
+            .verify_cached(contract_id, operation.clone(), &memory, &lib, root1, &cache)
+            .is_ok());
+
+        // Errors depending on the environment are not cached
+        assert_eq!(
+            codex.verify_cached(contract_id, operation.clone(), &memory, &lib, root2, &cache),
+            Err(CallError::NoImmutableInput(addr))
+        );
+        assert_eq!(cache.get(codex.codex_id(), contract_id, operation.opid(), root2), None);
+        assert_eq!(cache.len(), 1);
+        memory.immutable.insert(addr, StateValue::None);
+        assert!(codex
+            .verify_cached(contract_id, operation.clone(), &memory, &lib, root2, &cache)
+            .is_ok());
+        assert_eq!(cache.len(), 2);
+
+        // Script failures are cached
+        let failure = lib_failure_one();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(failure.lib_id(), 0) };
+        let err = codex
+            .verify_cached(contract_id, operation.clone(), &memory, &failure, root1, &cache)
+            .unwrap_err();
+        assert!(matches!(err, CallError::Script(_)));
+        assert_eq!(
+            cache.get(codex.codex_id(), contract_id, operation.opid(), root1),
+            Some(Err(err))
+        );
+        assert_eq!(cache.len(), 3);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn validate_call() {
+        let mut codex = Codex::strict_dumb();
+        let site = LibSite::new(lib_success().lib_id(), 0);
+        codex.verifiers = tiny_bmap! { 1 => site };
+        let mut operation = Operation::strict_dumb();
+        assert_eq!(codex.validate_call(&operation), Err(CallError::NotFound(0)));
+        operation.call_id = 1;
+        assert_eq!(codex.validate_call(&operation), Ok(site));
+    }
+
+    #[test]
+    fn verify_empty() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        operation.immutable_in = small_vec![CellAddr::strict_dumb()];
+        let mut memory = DumbMemory::default();
+        memory
+            .immutable
+            .insert(CellAddr::strict_dumb(), StateValue::None);
+
+        let strict = VerifyPolicy { reject_empty: true, ..default!() };
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        assert_eq!(codex.validate_operation(&operation, strict), Err(CallError::EmptyOperation));
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation.clone(), &memory, &lib, strict),
+            Err(CallError::EmptyOperation)
+        );
+
+        assert_eq!(
+            codex.verify_strict(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::EmptyOperation)
+        );
+
+        operation.immutable_out = small_vec![StateData::strict_dumb()];
+        assert_eq!(codex.validate_operation(&operation, strict), Ok(()));
+        assert!(codex
+            .verify_strict(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        assert!(codex
+            .verify_with_policy(contract_id, operation, &memory, &lib, strict)
+            .is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn verify_parallel() {
+        let lock = lib_lock();
+        let lock_id = lock.lib_id();
+        let lib = lib_success();
+        let lib_id = lib.lib_id();
+        let repo = HashMap::from([(lock_id, lock), (lib_id, lib)]);
+
+        let (codex, contract_id, _) = fixture(LibSite::new(lib_id, 0));
+
+        // Each case lists the inputs with an invalid witness and the inputs absent from memory
+        let cases: [(&[u16], &[u16]); 5] =
+            [(&[], &[]), (&[7], &[]), (&[12], &[10]), (&[5, 9], &[10]), (&[], &[0])];
+        for (invalid, absent) in cases {
+            let mut operation = Operation::strict_dumb();
+            operation.contract_id = contract_id;
+            let mut memory = DumbMemory::default();
+            for no in 0..20u16 {
+                let addr = CellAddr::new(Opid::strict_dumb(), no);
+                if !absent.contains(&no) {
+                    memory.destructible.insert(addr, StateCell {
+                        data: StateValue::Single { first: fe256::from(no) },
+                        auth: AuthToken::from(fe256::from(SECRET)),
+                        lock: Some(LibSite::new(lock_id, 1)),
+                    });
+                }
+                let secret = if invalid.contains(&no) { SECRET + 1 } else { SECRET };
+                operation
+                    .destructible_in
+                    .push(Input {
+                        addr,
+                        witness: StateValue::Single { first: fe256::from(secret) },
+                    })
+                    .unwrap();
+            }
+            let res = codex.verify(contract_id, operation.clone(), &memory, &repo);
+            assert_eq!(res.is_ok(), invalid.is_empty() && absent.is_empty());
+            assert_eq!(codex.verify_parallel(contract_id, operation, &memory, &repo), res);
+        }
+    }
+
+    #[test]
+    fn verify_with_preconditions() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        let memory = DumbMemory::default();
+
+        let required = RequiredCallIds(bset![0]);
+        let min = MinInputs { call_id: Some(0), destructible: 0, immutable: 1 };
+        assert!(codex
+            .verify_with_preconditions(contract_id, operation.clone(), &memory, &lib, &[&required])
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_preconditions(contract_id, operation.clone(), &memory, &lib, &[
+                &required, &min
+            ]),
+            Err(CallError::TooFewInputs)
+        );
+        operation.call_id = 1;
+        assert_eq!(
+            codex.verify_with_preconditions(contract_id, operation, &memory, &lib, &[
+                &required, &min
+            ]),
+            Err(CallError::CallNotAllowed(1))
+        );
+    }
+
+    #[test]
+    fn verify_with_postconditions() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        operation.destructible_out = small_vec![StateCell::strict_dumb(), StateCell::strict_dumb()];
+        let memory = DumbMemory::default();
+
+        assert!(codex
+            .verify_with_postconditions(contract_id, operation.clone(), &memory, &lib, &[
+                &OutputLocksResolve
+            ])
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_postconditions(contract_id, operation.clone(), &memory, &lib, &[
+                &OutputLocksResolve,
+                &UniqueOutputTokens
+            ]),
+            Err(CallError::DuplicateOutputToken(1))
+        );
+        operation.call_id = 1;
+        assert_eq!(
+            codex.verify_with_postconditions(contract_id, operation, &memory, &lib, &[
+                &UniqueOutputTokens
+            ]),
+            Err(CallError::NotFound(1))
+        );
+    }
+
+    #[test]
+    fn verify_burn() {
+        let lib = lib_success();
+        let (mut codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        codex
+            .verifiers
+            .insert(1, LibSite::new(lib.lib_id(), 0))
+            .unwrap();
+        let addr = CellAddr::strict_dumb();
+        operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(addr, StateCell::strict_dumb());
+
+        let policy = VerifyPolicy { burn_call: Some(1), ..default!() };
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation.clone(), &memory, &lib, policy),
+            Err(CallError::UnexpectedBurn(0))
+        );
+
+        operation.call_id = 1;
+        assert!(codex
+            .verify_with_policy(contract_id, operation.clone(), &memory, &lib, policy)
+            .is_ok());
+
+        operation.call_id = 0;
+        operation.destructible_out = small_vec![StateCell::strict_dumb()];
+        assert!(codex
+            .verify_with_policy(contract_id, operation, &memory, &lib, policy)
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_immutable_redefined() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        operation.immutable_out = small_vec![StateData::strict_dumb(), StateData::strict_dumb()];
+        let mut memory = DumbMemory::default();
+
+        let append_only = VerifyPolicy { reject_immutable_redefinition: true, ..default!() };
+        assert!(codex
+            .verify_with_policy(contract_id, operation.clone(), &memory, &lib, append_only)
+            .is_ok());
+
+        let addr = CellAddr::new(operation.opid(), 1);
+        memory.immutable.insert(addr, StateValue::None);
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation, &memory, &lib, append_only),
+            Err(CallError::ImmutableRedefined(addr))
+        );
+    }
+
+    #[test]
+    fn verify_call_depth() {
+        // Calls the routine at offset 4, which calls the routine at offset 8
+        let nested = Lib::assemble(&uasm! {
+            call    4;
+            stop;
+            call    8;
+            ret;
+            ret;
+        })
+        .unwrap();
+        let recursive = Lib::assemble(&uasm! { call 0; }).unwrap();
+        let (mut codex, contract_id, operation) = fixture(LibSite::new(nested.lib_id(), 0));
+        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+        let memory = DumbMemory::default();
+
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &nested)
+            .is_ok());
+        let shallow = VerifyPolicy { max_call_depth: Some(2), ..default!() };
+        assert!(codex
+            .verify_with_policy(contract_id, operation.clone(), &memory, &nested, shallow)
+            .is_ok());
+        let shallow = VerifyPolicy { max_call_depth: Some(1), ..default!() };
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation.clone(), &memory, &nested, shallow),
+            Err(CallError::CallDepthExceeded)
+        );
+
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(recursive.lib_id(), 0) };
+        let limited = VerifyPolicy {
+            max_call_depth: Some(VerifyPolicy::DEFAULT_MAX_CALL_DEPTH),
+            ..default!()
+        };
+        assert_eq!(
+            codex.verify_with_policy(contract_id, operation.clone(), &memory, &recursive, limited),
+            Err(CallError::CallDepthExceeded)
+        );
+        // Without the depth limit the recursion is stopped by the VM call stack overflow
+        assert!(!matches!(
+            codex.verify(contract_id, operation, &memory, &recursive),
+            Ok(_) | Err(CallError::CallDepthExceeded)
+        ));
+    }
+
+    #[test]
+    fn is_valid() {
+        let (mut codex, contract_id, mut operation) =
+            fixture(LibSite::new(lib_success().lib_id(), 0));
+        codex
+            .verifiers
+            .insert(1, LibSite::new(lib_failure_one().lib_id(), 0))
+            .unwrap();
+        let memory = DumbMemory::default();
+        let repo = HashMap::from([
+            (lib_success().lib_id(), lib_success()),
+            (lib_failure_one().lib_id(), lib_failure_one()),
+        ]);
 
-impl CommitmentId for CodexId {
-    const TAG: &'static str = "urn:ubideco:sonic:codex#2025-05-15";
-}
+        assert!(codex.is_valid(contract_id, operation.clone(), &memory, &repo));
+        operation.call_id = 1;
+        assert!(!codex.is_valid(contract_id, operation.clone(), &memory, &repo));
+        operation.call_id = 2;
+        assert!(!codex.is_valid(contract_id, operation, &memory, &repo));
+    }
 
-#[cfg(feature = "baid64")]
-mod _baid4 {
-    use core::fmt::{self, Display, Formatter};
-    use core::str::FromStr;
+    #[test]
+    fn verify_against() {
+        let lib = lib_failure_one();
+        let (mut codex, contract_id, operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        let memory = DumbMemory::default();
 
-    use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+        assert_eq!(
+            operation
+                .clone()
+                .verify_against(&codex, contract_id, &memory, &lib),
+            codex.verify(contract_id, operation.clone(), &memory, &lib)
+        );
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib_success().lib_id(), 0) };
+        let verified = operation
+            .clone()
+            .verify_against(&codex, contract_id, &memory, &lib_success())
+            .unwrap();
+        assert_eq!(verified.opid(), operation.opid());
+    }
 
-    use super::*;
+    #[test]
+    fn verify_detailed() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
 
-    impl DisplayBaid64 for CodexId {
-        const HRI: &'static str = "codex";
-        const CHUNKING: bool = true;
-        const PREFIX: bool = false;
-        const EMBED_CHECKSUM: bool = false;
-        const MNEMONIC: bool = true;
-        fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
-    }
-    impl FromBaid64Str for CodexId {}
-    impl FromStr for CodexId {
-        type Err = Baid64ParseError;
-        fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
-    }
-    impl Display for CodexId {
-        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
-    }
-}
+        let locked = CellAddr::new(Opid::strict_dumb(), 0);
+        let unlocked = CellAddr::new(Opid::strict_dumb(), 1);
+        let witness = StateValue::Single { first: fe256::from(SECRET) };
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(locked, StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        });
+        memory
+            .destructible
+            .insert(unlocked, StateCell::strict_dumb());
+        operation.destructible_in =
+            small_vec![Input { addr: unlocked, witness: StateValue::None }, Input {
+                addr: locked,
+                witness
+            },];
 
-#[cfg(test)]
-mod test {
-    #![cfg_attr(coverage_nightly, coverage(off))]
+        let opid = operation.opid();
+        let (verified, witnesses) = codex
+            .verify_detailed(contract_id, operation, &memory, &lib)
+            .unwrap();
+        assert_eq!(verified.opid(), opid);
+        assert_eq!(witnesses, vec![(locked, witness)]);
+    }
 
-    use core::str::FromStr;
-    use std::collections::HashMap;
+    #[test]
+    fn verify_with_witnesses() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
 
-    use aluvm::alu::aluasm;
-    use aluvm::{zk_aluasm, FIELD_ORDER_SECP};
-    use amplify::ByteArray;
-    use commit_verify::Digest;
-    use strict_encoding::StrictDumb;
+        let locked = CellAddr::new(Opid::strict_dumb(), 0);
+        let unlocked = CellAddr::new(Opid::strict_dumb(), 1);
+        let witness = StateValue::Single { first: fe256::from(SECRET) };
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(locked, StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        });
+        memory
+            .destructible
+            .insert(unlocked, StateCell::strict_dumb());
+        operation.destructible_in = small_vec![Input { addr: unlocked, witness: none!() }, Input {
+            addr: locked,
+            witness: none!()
+        },];
 
-    use super::*;
-    use crate::{uasm, AuthToken, Input};
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+        assert!(codex
+            .verify_with_witnesses(contract_id, operation.clone(), &memory, &lib, &[
+                StateValue::None,
+                witness
+            ])
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_witnesses(contract_id, operation.clone(), &memory, &lib, &[
+                witness,
+                StateValue::None
+            ]),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+        assert_eq!(
+            codex.verify_with_witnesses(contract_id, operation, &memory, &lib, &[witness]),
+            Err(CallError::WitnessCountMismatch { expected: 2, found: 1 })
+        );
+    }
 
     #[test]
-    fn codex_id_display() {
-        let id = CodexId::from_byte_array(Sha256::digest(b"test"));
+    fn verify_prepared() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let locked = CellAddr::new(Opid::strict_dumb(), 0);
+        let unlocked = CellAddr::new(Opid::strict_dumb(), 1);
+        let cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        };
+        operation.destructible_in = small_vec![Input { addr: unlocked, witness: none!() }, Input {
+            addr: locked,
+            witness: StateValue::Single { first: fe256::from(SECRET) }
+        },];
+        operation.immutable_in = small_vec![locked];
+        let read_once = [StateCell::strict_dumb(), cell];
+        let immutable = [StateData::strict_dumb()];
+
+        assert!(codex
+            .verify_prepared(contract_id, operation.clone(), &read_once, &immutable, &lib)
+            .is_ok());
         assert_eq!(
-            format!("{id}"),
-            "n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
+            codex.verify_prepared(
+                contract_id,
+                operation.clone(),
+                &[cell, StateCell::strict_dumb()],
+                &immutable,
+                &lib
+            ),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
         );
         assert_eq!(
-            format!("{id:-}"),
-            "codex:n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
+            codex.verify_prepared(
+                contract_id,
+                operation.clone(),
+                &read_once[..1],
+                &immutable,
+                &lib
+            ),
+            Err(CallError::PreparedInputMismatch { expected: 2, found: 1 })
         );
-        assert_eq!(format!("{id:#}"), "n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg");
+        assert_eq!(
+            codex.verify_prepared(contract_id, operation.clone(), &read_once, &[], &lib),
+            Err(CallError::PreparedInputMismatch { expected: 1, found: 0 })
+        );
+
+        // Duplicated addresses must be provided with the same cells
+        operation.immutable_in = small_vec![locked, locked];
+        let conflicting = [StateData::strict_dumb(), StateData::new(1u8, 2u8)];
+        assert_eq!(
+            codex.verify_prepared(contract_id, operation.clone(), &read_once, &conflicting, &lib),
+            Err(CallError::PreparedInputConflict(locked))
+        );
+        let same = [StateData::strict_dumb(), StateData::strict_dumb()];
+        assert!(codex
+            .verify_prepared(contract_id, operation, &read_once, &same, &lib)
+            .is_ok());
     }
 
     #[test]
-    fn codex_id_from_str() {
-        let id = CodexId::from_byte_array(Sha256::digest(b"test"));
+    fn verify_streaming_locks() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let mut memory = DumbMemory::default();
+        let cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        };
+        let witness = StateValue::Single { first: fe256::from(SECRET) };
+        let addrs = [0u16, 1, 2, 3].map(|pos| CellAddr::new(Opid::strict_dumb(), pos));
+        for addr in &addrs[..3] {
+            memory.destructible.insert(*addr, cell);
+        }
+        memory
+            .destructible
+            .insert(addrs[3], StateCell::strict_dumb());
+        operation.destructible_in = small_vec![
+            Input { addr: addrs[0], witness },
+            Input { addr: addrs[3], witness: none!() },
+            Input { addr: addrs[1], witness },
+        ];
+
+        let mut streamed = vec![];
+        assert!(codex
+            .verify_streaming_locks(contract_id, operation.clone(), &memory, &lib, |addr, res| {
+                streamed.push((addr, res))
+            })
+            .is_ok());
+        assert_eq!(streamed, vec![(addrs[0], Ok(())), (addrs[1], Ok(()))]);
+
+        let err = CallError::Lock(Some(fe256::from(2u8)));
+        operation.destructible_in = small_vec![
+            Input { addr: addrs[0], witness },
+            Input { addr: addrs[2], witness: none!() },
+            Input { addr: addrs[1], witness },
+        ];
+        let mut streamed = vec![];
         assert_eq!(
-            CodexId::from_str(
-                "n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
-            )
-            .unwrap(),
-            id
+            codex.verify_streaming_locks(
+                contract_id,
+                operation.clone(),
+                &memory,
+                &lib,
+                |addr, res| { streamed.push((addr, res)) }
+            ),
+            Err(err)
         );
+        assert_eq!(streamed, vec![(addrs[0], Ok(())), (addrs[2], Err(err))]);
+
+        let (res, report) = codex.verify_metered(contract_id, operation, &memory, &lib, default!());
+        assert_eq!(res, Err(err));
+        assert_eq!(report.failure, Some(err));
+        assert_eq!(report.locks, streamed);
+    }
+
+    #[test]
+    fn verify_with_selector() {
+        struct OutputCountSelector;
+        impl VerifierSelector for OutputCountSelector {
+            fn select(&self, codex: &Codex, _: &Operation, context: &VmContext) -> Option<LibSite> {
+                codex
+                    .verifiers
+                    .get(&(context.destructible_output.len() as CallId))
+                    .copied()
+            }
+        }
+
+        let success = lib_success();
+        let failure = lib_failure_one();
+        let repo = HashMap::from([
+            (success.lib_id(), success.clone()),
+            (failure.lib_id(), failure.clone()),
+        ]);
+        let (mut codex, contract_id, mut operation) = fixture(LibSite::new(failure.lib_id(), 0));
+        codex
+            .verifiers
+            .insert(1, LibSite::new(success.lib_id(), 0))
+            .unwrap();
+        let memory = DumbMemory::default();
+
         assert_eq!(
-            CodexId::from_str(
-                "codex:n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg#berlin-river-delta"
-            )
-            .unwrap(),
-            id
+            codex.verify_with_selector(
+                contract_id,
+                operation.clone(),
+                &memory,
+                &repo,
+                &CallIdSelector
+            ),
+            codex.verify(contract_id, operation.clone(), &memory, &repo)
         );
         assert_eq!(
-            CodexId::from_str("codex:n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg").unwrap(),
-            id
+            codex.verify_with_selector(
+                contract_id,
+                operation.clone(),
+                &memory,
+                &repo,
+                &OutputCountSelector
+            ),
+            Err(CallError::Script(fe256::from(1u8)))
         );
+
+        operation.destructible_out = small_vec![StateCell::strict_dumb()];
+        assert!(codex
+            .verify_with_selector(
+                contract_id,
+                operation.clone(),
+                &memory,
+                &repo,
+                &OutputCountSelector
+            )
+            .is_ok());
+        operation.destructible_out = small_vec![StateCell::strict_dumb(), StateCell::strict_dumb()];
         assert_eq!(
-            CodexId::from_str("n4bQgYhM-fWWaL_q-gxVrQFa-O~TxsrC-4Is0V1s-FbDwCgg").unwrap(),
-            id
+            codex.verify_with_selector(
+                contract_id,
+                operation,
+                &memory,
+                &repo,
+                &OutputCountSelector
+            ),
+            Err(CallError::NotFound(0))
         );
     }
 
-    #[derive(Clone, Eq, PartialEq, Debug, Default)]
-    pub struct DumbMemory {
-        pub destructible: HashMap<CellAddr, StateCell>,
-        pub immutable: HashMap<CellAddr, StateValue>,
-    }
+    #[cfg(not(target_arch = "wasm32"))]
+    mod context {
+        use std::cell::RefCell;
+
+        use amplify::confinement::SmallBlob;
+        use proptest::prelude::*;
+
+        use super::*;
+        use crate::StateData;
+
+        /// Observed copy of the VM context slices.
+        type Observed = (Vec<StateValue>, Vec<StateValue>, Vec<StateCell>, Vec<StateData>);
+
+        /// Selector observing the VM context passed to the verifier.
+        #[derive(Default)]
+        struct Observer(RefCell<Option<Observed>>);
 
-    impl Memory for DumbMemory {
-        fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
-            self.destructible.get(&addr).copied()
+        impl VerifierSelector for Observer {
+            fn select(
+                &self,
+                codex: &Codex,
+                operation: &Operation,
+                context: &VmContext,
+            ) -> Option<LibSite> {
+                *self.0.borrow_mut() = Some((
+                    context.destructible_input.to_vec(),
+                    context.immutable_input.to_vec(),
+                    context.destructible_output.to_vec(),
+                    context.immutable_output.to_vec(),
+                ));
+                CallIdSelector.select(codex, operation, context)
+            }
         }
 
-        fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
-            self.immutable.get(&addr).copied()
+        fn state_value() -> impl Strategy<Value = StateValue> {
+            prop::collection::vec(any::<u64>(), 0..=4)
+                .prop_map(|elems| elems.into_iter().map(fe256::from).collect())
+        }
+
+        fn state_cell() -> impl Strategy<Value = StateCell> {
+            (state_value(), any::<u64>(), any::<bool>()).prop_map(|(data, auth, locked)| {
+                StateCell {
+                    data,
+                    auth: AuthToken::from(fe256::from(auth)),
+                    lock: locked.then(|| LibSite::new(lib_success().lib_id(), 0)),
+                }
+            })
+        }
+
+        fn state_data() -> impl Strategy<Value = StateData> {
+            (state_value(), prop::option::of(prop::collection::vec(any::<u8>(), 0..64))).prop_map(
+                |(value, raw)| StateData {
+                    value,
+                    raw: raw.map(|raw| SmallBlob::from_checked(raw).into()),
+                },
+            )
         }
-    }
 
-    impl LibRepo for Lib {
-        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
-            if lib_id == self.lib_id() {
-                Some(self)
-            } else {
-                None
+        proptest! {
+            #[test]
+            fn context_mirrors_operation(
+                destructible_in in prop::collection::vec(state_cell(), 0..8),
+                immutable_in in prop::collection::vec(state_value(), 0..8),
+                destructible_out in prop::collection::vec(state_cell(), 0..8),
+                immutable_out in prop::collection::vec(state_data(), 0..8),
+            ) {
+                let lib = lib_success();
+                let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+                let mut memory = DumbMemory::default();
+                for (pos, cell) in destructible_in.iter().enumerate() {
+                    let addr = CellAddr::new(Opid::strict_dumb(), pos as u16);
+                    memory.destructible.insert(addr, *cell);
+                    operation.destructible_in.push(Input { addr, witness: none!() }).unwrap();
+                }
+                for (pos, value) in immutable_in.iter().enumerate() {
+                    let addr = CellAddr::new(Opid::from_byte_array([1u8; 32]), pos as u16);
+                    memory.immutable.insert(addr, *value);
+                    operation.immutable_in.push(addr).unwrap();
+                }
+                operation.destructible_out = SmallVec::from_checked(destructible_out.clone());
+                operation.immutable_out = SmallVec::from_checked(immutable_out.clone());
+
+                let observer = Observer::default();
+                let res = codex.verify_with_selector(contract_id, operation, &memory, &lib, &observer);
+                prop_assert!(res.is_ok());
+
+                let (dest_in, imm_in, dest_out, imm_out) = observer.0.into_inner().unwrap();
+                let expected_in = destructible_in.iter().map(|cell| cell.data).collect::<Vec<_>>();
+                prop_assert_eq!(dest_in, expected_in);
+                prop_assert_eq!(imm_in, immutable_in);
+                prop_assert_eq!(dest_out, destructible_out);
+                prop_assert_eq!(imm_out, immutable_out);
             }
         }
     }
 
-    fn lib_success() -> Lib { Lib::assemble(&aluasm! { stop; }).unwrap() }
-    fn lib_failure_none() -> Lib {
-        Lib::assemble(&zk_aluasm! {
-            clr     E1;
-            test    E2;
-            chk     CO;
+    #[test]
+    fn lib_dependencies() {
+        let leaf = lib_success();
+        let leaf_id = leaf.lib_id();
+        let middle = Lib::assemble(&zk_aluasm! {
+            call    leaf_id, 0;
+            stop;
         })
-        .unwrap()
-    }
-    fn lib_failure_one() -> Lib {
-        Lib::assemble(&zk_aluasm! {
-            put     E1, 1;
-            test    E2;
-            chk     CO;
+        .unwrap();
+        let middle_id = middle.lib_id();
+        let root = Lib::assemble(&zk_aluasm! {
+            call    middle_id, 0;
+            call    leaf_id, 0;
+            stop;
         })
-        .unwrap()
+        .unwrap();
+        let root_id = root.lib_id();
+        let repo = HashMap::from([(leaf_id, leaf), (middle_id, middle), (root_id, root)]);
+
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! {
+            0 => LibSite::new(root_id, 0),
+            1 => LibSite::new(middle_id, 0),
+        };
+        assert_eq!(codex.lib_dependencies(&repo), bset![leaf_id, middle_id, root_id]);
+        assert_eq!(codex.validate(&repo), Ok(()));
+        assert_eq!(codex.validate_with_policy(&repo, CodexPolicy { max_libs: Some(3) }), Ok(()));
+        assert_eq!(
+            codex.validate_with_policy(&repo, CodexPolicy { max_libs: Some(2) }),
+            Err(CodexError::TooManyLibs { limit: 2, actual: 3 })
+        );
+
+        let partial = HashMap::from([(root_id, repo[&root_id].clone())]);
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(root_id, 0) };
+        assert_eq!(codex.lib_dependencies(&partial), bset![leaf_id, middle_id, root_id]);
+
+        let mut operation = Operation::strict_dumb();
+        assert_eq!(codex.check_operation_libs(&operation, &repo), Ok(()));
+        let mut missing = vec![leaf_id, middle_id];
+        missing.sort();
+        assert_eq!(codex.check_operation_libs(&operation, &partial), Err(missing));
+        assert_eq!(codex.check_operation_libs(&operation, &HashMap::new()), Err(vec![root_id]));
+        operation.call_id = 1;
+        assert_eq!(codex.check_operation_libs(&operation, &HashMap::new()), Ok(()));
     }
-    const SECRET: u8 = 48;
-    fn lib_lock() -> Lib {
-        assert_eq!(SECRET, 48);
-        Lib::assemble(&uasm! {
+
+    #[test]
+    fn estimate_cost() {
+        let leaf = lib_success();
+        let leaf_id = leaf.lib_id();
+        let root = Lib::assemble(&zk_aluasm! {
+            call    leaf_id, 0;
+            call    leaf_id, 0;
             stop;
-            put     EA, 48; // Secret value
+        })
+        .unwrap();
+        let root_id = root.lib_id();
+        let repo = HashMap::from([(leaf_id, leaf), (root_id, root)]);
 
-            eq      EA, E1; // Must be provided as a token of authority
-            put     E8, 1;  // Failure #1
-            chk     CO;
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(root_id, 0) };
+        let mut operation = Genesis::strict_dumb().to_operation(ContractId::strict_dumb());
+        assert_eq!(codex.estimate_cost(&operation, &repo), 4 * Codex::INSTR_COST_ESTIMATE);
 
-            eq      EA, E2; // Must be provided in witness
-            put     E8, 2;  // Failure #2
-            chk     CO;
+        operation
+            .immutable_in
+            .push(CellAddr::strict_dumb())
+            .unwrap();
+        assert_eq!(codex.estimate_cost(&operation, &repo), 8 * Codex::INSTR_COST_ESTIMATE);
 
-            put     E8, 3;  // Failure #3
-            test    E3;     // The rest must be empty
-            not     CO;
-            chk     CO;
-            test    E4;
-            not     CO;
-            chk     CO;
-            test    E5;
-            not     CO;
-            chk     CO;
+        let partial = HashMap::from([(root_id, repo[&root_id].clone())]);
+        assert_eq!(codex.estimate_cost(&operation, &partial), 6 * Codex::INSTR_COST_ESTIMATE);
+
+        operation.call_id = 1;
+        assert_eq!(codex.estimate_cost(&operation, &repo), 0);
+    }
+
+    #[test]
+    fn total_verifier_instructions() {
+        let leaf = lib_success();
+        let leaf_id = leaf.lib_id();
+        let root = Lib::assemble(&zk_aluasm! {
+            call    leaf_id, 0;
+            call    leaf_id, 0;
+            stop;
         })
-        .unwrap()
+        .unwrap();
+        let root_id = root.lib_id();
+        let repo = HashMap::from([(leaf_id, leaf), (root_id, root)]);
+
+        let mut codex = Codex::strict_dumb();
+        assert_eq!(codex.total_verifier_instructions(&repo), Ok(0));
+        codex.verifiers = tiny_bmap! {
+            0 => LibSite::new(root_id, 0),
+            1 => LibSite::new(leaf_id, 0),
+            2 => LibSite::new(leaf_id, 0)
+        };
+        assert_eq!(codex.total_verifier_instructions(&repo), Ok(4));
+
+        let partial = HashMap::from([(root_id, repo[&root_id].clone())]);
+        assert_eq!(
+            codex.total_verifier_instructions(&partial),
+            Err(CallError::LibNotFound(leaf_id))
+        );
     }
 
-    fn test_stand(modify: impl FnOnce(&mut Codex, &mut Operation, &mut DumbMemory)) {
-        test_stand_script(lib_success(), modify)
+    #[test]
+    fn verify_cancellable() {
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        let addr = CellAddr::strict_dumb();
+        let mut memory = DumbMemory::default();
+        memory.destructible.insert(addr, StateCell::strict_dumb());
+        operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+
+        let cancel = AtomicBool::new(false);
+        assert!(codex
+            .verify_cancellable(contract_id, operation.clone(), &memory, &lib, &cancel)
+            .is_ok());
+        cancel.store(true, AtomicOrdering::Relaxed);
+        assert_eq!(
+            codex.verify_cancellable(contract_id, operation, &memory, &lib, &cancel),
+            Err(CallError::Cancelled)
+        );
     }
 
-    fn test_stand_script(
-        repo: Lib,
-        modify: impl FnOnce(&mut Codex, &mut Operation, &mut DumbMemory),
-    ) {
-        test_stand_repo(repo.lib_id(), repo, modify)
+    #[test]
+    fn cancel_between_inputs() {
+        struct CancellingMemory<'c>(DumbMemory, &'c AtomicBool);
+        impl Memory for CancellingMemory<'_> {
+            fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+                self.1.store(true, AtomicOrdering::Relaxed);
+                self.0.destructible(addr)
+            }
+            fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.0.immutable(addr) }
+        }
+
+        let lib = lib_success();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        let cancel = AtomicBool::new(false);
+        let mut memory = CancellingMemory(DumbMemory::default(), &cancel);
+        for pos in 0..2 {
+            let addr = CellAddr::new(Opid::strict_dumb(), pos);
+            memory.0.destructible.insert(addr, StateCell::strict_dumb());
+            operation
+                .destructible_in
+                .push(Input { addr, witness: none!() })
+                .unwrap();
+        }
+        assert_eq!(
+            codex.verify_cancellable(contract_id, operation, &memory, &lib, &cancel),
+            Err(CallError::Cancelled)
+        );
     }
 
-    fn test_stand_repo(
-        lib_id: LibId,
-        repo: impl LibRepo,
-        modify: impl FnOnce(&mut Codex, &mut Operation, &mut DumbMemory),
-    ) {
+    #[test]
+    #[cfg(feature = "test-utils")]
+    fn run_verifier() {
+        use crate::VmContextBuilder;
+
+        let lib = Lib::assemble(&uasm! {
+            cknxo   destructible;
+            chk     CO;
+            ldi     immutable;
+            chk     CO;
+            put     E1, 7;
+            eq      EA, E1;
+            chk     CO;
+        })
+        .unwrap();
         let mut codex = Codex::strict_dumb();
         codex.field_order = FIELD_ORDER_SECP;
-        codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
-        codex.input_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
-        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib_id, 0) };
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib.lib_id(), 0) };
 
-        let contract_id = ContractId::from_byte_array(Sha256::digest(b"test"));
-        let mut operation = Operation::strict_dumb();
-        operation.contract_id = contract_id;
-        operation.call_id = 0;
-        let mut memory = DumbMemory::default();
+        let builder = VmContextBuilder::new();
+        assert_eq!(
+            codex.run_verifier(0, &builder.context(), &lib),
+            Err(CallError::ScriptUnspecified)
+        );
+        let builder = builder
+            .destructible_output(StateCell::strict_dumb())
+            .immutable_input(StateValue::Single { first: fe256::from(7u8) });
+        assert_eq!(codex.run_verifier(0, &builder.context(), &lib), Ok(()));
+        assert_eq!(codex.run_verifier(1, &builder.context(), &lib), Err(CallError::NotFound(1)));
 
-        modify(&mut codex, &mut operation, &mut memory);
+        let builder = VmContextBuilder::new()
+            .destructible_output(StateCell::strict_dumb())
+            .immutable_input(StateValue::Single { first: fe256::from(8u8) });
+        assert_eq!(
+            codex.run_verifier(0, &builder.context(), &lib),
+            Err(CallError::Script(fe256::from(7u8)))
+        );
+    }
 
+    #[test]
+    #[cfg(feature = "test-utils")]
+    fn assert_rejected() {
+        let repo = HashMap::from([
+            (lib_success().lib_id(), lib_success()),
+            (lib_failure_one().lib_id(), lib_failure_one()),
+        ]);
+        let (mut codex, contract_id, mut operation) =
+            fixture(LibSite::new(lib_success().lib_id(), 0));
         codex
-            .verify(contract_id, operation, &memory, &repo)
+            .verifiers
+            .insert(1, LibSite::new(lib_failure_one().lib_id(), 0))
             .unwrap();
+        let memory = DumbMemory::default();
+
+        let expected = CallError::Script(fe256::from(1u8));
+        assert_eq!(
+            codex.assert_rejected(contract_id, operation.clone(), &memory, &repo, &expected),
+            Err(s!(
+                "operation is accepted, while expected to fail with \
+                 Script(fe256(0x0000000000000000000000000000000000000000000000000000000000000001))"
+            ))
+        );
+        operation.call_id = 1;
+        assert_eq!(
+            codex.assert_rejected(contract_id, operation.clone(), &memory, &repo, &expected),
+            Ok(())
+        );
+        assert!(codex
+            .assert_rejected(
+                contract_id,
+                operation,
+                &memory,
+                &repo,
+                &CallError::Script(fe256::from(2u8))
+            )
+            .unwrap_err()
+            .starts_with("operation is rejected with Script("));
     }
 
     #[test]
-    fn verify_dumb() { test_stand(|_codex, _operation, _memory| {}); }
+    #[cfg(feature = "test-utils")]
+    fn expect_regs() {
+        use crate::VmContextBuilder;
 
-    #[test]
-    #[should_panic(
-        expected = "WrongContract { expected: ContractId(Array<32>(9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08)), found: ContractId(Array<32>(8810ad581e59f2bc3928b261707a71308f7e139eb04820366dc4d5c18d980225))"
-    )]
-    fn verify_contract_id() {
-        test_stand(|_codex, operation, _memory| {
-            operation.contract_id = ContractId::from_byte_array(Sha256::digest(b"wrong"));
-        });
+        let lib = Lib::assemble(&uasm! {
+            put     E1, 7;
+            put     E2, 8;
+            ldi     immutable;
+            chk     CO;
+        })
+        .unwrap();
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib.lib_id(), 0) };
+        let context = VmContextBuilder::new();
+        let context = context.context();
+
+        assert_eq!(
+            codex.expect_regs(0, &context, &lib, &[
+                (RegE::E1, fe256::from(7u8)),
+                (RegE::E2, fe256::from(8u8))
+            ]),
+            Ok(())
+        );
+        let mismatches = codex
+            .expect_regs(0, &context, &lib, &[
+                (RegE::E1, fe256::from(7u8)),
+                (RegE::E2, fe256::from(9u8)),
+                (RegE::E3, fe256::from(1u8)),
+            ])
+            .unwrap_err();
+        assert_eq!(mismatches, vec![
+            RegMismatch {
+                reg: RegE::E2,
+                expected: fe256::from(9u8),
+                actual: Some(fe256::from(8u8))
+            },
+            RegMismatch { reg: RegE::E3, expected: fe256::from(1u8), actual: None },
+        ]);
+        assert_eq!(mismatches[1].to_string(), "E3: expected 1.fe, found ~");
+        assert_eq!(
+            codex
+                .expect_regs(1, &context, &lib, &[(RegE::E1, fe256::from(7u8))])
+                .unwrap_err()
+                .len(),
+            1
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "NoImmutableInput(CellAddr { opid: Opid(Array<32>(0000000000000000000000000000000000000000000000000000000000000000)), pos: 0 })"
-    )]
-    fn verify_no_immutable() {
-        test_stand(|_codex, operation, _memory| {
-            operation.immutable_in = small_vec![CellAddr::strict_dumb()];
-        });
-    }
+    fn verify_with_globals() {
+        let lib = Lib::assemble(&uasm! {
+            ldg;
+            chk     CO;
+            put     E2, 21;
+            eq      EB, E2;
+            chk     CO;
+            stop;
+        })
+        .unwrap();
+        let (codex, contract_id, operation) = fixture(LibSite::new(lib.lib_id(), 0));
+        let memory = DumbMemory::default();
 
-    #[test]
-    #[should_panic(
-        expected = " NoReadOnceInput(CellAddr { opid: Opid(Array<32>(0000000000000000000000000000000000000000000000000000000000000000)), pos: 0 })"
-    )]
-    fn verify_no_destructible() {
-        test_stand(|_codex, operation, _memory| {
-            operation.destructible_in =
-                small_vec![Input { addr: CellAddr::strict_dumb(), witness: none!() }];
-        });
+        let globals = [StateData::new(0u8, 21u8)];
+        assert!(codex
+            .verify_with_globals(contract_id, operation.clone(), &memory, &lib, &globals)
+            .is_ok());
+        let options = VerifyOptions { globals: &globals, ..default!() };
+        let (res, report) =
+            codex.verify_metered(contract_id, operation.clone(), &memory, &lib, options);
+        assert!(res.is_ok());
+        assert_eq!(report.executed_verifier, Some(LibSite::new(lib.lib_id(), 0)));
+        assert!(report.cycles > 0);
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::ScriptUnspecified)
+        );
+        let globals = [StateData::new(0u8, 20u8)];
+        assert_eq!(
+            codex.verify_with_globals(contract_id, operation, &memory, &lib, &globals),
+            Err(CallError::ScriptUnspecified)
+        );
     }
 
     #[test]
-    fn verify_immutable() {
-        test_stand(|_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.immutable.insert(addr, StateValue::strict_dumb());
-            operation.immutable_in = small_vec![addr];
+    fn verify_with_options() {
+        let lock = Lib::assemble(&zk_aluasm! {
+            put     E8, 1;
+            test    E7;
+            chk     CO;
+            put     E8, 2;
+            put     E6, 500000;
+            eq      E6, E7;
+            chk     CO;
+        })
+        .unwrap();
+        let lib = Lib::assemble(&uasm! {
+            ldg;
+            chk     CO;
+            put     E2, 21;
+            eq      EB, E2;
+            chk     CO;
+            stop;
+        })
+        .unwrap();
+        let (lock_id, lib_id) = (lock.lib_id(), lib.lib_id());
+        let repo = HashMap::from([(lock_id, lock), (lib_id, lib)]);
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib_id, 0));
+        let mut memory = DumbMemory::default();
+        let addr = CellAddr::new(Opid::strict_dumb(), 0);
+        memory.destructible.insert(addr, StateCell {
+            data: StateValue::None,
+            auth: AuthToken::strict_dumb(),
+            lock: Some(LibSite::new(lock_id, 0)),
         });
+        operation.destructible_in = small_vec![Input { addr, witness: none!() }];
+
+        let globals = [StateData::new(0u8, 21u8)];
+        let options = VerifyOptions {
+            policy: VerifyPolicy::strict(),
+            time: Some(500000),
+            globals: &globals,
+            ..default!()
+        };
+        assert!(codex
+            .verify_with_options(contract_id, operation.clone(), &memory, &repo, options)
+            .is_ok());
+        assert_eq!(
+            codex.verify_with_options(
+                contract_id,
+                operation.clone(),
+                &memory,
+                &repo,
+                VerifyOptions { time: None, ..options }
+            ),
+            Err(CallError::Lock(Some(fe256::from(1u8))))
+        );
+        assert_eq!(
+            codex.verify_with_options(
+                contract_id,
+                operation.clone(),
+                &memory,
+                &repo,
+                VerifyOptions { globals: &[], ..options }
+            ),
+            Err(CallError::ScriptUnspecified)
+        );
+        let cancel = AtomicBool::new(true);
+        assert_eq!(
+            codex.verify_with_options(contract_id, operation, &memory, &repo, VerifyOptions {
+                cancel: Some(&cancel),
+                ..options
+            }),
+            Err(CallError::Cancelled)
+        );
     }
 
     #[test]
-    fn verify_destructible() {
-        test_stand(|_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.destructible.insert(addr, StateCell::strict_dumb());
-            operation.destructible_in = small_vec![Input { addr, witness: none!() }];
-        });
+    fn verify_committed() {
+        struct LightMemory(DumbMemory, u256);
+        impl CommitmentMemory for LightMemory {
+            fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+                self.0.destructible(addr)
+            }
+            fn immutable_commitment(&self, addr: CellAddr) -> Option<fe256> {
+                self.0
+                    .immutable(addr)
+                    .map(|value| value.field_commitment(self.1))
+            }
+        }
+
+        // The verifier checks that the immutable input commits to the expected value
+        let expected = StateValue::new(1u8, 2u8).field_commitment(FIELD_ORDER_SECP);
+        let check = Lib::assemble(&uasm! {
+            ldi     immutable;
+            chk     CO;
+            test    EB;
+            not     CO;
+            chk     CO;
+            put     E1, expected;
+            eq      EA, E1;
+            chk     CO;
+        })
+        .unwrap();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(check.lib_id(), 0));
+        let addr = CellAddr::strict_dumb();
+        operation.immutable_in = small_vec![addr];
+
+        let mut memory = LightMemory(DumbMemory::default(), FIELD_ORDER_SECP);
+        memory.0.immutable.insert(addr, StateValue::new(1u8, 2u8));
+        assert!(codex
+            .verify_committed(contract_id, operation.clone(), &memory, &check)
+            .is_ok());
+
+        memory.0.immutable.insert(addr, StateValue::new(1u8, 3u8));
+        assert!(codex
+            .verify_committed(contract_id, operation.clone(), &memory, &check)
+            .is_err());
+
+        memory.0.immutable.clear();
+        assert_eq!(
+            codex.verify_committed(contract_id, operation, &memory, &check),
+            Err(CallError::NoImmutableInput(addr))
+        );
     }
 
     #[test]
-    fn verify_protected_dumb() {
-        test_stand_script(lib_lock(), |_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.destructible.insert(addr, StateCell {
-                data: StateValue::None,
-                auth: AuthToken::strict_dumb(),
-                lock: Some(LibSite::new(lib_lock().lib_id(), 0)),
-            });
-            operation.destructible_in = small_vec![Input { addr, witness: none!() }];
-        });
+    fn verifiers_sorted() {
+        let success = LibSite::new(lib_success().lib_id(), 0);
+        let failure = LibSite::new(lib_failure_one().lib_id(), 0);
+        let mut codex = Codex::strict_dumb();
+        assert!(codex.verifiers_sorted().is_empty());
+        codex.verifiers = tiny_bmap! { 7 => success, 1 => failure, 3 => success };
+        assert_eq!(codex.verifiers_sorted(), vec![(1, failure), (3, success), (7, success)]);
     }
 
     #[test]
-    fn verify_protected() {
-        test_stand_script(lib_lock(), |_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.destructible.insert(addr, StateCell {
-                data: StateValue::None,
-                auth: AuthToken::from(fe256::from(SECRET)),
-                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
-            });
-            operation.destructible_in = small_vec![Input {
-                addr,
-                witness: StateValue::Single { first: fe256::from(SECRET) }
-            }];
-        });
+    fn call_descriptors() {
+        let success = LibSite::new(lib_success().lib_id(), 0);
+        let failure = LibSite::new(lib_failure_one().lib_id(), 0);
+        let mut codex = Codex::strict_dumb();
+        assert!(codex.call_descriptors().is_empty());
+        codex.verifiers = tiny_bmap! { 7 => success, 1 => failure };
+        assert_eq!(codex.call_descriptors(), vec![
+            CallDescriptor { call_id: 1, verifier: failure },
+            CallDescriptor { call_id: 7, verifier: success },
+        ]);
     }
 
     #[test]
-    #[should_panic(
-        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000001)))"
-    )]
-    fn verify_protected_failure1() {
-        test_stand_script(lib_lock(), |_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.destructible.insert(addr, StateCell {
-                data: StateValue::None,
-                auth: AuthToken::strict_dumb(),
-                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
-            });
-            operation.destructible_in = small_vec![Input {
-                addr,
-                witness: StateValue::Single { first: fe256::from(SECRET) }
-            }];
-        });
+    fn verifiers_using() {
+        let success = lib_success().lib_id();
+        let failure = lib_failure_one().lib_id();
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! {
+            0 => LibSite::new(success, 0),
+            1 => LibSite::new(failure, 0),
+            2 => LibSite::new(success, 1),
+        };
+        assert_eq!(codex.verifiers_using(success), vec![0, 2]);
+        assert_eq!(codex.verifiers_using(failure), vec![1]);
+        assert!(codex
+            .verifiers_using(lib_failure_none().lib_id())
+            .is_empty());
     }
 
     #[test]
-    #[should_panic(
-        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000002)))"
-    )]
-    fn verify_protected_failure2() {
-        test_stand_script(lib_lock(), |_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.destructible.insert(addr, StateCell {
-                data: StateValue::None,
-                auth: AuthToken::from(fe256::from(SECRET)),
-                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
-            });
-            operation.destructible_in = small_vec![Input { addr, witness: StateValue::None }];
-        });
+    fn unused_verifiers() {
+        let lib = lib_success();
+        let (mut codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        codex
+            .verifiers
+            .insert(1, LibSite::new(lib.lib_id(), 0))
+            .unwrap();
+        codex
+            .verifiers
+            .insert(2, LibSite::new(lib.lib_id(), 0))
+            .unwrap();
+        let memory = DumbMemory::default();
+
+        let tracker = CoverageTracker::new();
+        for call_id in [0, 2, 2, 5] {
+            let mut operation = Operation::strict_dumb();
+            operation.contract_id = contract_id;
+            operation.call_id = call_id;
+            let _ = codex.verify_with_selector(contract_id, operation, &memory, &lib, &tracker);
+        }
+        assert_eq!(tracker.covered(), bset![0, 2]);
+        assert_eq!(codex.unused_verifiers(&tracker.into_covered()), vec![1]);
+        assert_eq!(codex.unused_verifiers(&none!()), vec![0, 1, 2]);
     }
 
     #[test]
-    #[should_panic(
-        expected = "Lock(Some(fe256(0x0000000000000000000000000000000000000000000000000000000000000003)))"
-    )]
-    fn verify_protected_failure3() {
-        test_stand_script(lib_lock(), |_codex, operation, memory| {
-            let addr = CellAddr::strict_dumb();
-            memory.destructible.insert(addr, StateCell {
-                data: StateValue::None,
-                auth: AuthToken::from(fe256::from(SECRET)),
-                lock: Some(LibSite::new(lib_lock().lib_id(), 1)),
-            });
-            operation.destructible_in = small_vec![Input {
-                addr,
-                witness: StateValue::Double {
-                    first: fe256::from(SECRET),
-                    second: fe256::from(SECRET)
-                }
-            }];
-        });
+    fn verify_with_outputs_returned() {
+        let lib = Lib::assemble(&zk_aluasm! {
+            put     E2, 5;
+            put     E3, 7;
+            put     E5, 9;
+            stop;
+        })
+        .unwrap();
+        let (mut codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        codex
+            .verifiers
+            .insert(1, LibSite::new(lib_success().lib_id(), 0))
+            .unwrap();
+        let memory = DumbMemory::default();
+        let repo = HashMap::from([(lib.lib_id(), lib), (lib_success().lib_id(), lib_success())]);
+        let mut operation = Operation::strict_dumb();
+        operation.contract_id = contract_id;
+
+        let (verified, outputs) = codex
+            .verify_with_outputs_returned(contract_id, operation.clone(), &memory, &repo)
+            .unwrap();
+        assert_eq!(verified.as_operation(), &operation);
+        assert_eq!(outputs, vec![fe256::from(5u8), fe256::from(7u8)]);
+
+        operation.call_id = 1;
+        let (_, outputs) = codex
+            .verify_with_outputs_returned(contract_id, operation.clone(), &memory, &repo)
+            .unwrap();
+        assert!(outputs.is_empty());
+
+        operation.call_id = 2;
+        assert_eq!(
+            codex
+                .verify_with_outputs_returned(contract_id, operation, &memory, &repo)
+                .unwrap_err(),
+            CallError::NotFound(2)
+        );
     }
 
     #[test]
-    #[should_panic(expected = "ScriptUnspecified")]
-    fn verify_script_failure_unspecified() {
-        test_stand_script(lib_failure_none(), |_codex, _operation, _memory| {});
+    fn verify_with_outputs_returned_after_load() {
+        let lib = Lib::assemble(&uasm! {
+            ldi     immutable;
+            chk     CO;
+            put     E2, 5;
+            put     E3, 7;
+            put     E4, 9;
+            stop;
+        })
+        .unwrap();
+        let (mut codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        let addr = CellAddr::strict_dumb();
+        let mut memory = DumbMemory::default();
+        memory.immutable.insert(addr, StateValue::new(1u8, 2u8));
+        let mut operation = Operation::strict_dumb();
+        operation.contract_id = contract_id;
+        operation.immutable_in = small_vec![addr];
+
+        let (_, outputs) = codex
+            .verify_with_outputs_returned(contract_id, operation.clone(), &memory, &lib)
+            .unwrap();
+        assert_eq!(outputs, vec![fe256::from(5u8), fe256::from(7u8), fe256::from(9u8)]);
+
+        // `E3` is bridged from the input VM, and is not returned
+        codex.input_bridge = 0b100;
+        let (_, outputs) = codex
+            .verify_with_outputs_returned(contract_id, operation, &memory, &lib)
+            .unwrap();
+        assert_eq!(outputs, vec![fe256::from(5u8), fe256::from(9u8)]);
     }
 
     #[test]
-    #[should_panic(
-        expected = "Script(fe256(0x0000000000000000000000000000000000000000000000000000000000000001))"
-    )]
-    fn verify_script_failure_code() {
-        test_stand_script(lib_failure_one(), |_codex, _operation, _memory| {});
+    fn bisect_bundle() {
+        let lib = lib_success();
+        let (codex, contract_id, _) = fixture(LibSite::new(lib.lib_id(), 0));
+        let memory = DumbMemory::default();
+        let operation = |nonce: u8, inputs: &[CellAddr]| {
+            let mut operation = Operation::strict_dumb();
+            operation.contract_id = contract_id;
+            operation.nonce = fe256::from(nonce);
+            operation.destructible_in = SmallVec::from_iter_checked(
+                inputs
+                    .iter()
+                    .map(|addr| Input { addr: *addr, witness: none!() }),
+            );
+            operation.destructible_out =
+                small_vec![StateCell::strict_dumb(), StateCell::strict_dumb()];
+            operation.immutable_out = small_vec![StateData::strict_dumb()];
+            operation
+        };
+
+        let first = operation(0, &[]);
+        let out0 = CellAddr::new(first.opid(), 0);
+        let out1 = CellAddr::new(first.opid(), 1);
+        let second = operation(1, &[out0]);
+        let mut third = operation(2, &[out1]);
+        third.immutable_in = small_vec![CellAddr::new(first.opid(), 0)];
+        let double_spend = operation(3, &[out0]);
+
+        assert_eq!(codex.bisect_bundle(contract_id, &[], &memory, &lib), 0);
+        let bundle = [first.clone(), second.clone(), third.clone()];
+        assert_eq!(codex.bisect_bundle(contract_id, &bundle, &memory, &lib), 3);
+        let bundle = [first.clone(), second.clone(), double_spend, third.clone()];
+        assert_eq!(codex.bisect_bundle(contract_id, &bundle, &memory, &lib), 2);
+        let bundle = [second, first, third];
+        assert_eq!(codex.bisect_bundle(contract_id, &bundle, &memory, &lib), 0);
+        assert!(memory.destructible.is_empty());
     }
 
     #[test]
-    #[should_panic(expected = "NotFound(0)")]
-    fn verify_no_verifier() {
-        test_stand_script(lib_success(), |codex, _operation, _memory| {
-            codex.verifiers.clear();
-        });
+    fn verify_developer_signature() {
+        // Toy signature scheme: a hash of the key and the message
+        struct ToySig(SigScheme, [u8; 32]);
+        impl ToySig {
+            fn sign(key: &DeveloperKey, msg: &[u8; 32]) -> Self {
+                let mut engine = Sha256::default();
+                engine.input_raw(key.key.as_slice());
+                engine.input_raw(msg);
+                Self(key.scheme, engine.finish())
+            }
+        }
+        impl DeveloperSig for ToySig {
+            fn scheme(&self) -> SigScheme { self.0 }
+            fn verify(&self, key: &Bytes32, msg: &[u8; 32]) -> bool {
+                self.1 == Self::sign(&DeveloperKey::new(self.0, *key), msg).1
+            }
+        }
+
+        let key = DeveloperKey::new(SigScheme::Bip340, [0xAA; 32]);
+        let mut codex = Codex::strict_dumb();
+        let sig = ToySig::sign(&key, &codex.developer_sig_msg());
+        assert!(!codex.verify_developer_signature(&sig));
+
+        codex.developer_key = Some(key);
+        assert!(!codex.verify_developer_signature(&sig));
+        let sig = ToySig::sign(&key, &codex.developer_sig_msg());
+        assert!(codex.verify_developer_signature(&sig));
+        let raw = ToySig::sign(&key, &codex.codex_id().to_byte_array());
+        assert!(!codex.verify_developer_signature(&raw));
+        let other = ToySig(SigScheme::Ed25519, sig.1);
+        assert!(!codex.verify_developer_signature(&other));
+
+        codex.developer = Identity::from("ssi:impostor");
+        assert!(!codex.verify_developer_signature(&sig));
+        codex.developer_key = Some(DeveloperKey::new(SigScheme::Bip340, [0xBB; 32]));
+        assert!(!codex.verify_developer_signature(&sig));
     }
 
     #[test]
-    #[should_panic(expected = "ScriptUnspecified")]
-    fn verify_lib_absent() {
-        test_stand_script(lib_success(), |codex, _operation, _memory| {
-            codex
-                .verifiers
-                .insert(0, LibSite::new(lib_failure_one().lib_id(), 0))
-                .unwrap();
-        });
+    fn verify_structure() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let mut memory = DumbMemory::default();
+        let cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        };
+        let witness = StateValue::Single { first: fe256::from(SECRET) };
+        let addrs = [0u16, 1, 2].map(|pos| CellAddr::new(Opid::strict_dumb(), pos));
+        memory.destructible.insert(addrs[0], cell);
+        memory.destructible.insert(addrs[1], cell);
+        memory
+            .destructible
+            .insert(addrs[2], StateCell::strict_dumb());
+        operation.destructible_in = small_vec![
+            Input { addr: addrs[0], witness },
+            Input { addr: addrs[1], witness: none!() },
+            Input { addr: addrs[2], witness: none!() },
+        ];
+
+        assert_eq!(
+            codex.verify_structure(contract_id, &operation, &memory, &lib),
+            Ok(vec![addrs[1]])
+        );
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+
+        operation.destructible_in = small_vec![
+            Input {
+                addr: addrs[0],
+                witness: StateValue::Single { first: fe256::from(1u8) }
+            },
+            Input { addr: addrs[1], witness: none!() },
+        ];
+        assert_eq!(
+            codex.verify_structure(contract_id, &operation, &memory, &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+
+        operation.destructible_in =
+            small_vec![Input { addr: addrs[0], witness }, Input { addr: addrs[1], witness },];
+        assert_eq!(codex.verify_structure(contract_id, &operation, &memory, &lib), Ok(vec![]));
+        assert!(codex.verify(contract_id, operation, &memory, &lib).is_ok());
     }
 
     #[test]
-    #[should_panic(expected = "ScriptUnspecified")]
-    fn verify_lib_wrong_pos() {
-        test_stand_script(lib_success(), |codex, _operation, _memory| {
-            codex
-                .verifiers
-                .insert(0, LibSite::new(lib_success().lib_id(), 1))
-                .unwrap();
+    fn check_upgrade() {
+        let success = LibSite::new(lib_success().lib_id(), 0);
+        let failure = LibSite::new(lib_failure_one().lib_id(), 0);
+        let repo =
+            HashMap::from([(success.lib_id, lib_success()), (failure.lib_id, lib_failure_one())]);
+        let mut older = Codex::strict_dumb();
+        older.field_order = FIELD_ORDER_SECP;
+        older.verifiers = tiny_bmap! { 0 => success, 1 => failure, 3 => success };
+        let mut newer = older.clone();
+        newer.verifiers = tiny_bmap! { 0 => success, 1 => success, 2 => success, 3 => failure };
+        let contract_id = ContractId::from_byte_array(Sha256::digest(b"test"));
+        let memory = DumbMemory::default();
+        let operations = [0, 1, 2, 3, 4].map(|call_id| {
+            let mut operation = Operation::strict_dumb();
+            operation.contract_id = contract_id;
+            operation.call_id = call_id;
+            operation
         });
+
+        let report = older.check_upgrade(&older, contract_id, &operations, &memory, &repo);
+        assert_eq!(report, UpgradeReport { checked: 5, divergences: vec![] });
+        assert!(report.is_compatible());
+
+        let report = older.check_upgrade(&newer, contract_id, &operations, &memory, &repo);
+        assert_eq!(report.checked, 5);
+        let failed = Err(CallError::Script(fe256::from(1u8)));
+        assert_eq!(report.divergences, vec![
+            UpgradeDivergence {
+                index: 1,
+                opid: operations[1].opid(),
+                older: failed,
+                newer: Ok(())
+            },
+            UpgradeDivergence {
+                index: 2,
+                opid: operations[2].opid(),
+                older: Err(CallError::NotFound(2)),
+                newer: Ok(())
+            },
+            UpgradeDivergence {
+                index: 3,
+                opid: operations[3].opid(),
+                older: Ok(()),
+                newer: failed
+            },
+        ]);
+        assert!(!report.is_compatible());
+        assert_eq!(report.regressions().map(|d| d.index).collect::<Vec<_>>(), vec![3]);
+
+        let report = newer.check_upgrade(&older, contract_id, &operations[..3], &memory, &repo);
+        assert!(!report.is_compatible());
+        let report = older.check_upgrade(&newer, contract_id, &operations[..3], &memory, &repo);
+        assert!(report.is_compatible());
     }
 
     #[test]
-    #[should_panic(expected = "The library returned by the `LibRepo` provided for the contract \
-                               operation verification doesn't match the requested library id. \
-                               This error indicates that the software using the consensus \
-                               verification is invalid or compromised.")]
-    fn verify_wrong_lib_id() {
-        struct InvalidRepo(Lib);
-        impl LibRepo for InvalidRepo {
-            fn get_lib(&self, _lib_id: LibId) -> Option<&Lib> { Some(&self.0) }
-        }
-        let repo = InvalidRepo(lib_failure_one());
-        test_stand_repo(lib_success().lib_id(), repo, |_codex, _operation, _memory| {});
+    fn verify_collect_locks() {
+        let lib = lib_lock();
+        let (codex, contract_id, mut operation) = fixture(LibSite::new(lib.lib_id(), 0));
+
+        let mut memory = DumbMemory::default();
+        let cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(SECRET)),
+            lock: Some(LibSite::new(lib.lib_id(), 1)),
+        };
+        let witness = StateValue::Single { first: fe256::from(SECRET) };
+        let addrs = [0u16, 1, 2, 3].map(|pos| CellAddr::new(Opid::strict_dumb(), pos));
+        memory.destructible.insert(addrs[0], cell);
+        memory.destructible.insert(addrs[1], cell);
+        memory
+            .destructible
+            .insert(addrs[2], StateCell { auth: strict_dumb!(), ..cell });
+        operation.destructible_in = small_vec![
+            Input { addr: addrs[0], witness },
+            Input { addr: addrs[1], witness: none!() },
+            Input { addr: addrs[2], witness },
+        ];
+
+        assert_eq!(
+            codex.verify(contract_id, operation.clone(), &memory, &lib),
+            Err(CallError::Lock(Some(fe256::from(2u8))))
+        );
+        assert_eq!(
+            codex.verify_collect_locks(contract_id, operation.clone(), &memory, &lib),
+            Err(CollectedError::Locks(vec![
+                (addrs[1], Some(fe256::from(2u8))),
+                (addrs[2], Some(fe256::from(1u8)))
+            ]))
+        );
+
+        operation.destructible_in = small_vec![Input { addr: addrs[0], witness }, Input {
+            addr: addrs[3],
+            witness: none!()
+        },];
+        assert_eq!(
+            codex.verify_collect_locks(contract_id, operation.clone(), &memory, &lib),
+            Err(CollectedError::Call(CallError::NoReadOnceInput(addrs[3])))
+        );
+
+        operation.destructible_in =
+            small_vec![Input { addr: addrs[0], witness }, Input { addr: addrs[1], witness },];
+        assert!(codex
+            .verify_collect_locks(contract_id, operation, &memory, &lib)
+            .is_ok());
     }
 }
@@ -0,0 +1,146 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeMap;
+
+use crate::{CellAddr, Memory, StateCell, StateValue};
+
+/// Differences between the cells of one class (destructible or immutable) of two memories.
+///
+/// Cells are compared by their values; the cells absent from both memories are not reported.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct CellsDiff<T> {
+    /// Cells present only in the second memory.
+    pub added: BTreeMap<CellAddr, T>,
+    /// Cells present only in the first memory.
+    pub removed: BTreeMap<CellAddr, T>,
+    /// Cells present in both memories with different values, the value from the first memory
+    /// going first.
+    pub changed: BTreeMap<CellAddr, (T, T)>,
+}
+
+impl<T> Default for CellsDiff<T> {
+    fn default() -> Self { Self { added: none!(), removed: none!(), changed: none!() } }
+}
+
+impl<T: Eq> CellsDiff<T> {
+    fn record(&mut self, addr: CellAddr, a: Option<T>, b: Option<T>) {
+        match (a, b) {
+            (None, Some(b)) => {
+                self.added.insert(addr, b);
+            }
+            (Some(a), None) => {
+                self.removed.insert(addr, a);
+            }
+            (Some(a), Some(b)) if a != b => {
+                self.changed.insert(addr, (a, b));
+            }
+            _ => {}
+        }
+    }
+
+    /// Detects whether the cells are the same in both memories.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Differences between two memories, computed with [`memory_diff`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MemoryDiff {
+    /// Differences in the destructible memory cells.
+    pub destructible: CellsDiff<StateCell>,
+    /// Differences in the immutable memory cells.
+    pub immutable: CellsDiff<StateValue>,
+}
+
+impl MemoryDiff {
+    /// Detects whether the memories are the same over the compared addresses.
+    pub fn is_empty(&self) -> bool { self.destructible.is_empty() && self.immutable.is_empty() }
+}
+
+/// Computes differences between the memories `a` (for instance, a local one) and `b` (for
+/// instance, a remote snapshot) over the set of the candidate addresses `addrs`.
+///
+/// Since [`Memory`] doesn't support enumeration of its cells, only the cells at the `addrs` are
+/// compared, both in the destructible and immutable memory. The cells present only in `b` are
+/// reported as added, and the cells present only in `a` as removed.
+pub fn memory_diff(a: &impl Memory, b: &impl Memory, addrs: &[CellAddr]) -> MemoryDiff {
+    let mut diff = MemoryDiff::default();
+    for addr in addrs {
+        diff.destructible
+            .record(*addr, a.destructible(*addr), b.destructible(*addr));
+        diff.immutable
+            .record(*addr, a.immutable(*addr), b.immutable(*addr));
+    }
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::memory::test_helpers::DumbMemory;
+    use crate::{fe256, Opid};
+
+    #[test]
+    fn diff() {
+        let addr = |pos| CellAddr::new(Opid::strict_dumb(), pos);
+        let cell = |val: u8| StateCell {
+            data: StateValue::Single { first: fe256::from(val) },
+            auth: strict_dumb!(),
+            lock: None,
+        };
+        let value = |val: u8| StateValue::Single { first: fe256::from(val) };
+
+        let mut a = DumbMemory::default();
+        let mut b = DumbMemory::default();
+        a.destructible.insert(addr(0), cell(1));
+        a.destructible.insert(addr(1), cell(1));
+        b.destructible.insert(addr(1), cell(2));
+        b.destructible.insert(addr(2), cell(3));
+        a.immutable.insert(addr(0), value(1));
+        b.immutable.insert(addr(0), value(1));
+        b.immutable.insert(addr(3), value(4));
+
+        assert!(memory_diff(&a, &b, &[]).is_empty());
+        assert!(memory_diff(&a, &a, &[addr(0), addr(1)]).is_empty());
+
+        let diff = memory_diff(&a, &b, &[addr(0), addr(1), addr(2), addr(3), addr(4)]);
+        assert_eq!(diff, MemoryDiff {
+            destructible: CellsDiff {
+                added: bmap! { addr(2) => cell(3) },
+                removed: bmap! { addr(0) => cell(1) },
+                changed: bmap! { addr(1) => (cell(1), cell(2)) },
+            },
+            immutable: CellsDiff {
+                added: bmap! { addr(3) => value(4) },
+                removed: none!(),
+                changed: none!(),
+            },
+        });
+    }
+}
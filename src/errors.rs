@@ -0,0 +1,107 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use aluvm::alu::LibId;
+use aluvm::fe256;
+
+use crate::{CallError, CallId, CellAddr};
+
+/// Codex validation errors.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CodexError {
+    /// library {lib} used by the verifier for the call {call_id} is not known.
+    LibAbsent {
+        /// Call id of the verifier.
+        call_id: CallId,
+        /// Id of the library which is not known.
+        lib: LibId,
+    },
+
+    /// verifier for the call {call_id} has entry point at offset {offset} of library {lib}, which
+    /// doesn't correspond to an instruction boundary.
+    InvalidEntryPoint {
+        /// Call id of the verifier.
+        call_id: CallId,
+        /// Id of the verifier library.
+        lib: LibId,
+        /// Invalid entry point offset within the library code.
+        offset: u16,
+    },
+
+    /// the number of verifiers exceeds the maximum allowed by the codex.
+    TooManyVerifiers,
+
+    /// library {0} called by the codex verifiers or lock scripts is not known.
+    LibUnresolved(LibId),
+
+    /// codex uses {actual} libraries, exceeding the limit of {limit} libraries.
+    TooManyLibs {
+        /// Maximum number of libraries allowed by the policy.
+        limit: usize,
+        /// Actual number of libraries used by the codex.
+        actual: usize,
+    },
+}
+
+/// Errors of the operation verification with [`crate::Codex::verify_collect_locks`], aggregating
+/// the failures of all the input lock scripts.
+///
+/// Unlike [`CallError`], the error is not `Copy`, since it holds a list of the failures.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum CollectedError {
+    /// {0}
+    #[from]
+    Call(CallError),
+
+    /// operation input access conditions are unsatisfied for some of the inputs.
+    Locks(Vec<(CellAddr, Option<fe256>)>),
+}
+
+/// Errors merging codices with [`crate::Codex::merge_verifiers`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum MergeError {
+    /// codices use different field orders.
+    FieldOrderMismatch,
+
+    /// codices use different VM configurations.
+    ConfigMismatch,
+
+    /// codices use different input bridge or absent immutable input settings.
+    FlagsMismatch,
+
+    /// codices define different verifiers for the call {0}.
+    CallIdConflict(CallId),
+
+    /// codices define different witness banks for the lock library {0}.
+    WitnessBankConflict(LibId),
+
+    /// the number of verifiers in the merged codex exceeds the maximum allowed by the codex.
+    TooManyVerifiers,
+
+    /// the number of witness banks in the merged codex exceeds the maximum allowed by the codex.
+    TooManyWitnessBanks,
+}
@@ -0,0 +1,84 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::io;
+
+#[cfg(feature = "chf-sha256")]
+use commit_verify::{DigestExt, Sha256};
+
+/// Hash function used to compute the codex ids with [`crate::Codex::codex_id_with`].
+///
+/// The ids returned by [`crate::Codex::codex_id`] always use the tagged SHA-256 hash
+/// ([`Sha256Hash`]); computing them with [`Sha256Hash`] produces exactly the same values. Other
+/// hash functions (like BLAKE3 or Keccak) are provided by the integrators targeting ecosystems
+/// which require a specific hash for their commitments.
+///
+/// Operation ids ([`crate::Operation::opid`]) are always computed with SHA-256, since their
+/// commitment procedure merklizes the operation inputs and outputs with SHA-256 merkle trees.
+///
+/// # Security
+///
+/// The choice of the hash function is a part of the contract consensus:
+/// - ids computed with different hash functions are not compatible, thus all the hosts verifying a
+///   contract must use the same hash function for it;
+/// - the hash function must be collision-resistant with at least 128-bit security, and must provide
+///   the full 32-byte output: a weaker or truncated hash allows constructing different codices with
+///   the same id;
+/// - the implementation must domain-separate the hashed data by the `tag`, such that ids of
+///   different kinds of data can't collide.
+pub trait CommitHash: Sized {
+    /// Constructs a hash engine domain-separated by the `tag`.
+    fn from_tag(tag: &str) -> Self;
+
+    /// Feeds the engine with the `data`.
+    fn input_raw(&mut self, data: &[u8]);
+
+    /// Completes the hashing, returning the hash value.
+    fn finish(self) -> [u8; 32];
+}
+
+/// Tagged SHA-256 hash (in the form defined by BIP-340), used by the crate for all the ids.
+#[cfg(feature = "chf-sha256")]
+#[derive(Clone, Debug)]
+pub struct Sha256Hash(Sha256);
+
+#[cfg(feature = "chf-sha256")]
+impl CommitHash for Sha256Hash {
+    fn from_tag(tag: &str) -> Self { Self(Sha256::from_tag(tag)) }
+
+    fn input_raw(&mut self, data: &[u8]) { self.0.input_raw(data) }
+
+    fn finish(self) -> [u8; 32] { self.0.finish() }
+}
+
+/// Adaptor streaming the written data into a [`CommitHash`] engine.
+pub(crate) struct HashWriter<H: CommitHash>(pub H);
+
+impl<H: CommitHash> io::Write for HashWriter<H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.input_raw(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
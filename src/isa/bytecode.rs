@@ -32,7 +32,7 @@ use crate::Instr;
 
 impl UsonicInstr {
     const START: u8 = 128;
-    const END: u8 = Self::START + Self::RSTOAO;
+    const END: u8 = Self::START + Self::RSTG;
 
     const CKNXIRO: u8 = 0;
     const CKNXIAO: u8 = 1;
@@ -48,6 +48,11 @@ impl UsonicInstr {
     const RSTIAO: u8 = 9;
     const RSTORO: u8 = 10;
     const RSTOAO: u8 = 11;
+
+    // Added in v0.12; see `UsonicInstr::CkNxG` docs
+    const CKNXG: u8 = 12;
+    const LDG: u8 = 13;
+    const RSTG: u8 = 14;
 }
 
 impl<Id: SiteId> Bytecode<Id> for UsonicInstr {
@@ -68,6 +73,9 @@ impl<Id: SiteId> Bytecode<Id> for UsonicInstr {
                 UsonicInstr::RstIAo => Self::RSTIAO,
                 UsonicInstr::RstORo => Self::RSTORO,
                 UsonicInstr::RstOAo => Self::RSTOAO,
+                UsonicInstr::CkNxG => Self::CKNXG,
+                UsonicInstr::LdG => Self::LDG,
+                UsonicInstr::RstG => Self::RSTG,
             }
     }
 
@@ -89,6 +97,7 @@ impl<Id: SiteId> Bytecode<Id> for UsonicInstr {
             | UsonicInstr::RstIAo
             | UsonicInstr::RstORo
             | UsonicInstr::RstOAo => Ok(()),
+            UsonicInstr::CkNxG | UsonicInstr::LdG | UsonicInstr::RstG => Ok(()),
         }
     }
 
@@ -110,6 +119,9 @@ impl<Id: SiteId> Bytecode<Id> for UsonicInstr {
             Self::RSTIAO => UsonicInstr::RstIAo,
             Self::RSTORO => UsonicInstr::RstORo,
             Self::RSTOAO => UsonicInstr::RstOAo,
+            Self::CKNXG => UsonicInstr::CkNxG,
+            Self::LDG => UsonicInstr::LdG,
+            Self::RSTG => UsonicInstr::RstG,
             _ => unreachable!(),
         })
     }
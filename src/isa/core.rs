@@ -77,6 +77,9 @@ pub struct UsonicCore {
     /// State value iterator positions
     pub(super) ui: [u16; 4],
 
+    /// Contract-global parameters iterator position
+    pub(super) ug: u16,
+
     pub(super) gfa: GfaCore,
 }
 
@@ -95,6 +98,7 @@ impl Debug for UsonicCore {
         write!(f, "{reg}UI2{reset} {val}{}{reset}  ", self.ui[IoCat::IN_AO.index()])?;
         write!(f, "{reg}UI3{reset} {val}{}{reset}  ", self.ui[IoCat::OUT_RO.index()])?;
         writeln!(f, "{reg}UI4{reset} {val}{}{reset}  ", self.ui[IoCat::OUT_AO.index()])?;
+        writeln!(f, "{reg}UG{reset}  {val}{}{reset}  ", self.ug)?;
         writeln!(f)
     }
 }
@@ -103,7 +107,9 @@ impl CoreExt for UsonicCore {
     type Reg = RegE;
     type Config = GfaConfig;
 
-    fn with(config: Self::Config) -> Self { UsonicCore { ui: [0; 4], gfa: GfaCore::with(config) } }
+    fn with(config: Self::Config) -> Self {
+        UsonicCore { ui: [0; 4], ug: 0, gfa: GfaCore::with(config) }
+    }
 
     fn get(&self, reg: Self::Reg) -> Option<<Self::Reg as Register>::Value> { self.gfa.get(reg) }
 
@@ -116,6 +122,7 @@ impl CoreExt for UsonicCore {
     fn reset(&mut self) {
         self.gfa.reset();
         self.ui = [0; 4];
+        self.ug = 0;
     }
 }
 
@@ -21,28 +21,62 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use core::cell::Cell;
 use std::collections::BTreeSet;
 
 use aluvm::alu::regs::Status;
 use aluvm::alu::{Core, CoreExt, ExecStep, Site, SiteId, Supercore};
-use aluvm::isa::{GotoTarget, Instruction};
+use aluvm::isa::{CtrlInstr, GotoTarget, Instruction};
 use aluvm::RegE;
 
 use super::{UsonicCore, UsonicInstr};
+#[cfg(feature = "profiling")]
+use crate::Profiler;
 use crate::{Instr, IoCat, StateCell, StateData, StateValue, ISA_ULTRASONIC};
 
 /// Context object provided to the VM instance, containing references to the operation inputs and
 /// outputs.
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct VmContext<'ctx> {
     /// Operation input consisting of the destructible (read-once) memory cells.
     pub destructible_input: &'ctx [StateValue],
-    /// Operation input consisting of the immutable (read-only) memory cells.
+    /// Operation input consisting of the immutable (read-only) memory cells, in the order of
+    /// [`crate::Operation::immutable_in`].
     pub immutable_input: &'ctx [StateValue],
     /// Operation output defining new destructible (read-once) memory cells.
     pub destructible_output: &'ctx [StateCell],
     /// Operation output defining new immutable (append-only) memory cells.
     pub immutable_output: &'ctx [StateData],
+    /// Contract-global parameters, which are not a part of any specific memory cell.
+    ///
+    /// The globals are a part of the committed contract definition, such that all hosts verifying
+    /// the contract operations must provide the same globals to the verifiers (see
+    /// [`crate::Codex::verify_with_globals`]). Only the `value` part of the data can be accessed
+    /// by the verification scripts.
+    pub globals: &'ctx [StateData],
+    /// Complexity accumulated by the instructions executed over this context.
+    ///
+    /// Mirrors the value of the VM `CA` register, which is not readable outside the VM core, and
+    /// is used for metering the verification (see [`crate::Codex::verify_metered`]).
+    pub complexity: Cell<u64>,
+    /// Maximum depth of the nested calls the verifier may perform, if limited.
+    ///
+    /// A call which would exceed the depth fails (without performing the call) in the same way as
+    /// a call overflowing the VM call stack, and sets [`Self::call_depth_exceeded`]. If not set,
+    /// the depth is limited only by the size of the VM call stack.
+    pub max_call_depth: Option<u16>,
+    /// Set if the verifier has attempted a call exceeding [`Self::max_call_depth`].
+    pub call_depth_exceeded: Cell<bool>,
+    /// Number of the immutable inputs loaded by the verifier.
+    ///
+    /// Since the inputs are loaded sequentially, the verifier has loaded exactly the first
+    /// `immutable_loaded` inputs of [`Self::immutable_input`] (see
+    /// [`crate::VerificationReport::consulted_immutable`]).
+    pub immutable_loaded: Cell<u16>,
+    /// Profiler recording the executed instructions, if the execution is profiled (see
+    /// [`crate::Codex::verify_profile`]).
+    #[cfg(feature = "profiling")]
+    pub profiler: Option<&'ctx Profiler>,
 }
 
 impl VmContext<'_> {
@@ -64,6 +98,80 @@ impl VmContext<'_> {
                 .map(|cell| cell.value),
         }
     }
+
+    /// Returns a state value of the contract-global parameter with the given `index`.
+    ///
+    /// If there is no global parameter with the index, returns `None`.
+    pub fn global_value(&self, index: u16) -> Option<StateValue> {
+        self.globals.get(index as usize).map(|data| data.value)
+    }
+}
+
+/// Builder assembling data for a [`VmContext`] without constructing an operation or a memory.
+///
+/// This is a testing aid for running the verifiers in isolation with
+/// [`crate::Codex::run_verifier`]; it is not a part of the consensus verification.
+#[cfg(feature = "test-utils")]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct VmContextBuilder {
+    destructible_input: Vec<StateValue>,
+    immutable_input: Vec<StateValue>,
+    destructible_output: Vec<StateCell>,
+    immutable_output: Vec<StateData>,
+    globals: Vec<StateData>,
+}
+
+#[cfg(feature = "test-utils")]
+impl VmContextBuilder {
+    /// Constructs a builder for an empty context.
+    pub fn new() -> Self { Self::default() }
+
+    /// Adds a destructible (read-once) input value.
+    pub fn destructible_input(mut self, value: StateValue) -> Self {
+        self.destructible_input.push(value);
+        self
+    }
+
+    /// Adds an immutable (read-only) input value.
+    pub fn immutable_input(mut self, value: StateValue) -> Self {
+        self.immutable_input.push(value);
+        self
+    }
+
+    /// Adds a destructible (read-once) output memory cell.
+    pub fn destructible_output(mut self, cell: StateCell) -> Self {
+        self.destructible_output.push(cell);
+        self
+    }
+
+    /// Adds an immutable (append-only) output memory cell.
+    pub fn immutable_output(mut self, data: StateData) -> Self {
+        self.immutable_output.push(data);
+        self
+    }
+
+    /// Adds a contract-global parameter.
+    pub fn global(mut self, data: StateData) -> Self {
+        self.globals.push(data);
+        self
+    }
+
+    /// Constructs the VM context referencing the data assembled by the builder.
+    pub fn context(&self) -> VmContext<'_> {
+        VmContext {
+            destructible_input: &self.destructible_input,
+            immutable_input: &self.immutable_input,
+            destructible_output: &self.destructible_output,
+            immutable_output: &self.immutable_output,
+            globals: &self.globals,
+            complexity: none!(),
+            max_call_depth: None,
+            call_depth_exceeded: none!(),
+            immutable_loaded: none!(),
+            #[cfg(feature = "profiling")]
+            profiler: None,
+        }
+    }
 }
 
 impl<Id: SiteId> Instruction<Id> for UsonicInstr {
@@ -73,7 +181,7 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr {
 
     fn is_goto_target(&self) -> bool { false }
 
-    fn local_goto_pos(&mut self) -> GotoTarget { GotoTarget::None }
+    fn local_goto_pos(&mut self) -> GotoTarget<'_> { GotoTarget::None }
 
     fn remote_goto_pos(&mut self) -> Option<&mut Site<Id>> { None }
 
@@ -92,6 +200,8 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr {
             | UsonicInstr::RstIAo
             | UsonicInstr::RstORo
             | UsonicInstr::RstOAo => none!(),
+            UsonicInstr::CkNxG | UsonicInstr::RstG => none!(),
+            UsonicInstr::LdG => bset![RegE::EA, RegE::EB, RegE::EC, RegE::ED],
         }
     }
 
@@ -106,6 +216,7 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr {
             | UsonicInstr::RstIAo
             | UsonicInstr::RstORo
             | UsonicInstr::RstOAo => 0,
+            UsonicInstr::CkNxG | UsonicInstr::LdG | UsonicInstr::RstG => 0,
         }
     }
 
@@ -120,6 +231,7 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr {
             | UsonicInstr::RstIAo
             | UsonicInstr::RstORo
             | UsonicInstr::RstOAo => 0,
+            UsonicInstr::CkNxG | UsonicInstr::LdG | UsonicInstr::RstG => 0,
         }
     }
 
@@ -154,6 +266,12 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr {
                 core.cx.reset(IoCat::OUT_AO);
                 return ExecStep::Next;
             }
+            UsonicInstr::CkNxG => core.cx.has_global(context),
+            UsonicInstr::LdG => core.cx.load_global(context),
+            UsonicInstr::RstG => {
+                core.cx.reset_global();
+                return ExecStep::Next;
+            }
         };
         core.set_co(if res { Status::Ok } else { Status::Fail });
         ExecStep::Next
@@ -174,7 +292,7 @@ impl<Id: SiteId> Instruction<Id> for Instr<Id> {
         }
     }
 
-    fn local_goto_pos(&mut self) -> GotoTarget {
+    fn local_goto_pos(&mut self) -> GotoTarget<'_> {
         match self {
             Instr::Ctrl(instr) => instr.local_goto_pos(),
             Instr::Gfa(instr) => Instruction::<Id>::local_goto_pos(instr),
@@ -234,7 +352,18 @@ impl<Id: SiteId> Instruction<Id> for Instr<Id> {
         core: &mut Core<Id, Self::Core>,
         context: &Self::Context<'_>,
     ) -> ExecStep<Site<Id>> {
-        match self {
+        let cycles = Instruction::<Id>::complexity(self);
+        let complexity = context.complexity.get().saturating_add(cycles);
+        context.complexity.set(complexity);
+        #[cfg(feature = "profiling")]
+        let depth = core.cp();
+        let step = match self {
+            Instr::Ctrl(CtrlInstr::Fn { .. } | CtrlInstr::Call { .. })
+                if context.max_call_depth.is_some_and(|max| core.cp() >= max) =>
+            {
+                context.call_depth_exceeded.set(true);
+                ExecStep::Fail
+            }
             Instr::Ctrl(instr) => {
                 let mut subcore = core.subcore();
                 let step = instr.exec(site, &mut subcore, &());
@@ -254,7 +383,18 @@ impl<Id: SiteId> Instruction<Id> for Instr<Id> {
                 core.merge_subcore(subcore);
                 step
             }
+        };
+        #[cfg(feature = "profiling")]
+        if let Some(profiler) = context.profiler {
+            let nested = core.cp() > depth;
+            let target = match step {
+                ExecStep::Call(target) => Some((target, nested)),
+                ExecStep::Jump(pos) if nested => Some((Site::new(site.prog_id, pos), nested)),
+                _ => None,
+            };
+            profiler.record(site, depth, cycles, target);
         }
+        step
     }
 }
 
@@ -376,7 +516,48 @@ mod test {
             immutable_input: &[state],
             destructible_output: &[StateCell { data: state, auth: strict_dumb!(), lock: None }],
             immutable_output: &[StateData { value: state, raw: None }],
+            globals: &[],
+            ..default!()
+        };
+        let mut vm_main =
+            Vm::<Instr<LibId>>::with(CoreConfig { halt: true, complexity_lim: None }, GfaConfig {
+                field_order: FIELD_ORDER_SECP,
+            });
+        let resolver = |_: LibId| Some(&lib);
+        let status = vm_main.exec(LibSite::new(lib.lib_id(), 0), &context, resolver);
+        assert_eq!(status, Status::Ok);
+    }
+
+    #[test]
+    fn exec_globals() {
+        const VALUE: u32 = 1234567890u32;
+        let code = uasm! {
+            cknxg;
+            chk     CO;
+            ldg;
+            chk     CO;
+            put     E2, VALUE;
+            eq      EA, E2;
+            chk     CO;
+            ldg;
+            not     CO;
+            chk     CO;
+            rstg;
+            ldg;
+            chk     CO;
+            eq      EA, E2;
+            chk     CO;
+            cknxg;
+            not     CO;
+            chk     CO;
+            stop;
         };
+        let lib = Lib::assemble(&code).unwrap();
+        assert_eq!(lib.disassemble::<Instr<_>>().unwrap(), code);
+
+        let state = StateValue::Single { first: fe256::from(VALUE) };
+        let globals = [StateData { value: state, raw: None }];
+        let mut context = VmContext { globals: &globals, ..default!() };
         let mut vm_main =
             Vm::<Instr<LibId>>::with(CoreConfig { halt: true, complexity_lim: None }, GfaConfig {
                 field_order: FIELD_ORDER_SECP,
@@ -384,5 +565,23 @@ mod test {
         let resolver = |_: LibId| Some(&lib);
         let status = vm_main.exec(LibSite::new(lib.lib_id(), 0), &context, resolver);
         assert_eq!(status, Status::Ok);
+
+        context.globals = &[];
+        vm_main.reset();
+        let status = vm_main.exec(LibSite::new(lib.lib_id(), 0), &context, resolver);
+        assert_eq!(status, Status::Fail);
+    }
+
+    #[test]
+    fn exec_globals_saturated() {
+        let state = StateValue::Single { first: fe256::from(1u8) };
+        let globals = [StateData { value: state, raw: None }];
+        let context = VmContext { globals: &globals, ..default!() };
+        let mut core = UsonicCore::with(GfaConfig { field_order: FIELD_ORDER_SECP });
+        core.ug = u16::MAX;
+        assert!(!core.has_global(&context));
+        assert!(!core.load_global(&context));
+        assert_eq!(core.ug, u16::MAX);
+        assert_eq!(core.get(RegE::EA), None);
     }
 }
@@ -148,4 +148,33 @@ pub enum UsonicInstr {
     /// Does not affect the value of `CO` or `CK` registers.
     #[display("rsto    :immutable")]
     RstOAo,
+
+    /// Checks whether there is a next contract-global parameter and sets `CO` register
+    /// accordingly.
+    ///
+    /// # ISA version
+    ///
+    /// The contract-global parameter instructions (`cknxg`, `ldg` and `rstg`) were added in
+    /// `ultrasonic` v0.12 of the `USONIC` ISA extension. The earlier versions do not recognize
+    /// their opcodes, thus the libraries using them must not be used by the codices verified by
+    /// hosts running the earlier versions.
+    #[display("cknxg")]
+    CkNxG,
+
+    /// Load next [`StateValue`] from the contract-global parameters to `EA`-`ED` registers.
+    ///
+    /// If the next state value is absent, sets `CO` to a failed state. Otherwise, resets `CO`.
+    /// Only the first `u16::MAX` parameters are accessible.
+    ///
+    /// Added in `ultrasonic` v0.12 (see [`Self::CkNxG`]).
+    #[display("ldg")]
+    LdG,
+
+    /// Resets iterator over contract-global parameters by setting `UG` value to zero.
+    ///
+    /// Does not affect the value of `CO` or `CK` registers.
+    ///
+    /// Added in `ultrasonic` v0.12 (see [`Self::CkNxG`]).
+    #[display("rstg")]
+    RstG,
 }
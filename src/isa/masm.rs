@@ -27,6 +27,7 @@
 ///
 /// ```
 /// ##![cfg_attr(coverage_nightly, feature(coverage_attribute), coverage(off))]
+/// # extern crate alloc;
 /// use ultrasonic::{uasm, Instr, VmContext};
 /// use zkaluvm::alu::regs::Status;
 /// use zkaluvm::alu::{Lib, LibId, LibSite, Vm};
@@ -58,6 +59,8 @@
 ///     immutable_input: &[],
 ///     destructible_output: &[],
 ///     immutable_output: &[],
+///     globals: &[],
+///     ..Default::default()
 /// };
 /// match vm.exec(LibSite::new(lib.lib_id(), 0), &ctx, |_| Some(&lib)) {
 ///     Status::Ok => println!("success"),
@@ -121,6 +124,16 @@ macro_rules! instr {
         $crate::UsonicInstr::RstOAo.into()
     };
 
+    (cknxg) => {
+        $crate::UsonicInstr::CkNxG.into()
+    };
+    (ldg) => {
+        $crate::UsonicInstr::LdG.into()
+    };
+    (rstg) => {
+        $crate::UsonicInstr::RstG.into()
+    };
+
     { $($tt:tt)+ } => {
         $crate::aluvm::instr! { $( $tt )+ }
     };
@@ -24,7 +24,7 @@
 use aluvm::alu::CoreExt;
 use aluvm::RegE;
 
-use crate::{IoCat, UsonicCore, VmContext};
+use crate::{IoCat, StateValue, UsonicCore, VmContext};
 
 impl UsonicCore {
     /// Checks that there is more state values remain in the given category.
@@ -37,18 +37,52 @@ impl UsonicCore {
     /// category into the `EA`-`ED` registers, increasing `UI` iterator count.
     pub fn load(&mut self, cat: IoCat, context: &VmContext) -> bool {
         let data = context.state_value(cat, self.ui[cat.index()]);
-        let co = data.is_some();
-        let data = data.unwrap_or_default();
-        self.gfa.put(RegE::EA, data.get(0));
-        self.gfa.put(RegE::EB, data.get(1));
-        self.gfa.put(RegE::EC, data.get(2));
-        self.gfa.put(RegE::ED, data.get(3));
+        let co = self.put_value(data);
         if co {
             self.ui[cat.index()] += 1;
+            if cat == IoCat::IN_AO {
+                let loaded = context.immutable_loaded.get().max(self.ui[cat.index()]);
+                context.immutable_loaded.set(loaded);
+            }
         }
         co
     }
 
     /// Reset a value (set to zero) of an `UI` register.
     pub fn reset(&mut self, cat: IoCat) { self.ui[cat.index()] = 0; }
+
+    /// Checks that there is more contract-global parameters remain.
+    ///
+    /// Since the `UG` iterator can't advance past `u16::MAX`, the parameters starting from this
+    /// index are not accessible.
+    pub fn has_global(&mut self, context: &VmContext) -> bool {
+        self.ug < u16::MAX && context.global_value(self.ug).is_some()
+    }
+
+    /// Loads next contract-global parameter (basing on iterator position from `UG` index) into
+    /// the `EA`-`ED` registers, increasing `UG` iterator count.
+    pub fn load_global(&mut self, context: &VmContext) -> bool {
+        let Some(next) = self.ug.checked_add(1) else {
+            return self.put_value(None);
+        };
+        let co = self.put_value(context.global_value(self.ug));
+        if co {
+            self.ug = next;
+        }
+        co
+    }
+
+    /// Reset a value (set to zero) of an `UG` register.
+    pub fn reset_global(&mut self) { self.ug = 0; }
+
+    /// Puts the state value into the `EA`-`ED` registers, returning whether the value was present.
+    fn put_value(&mut self, data: Option<StateValue>) -> bool {
+        let co = data.is_some();
+        let data = data.unwrap_or_default();
+        self.gfa.put(RegE::EA, data.get(0));
+        self.gfa.put(RegE::EB, data.get(1));
+        self.gfa.put(RegE::EC, data.get(2));
+        self.gfa.put(RegE::ED, data.get(3));
+        co
+    }
 }
@@ -31,4 +31,6 @@ mod masm;
 
 pub use self::core::{IoCat, UsonicCore};
 pub use self::exec::VmContext;
+#[cfg(feature = "test-utils")]
+pub use self::exec::VmContextBuilder;
 pub use self::instr::{Instr, UsonicInstr, ISA_ULTRASONIC};
@@ -0,0 +1,236 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use amplify::confinement::Confined;
+use amplify::ByteArray;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{CallError, CellAddr, Memory, StateCell, StateData, StateValue};
+
+/// Maximal length of a strict-serialized memory cell stored in a key-value database.
+pub const KV_VALUE_MAX_LEN: usize = 0xFF_FFFF;
+
+/// Length of a key under which a memory cell is stored in a key-value database.
+pub const KV_KEY_LEN: usize = 1 + 32 + 2;
+
+/// Key prefix of the destructible memory cells.
+const PREFIX_DESTRUCTIBLE: u8 = 0;
+/// Key prefix of the immutable memory cells.
+const PREFIX_IMMUTABLE: u8 = 1;
+
+/// Read access to a key-value database, like a read transaction of an embedded database used by a
+/// node to keep the contract state.
+pub trait KvRead {
+    /// Fetches the value stored under the `key`.
+    fn get_value(&self, key: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// Contract memory reading the memory cells from a key-value database.
+///
+/// The destructible memory cells are stored as strict-serialized [`StateCell`]s, and the immutable
+/// memory cells as strict-serialized [`StateData`]s, under the keys constructed by
+/// [`Self::destructible_key`] and [`Self::immutable_key`]. The values to store can be produced
+/// with [`Self::destructible_value`] and [`Self::immutable_value`].
+///
+/// Values which can't be deserialized are reported by [`Memory::try_destructible`] and
+/// [`Memory::try_immutable`] as [`CallError::CorruptedCell`], such that the verification fails
+/// instead of taking a corrupted cell for an absent one (the verification of some operations, for
+/// instance with [`crate::Codex::absent_immutable_as_none`] set, may succeed in the absence of a
+/// memory cell). The addresses of such cells are also recorded (see [`Self::corrupted`]).
+#[derive(Clone, Debug)]
+pub struct KvMemory<T: KvRead> {
+    txn: T,
+    corrupted: RefCell<BTreeSet<CellAddr>>,
+}
+
+impl<T: KvRead> KvMemory<T> {
+    /// Constructs a memory reading from a database transaction `txn`.
+    pub fn new(txn: T) -> Self { Self { txn, corrupted: none!() } }
+
+    /// Constructs a key of a destructible memory cell.
+    ///
+    /// The key consists of a prefix byte, the operation id and the output number in big-endian
+    /// encoding, such that the cells of the same kind are ordered by their addresses.
+    pub fn destructible_key(addr: CellAddr) -> [u8; KV_KEY_LEN] { key(PREFIX_DESTRUCTIBLE, addr) }
+
+    /// Constructs a key of an immutable memory cell (see [`Self::destructible_key`]).
+    pub fn immutable_key(addr: CellAddr) -> [u8; KV_KEY_LEN] { key(PREFIX_IMMUTABLE, addr) }
+
+    /// Serializes a destructible memory cell into a value to be stored in the database.
+    pub fn destructible_value(cell: &StateCell) -> Vec<u8> {
+        cell.to_strict_serialized::<KV_VALUE_MAX_LEN>()
+            .expect("state cell has a fixed size")
+            .release()
+    }
+
+    /// Serializes an immutable memory cell into a value to be stored in the database.
+    pub fn immutable_value(data: &StateData) -> Vec<u8> {
+        data.to_strict_serialized::<KV_VALUE_MAX_LEN>()
+            .expect("raw data are limited to 64kiB")
+            .release()
+    }
+
+    /// Returns addresses of the memory cells which values were present in the database, but
+    /// couldn't be deserialized.
+    pub fn corrupted(&self) -> BTreeSet<CellAddr> { self.corrupted.borrow().clone() }
+
+    /// Returns the underlying database transaction.
+    pub fn txn(&self) -> &T { &self.txn }
+
+    /// Consumes the memory, returning the underlying database transaction.
+    pub fn into_txn(self) -> T { self.txn }
+
+    fn read<V: StrictDeserialize>(
+        &self,
+        key: [u8; KV_KEY_LEN],
+        addr: CellAddr,
+    ) -> Result<Option<V>, CallError> {
+        let Some(value) = self.txn.get_value(&key) else {
+            return Ok(None);
+        };
+        let value = Confined::try_from(value)
+            .ok()
+            .and_then(|value| V::from_strict_serialized::<KV_VALUE_MAX_LEN>(value).ok());
+        if value.is_none() {
+            self.corrupted.borrow_mut().insert(addr);
+            return Err(CallError::CorruptedCell(addr));
+        }
+        Ok(value)
+    }
+}
+
+impl<T: KvRead> Memory for KvMemory<T> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        self.try_destructible(addr).ok().flatten()
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.try_immutable(addr).ok().flatten()
+    }
+
+    fn try_destructible(&self, addr: CellAddr) -> Result<Option<StateCell>, CallError> {
+        self.read(Self::destructible_key(addr), addr)
+    }
+
+    fn try_immutable(&self, addr: CellAddr) -> Result<Option<StateValue>, CallError> {
+        Ok(self
+            .read::<StateData>(Self::immutable_key(addr), addr)?
+            .map(|data| data.value))
+    }
+}
+
+fn key(prefix: u8, addr: CellAddr) -> [u8; KV_KEY_LEN] {
+    let mut key = [0u8; KV_KEY_LEN];
+    key[0] = prefix;
+    key[1..33].copy_from_slice(&addr.opid.to_byte_array());
+    key[33..].copy_from_slice(&addr.pos.to_be_bytes());
+    key
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use std::collections::HashMap;
+
+    use aluvm::alu::{Lib, LibSite};
+    use commit_verify::{Digest, Sha256};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{uasm, AuthToken, CallError, Codex, ContractId, Input, Operation, Opid, RawData};
+
+    impl KvRead for HashMap<Vec<u8>, Vec<u8>> {
+        fn get_value(&self, key: &[u8]) -> Option<Vec<u8>> { self.get(key).cloned() }
+    }
+
+    #[test]
+    fn kv_memory() {
+        let opid = Opid::from_byte_array(Sha256::digest(b"prev"));
+        let cell = StateCell {
+            data: StateValue::new(1u8, 2u8),
+            auth: AuthToken::strict_dumb(),
+            lock: None,
+        };
+        let data = StateData::with_raw(3u8, 4u8, RawData::from(small_blob!(0xAB, 0xCD)));
+        let (destructible, immutable) = (CellAddr::new(opid, 0), CellAddr::new(opid, 1));
+
+        let mut store = HashMap::new();
+        store.insert(
+            KvMemory::<HashMap<_, _>>::destructible_key(destructible).to_vec(),
+            KvMemory::<HashMap<_, _>>::destructible_value(&cell),
+        );
+        store.insert(
+            KvMemory::<HashMap<_, _>>::immutable_key(immutable).to_vec(),
+            KvMemory::<HashMap<_, _>>::immutable_value(&data),
+        );
+        let memory = KvMemory::new(store);
+        assert_eq!(memory.destructible(destructible), Some(cell));
+        assert_eq!(memory.immutable(immutable), Some(data.value));
+        assert_eq!(memory.destructible(immutable), None);
+        assert_eq!(memory.immutable(destructible), None);
+        assert!(memory.corrupted().is_empty());
+
+        let lib = Lib::assemble(&uasm! { stop; }).unwrap();
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib.lib_id(), 0) };
+        let contract_id = ContractId::strict_dumb();
+        let mut operation = Operation::strict_dumb();
+        operation.destructible_in = small_vec![Input { addr: destructible, witness: none!() }];
+        operation.immutable_in = small_vec![immutable];
+        assert!(codex
+            .verify(contract_id, operation.clone(), &memory, &lib)
+            .is_ok());
+        operation.immutable_in = small_vec![destructible];
+        assert_eq!(
+            codex.verify(contract_id, operation, &memory, &lib),
+            Err(CallError::NoImmutableInput(destructible))
+        );
+    }
+
+    #[test]
+    fn kv_memory_corrupted() {
+        let addr = CellAddr::new(Opid::from_byte_array(Sha256::digest(b"prev")), 1);
+        let mut store = HashMap::new();
+        store.insert(KvMemory::<HashMap<_, _>>::immutable_key(addr).to_vec(), vec![0xFF; 3]);
+        let memory = KvMemory::new(store);
+        assert_eq!(memory.immutable(addr), None);
+        assert_eq!(memory.try_immutable(addr), Err(CallError::CorruptedCell(addr)));
+        assert_eq!(memory.corrupted(), bset![addr]);
+
+        // The corrupted cell is not taken for an absent one even when absent cells are allowed
+        let lib = Lib::assemble(&uasm! { stop; }).unwrap();
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(lib.lib_id(), 0) };
+        codex.absent_immutable_as_none = true;
+        let mut operation = Operation::strict_dumb();
+        operation.immutable_in = small_vec![addr];
+        assert_eq!(
+            codex.verify(ContractId::strict_dumb(), operation, &memory, &lib),
+            Err(CallError::CorruptedCell(addr))
+        );
+    }
+}
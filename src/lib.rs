@@ -68,23 +68,70 @@ extern crate serde;
 #[macro_use]
 #[cfg(feature = "serde")]
 mod macros;
+mod archive;
+mod cache;
+mod cas;
 mod codex;
+mod diff;
+mod errors;
+mod hash;
 mod state;
 mod operation;
 mod isa;
 mod issue;
+#[cfg(feature = "kv")]
+mod kv;
+mod memory;
+mod policy;
+#[cfg(feature = "profiling")]
+mod profile;
+mod report;
+mod render;
+mod root;
+mod sealed;
 #[cfg(feature = "stl")]
 pub mod stl;
 mod util;
 
-pub use codex::{CallError, CallId, Codex, CodexId, LibRepo, Memory};
+pub use archive::{write_operations, OpArchiveReader, OpArchiveWriter, OPERATION_MAX_LEN};
+pub use cache::VerifyCache;
+pub use cas::{BlobStore, CasLibRepo, LIB_BLOB_MAX_LEN};
+#[cfg(feature = "test-utils")]
+pub use codex::RegMismatch;
+pub use codex::{
+    CallDescriptor, CallError, CallId, CallIdSelector, Codex, CodexBuilder, CodexId, CodexManifest,
+    ConfigSummary, CoverageTracker, ElementLocation, LibRepo, LockOutcome, Memory,
+    VerifierSelector, WitnessBank,
+};
+pub use diff::{memory_diff, CellsDiff, MemoryDiff};
+pub use errors::{CodexError, CollectedError, MergeError};
+pub use hash::CommitHash;
+#[cfg(feature = "chf-sha256")]
+pub use hash::Sha256Hash;
+#[cfg(feature = "test-utils")]
+pub use isa::VmContextBuilder;
 pub use isa::{Instr, IoCat, UsonicCore, UsonicInstr, VmContext, ISA_ULTRASONIC};
 pub use issue::{Consensus, ContractId, ContractMeta, ContractName, Issue};
+#[cfg(feature = "kv")]
+pub use kv::{KvMemory, KvRead, KV_KEY_LEN, KV_VALUE_MAX_LEN};
+pub use memory::{
+    CommitmentMemory, CommittedMemory, HeightScopedMemory, HistoricMemory, PrevOutputMemory,
+};
 #[cfg(feature = "baid64")]
 pub use operation::ParseAddrError;
-pub use operation::{CellAddr, Genesis, Input, Operation, Opid, VerifiedOperation};
+pub use operation::{CellAddr, Genesis, Input, Operation, Opid, StateEffects, VerifiedOperation};
+pub use policy::{
+    CodexPolicy, MinInputs, OutputLocksResolve, PostCondition, PreCondition, RequiredCallIds,
+    UniqueOutputTokens, VerifyOptions, VerifyPolicy,
+};
+#[cfg(feature = "profiling")]
+pub use profile::{Profile, Profiler};
+pub use render::{EnglishRenderer, ErrorCatalog, ErrorRenderer};
+pub use report::{LockDiff, ReportDiff, UpgradeDivergence, UpgradeReport, VerificationReport};
+pub use root::{DestructibleLeaf, ImmutableLeaf, StateRoot};
+pub use sealed::SealedCodex;
 pub use state::{AuthToken, RawData, StateCell, StateData, StateValue};
-pub use util::Identity;
+pub use util::{DeveloperKey, DeveloperSig, Identity, SigScheme};
 pub use zkaluvm::fe256;
 
 /// Strict type library name for the types defined in this crate.
@@ -0,0 +1,313 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use aluvm::fe256;
+
+use crate::{
+    CallError, CellAddr, Memory, Operation, Opid, StateCell, StateData, StateValue,
+    VerifiedOperation,
+};
+
+/// Memory holding the cells gathered in advance for [`crate::Codex::verify_prepared`].
+pub(crate) struct PreparedMemory<'c> {
+    pub read_once: BTreeMap<CellAddr, &'c StateCell>,
+    pub immutable: BTreeMap<CellAddr, &'c StateData>,
+}
+
+impl<'c> PreparedMemory<'c> {
+    /// Pairs the input addresses with the cells provided for them, checking that the cells
+    /// provided for the same address are the same.
+    pub fn collect<T: PartialEq>(
+        addrs: impl Iterator<Item = CellAddr>,
+        cells: &'c [T],
+    ) -> Result<BTreeMap<CellAddr, &'c T>, CallError> {
+        let mut map = BTreeMap::new();
+        for (addr, cell) in addrs.zip(cells) {
+            match map.insert(addr, cell) {
+                Some(prev) if prev != cell => return Err(CallError::PreparedInputConflict(addr)),
+                _ => {}
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl Memory for PreparedMemory<'_> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        self.read_once.get(&addr).map(|cell| **cell)
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.immutable.get(&addr).map(|data| data.value)
+    }
+}
+
+/// Memory of an ultra-light client, which holds full destructible memory cells, but only
+/// commitments to the immutable memory cells (see [`StateValue::field_commitment`]).
+///
+/// Operations can be verified against such memory with [`crate::Codex::verify_committed`]. In this
+/// mode the lock conditions of the destructible inputs are checked as usual, but the verification
+/// script receives a commitment as a [`StateValue::Single`] value in place of each of the
+/// immutable inputs; thus, the verifier library must be written against the commitments.
+pub trait CommitmentMemory {
+    /// Read a destructible memory cell created by a specific operation read-once output (the same
+    /// as [`Memory::destructible`]).
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell>;
+    /// Read a commitment to an immutable memory cell created by a specific operation immutable
+    /// output.
+    fn immutable_commitment(&self, addr: CellAddr) -> Option<fe256>;
+}
+
+/// Adaptor presenting [`CommitmentMemory`] as [`Memory`], returning the immutable memory cell
+/// commitments as [`StateValue::Single`] values.
+#[derive(Copy, Clone, Debug)]
+pub struct CommittedMemory<'m, M: CommitmentMemory>(pub &'m M);
+
+impl<M: CommitmentMemory> Memory for CommittedMemory<'_, M> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> { self.0.destructible(addr) }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.0
+            .immutable_commitment(addr)
+            .map(|first| StateValue::Single { first })
+    }
+}
+
+/// Memory keeping the history of the memory cells, which allows re-validating operations against
+/// the contract state at some past height (see [`HeightScopedMemory`]).
+///
+/// Unlike [`Memory`], which provides only the most recent contract state, the historic memory
+/// must also provide the destructible memory cells which were already spent.
+pub trait HistoricMemory: Memory {
+    /// Returns the height at which the memory cell was created, or `None` if the cell is not known.
+    fn created_at(&self, addr: CellAddr) -> Option<u64>;
+
+    /// Returns the height at which the destructible memory cell was spent, or `None` if the cell
+    /// is not spent (or is an immutable memory cell).
+    fn spent_at(&self, addr: CellAddr) -> Option<u64>;
+}
+
+/// Memory providing the contract state as of a given height, used to re-validate historic
+/// operations with [`crate::Codex::verify_at_height`].
+///
+/// The memory exposes the state in which the operations included at the `height` were verified:
+/// - memory cells created at a greater height are filtered out;
+/// - destructible memory cells spent at the `height` or later are exposed as unspent, since the
+///   operations included at the `height` have spent them.
+///
+/// The memory cells created at the `height` are exposed, such that an operation spending a cell
+/// created by another operation included at the same height can be re-validated.
+#[derive(Copy, Clone, Debug)]
+pub struct HeightScopedMemory<'m, M: HistoricMemory> {
+    /// Memory keeping the state history.
+    pub memory: &'m M,
+    /// Height as of which the state is provided.
+    pub height: u64,
+}
+
+impl<'m, M: HistoricMemory> HeightScopedMemory<'m, M> {
+    /// Constructs a memory providing the state of the historic `memory` as of the `height`.
+    pub fn new(memory: &'m M, height: u64) -> Self { Self { memory, height } }
+
+    fn is_created(&self, addr: CellAddr) -> bool {
+        self.memory
+            .created_at(addr)
+            .is_some_and(|created| created <= self.height)
+    }
+}
+
+impl<M: HistoricMemory> Memory for HeightScopedMemory<'_, M> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        if !self.is_created(addr) {
+            return None;
+        }
+        if self
+            .memory
+            .spent_at(addr)
+            .is_some_and(|spent| spent < self.height)
+        {
+            return None;
+        }
+        self.memory.destructible(addr)
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        if !self.is_created(addr) {
+            return None;
+        }
+        self.memory.immutable(addr)
+    }
+}
+
+/// Memory consisting only of the outputs of a single operation, which allows verifying an
+/// operation spending the outputs of the preceding one without a full memory store.
+///
+/// The memory cells are addressed by the id of the previous operation and the output number in
+/// its [`Operation::destructible_out`] or [`Operation::immutable_out`]. This is a convenience for
+/// testing and small pipelines of chained operations; the memory doesn't track which cells were
+/// already spent.
+#[derive(Clone, Debug)]
+pub struct PrevOutputMemory<'o> {
+    operation: &'o Operation,
+    opid: Opid,
+}
+
+impl<'o> PrevOutputMemory<'o> {
+    /// Constructs a memory holding the outputs of the previous `operation`.
+    pub fn new(operation: &'o Operation) -> Self { Self { operation, opid: operation.opid() } }
+
+    /// Returns the id of the operation which outputs are held by the memory.
+    pub fn opid(&self) -> Opid { self.opid }
+
+    /// Returns the address of the memory cell defined by the operation output number `pos`.
+    pub fn addr(&self, pos: u16) -> CellAddr { CellAddr::new(self.opid, pos) }
+}
+
+impl Memory for PrevOutputMemory<'_> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        if addr.opid != self.opid {
+            return None;
+        }
+        self.operation
+            .destructible_out
+            .get(addr.pos as usize)
+            .copied()
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        if addr.opid != self.opid {
+            return None;
+        }
+        self.operation
+            .immutable_out
+            .get(addr.pos as usize)
+            .map(|data| data.value)
+    }
+}
+
+/// Memory overlaying the cells created and spent by a sequence of verified operations over the
+/// underlying memory, used by [`crate::Codex::bisect_bundle`].
+pub(crate) struct OverlayMemory<'m, M: Memory> {
+    memory: &'m M,
+    spent: BTreeSet<CellAddr>,
+    destructible: BTreeMap<CellAddr, StateCell>,
+    immutable: BTreeMap<CellAddr, StateValue>,
+}
+
+impl<'m, M: Memory> OverlayMemory<'m, M> {
+    pub fn new(memory: &'m M) -> Self {
+        Self {
+            memory,
+            spent: none!(),
+            destructible: none!(),
+            immutable: none!(),
+        }
+    }
+
+    pub fn apply(&mut self, verified: &VerifiedOperation) {
+        let operation = verified.as_operation();
+        let effects = verified.effects();
+        for addr in effects.consumed {
+            if self.destructible.remove(&addr).is_none() {
+                self.spent.insert(addr);
+            }
+        }
+        for (addr, cell) in effects
+            .created_destructible
+            .into_iter()
+            .zip(&operation.destructible_out)
+        {
+            self.destructible.insert(addr, *cell);
+        }
+        for (addr, data) in effects
+            .created_immutable
+            .into_iter()
+            .zip(&operation.immutable_out)
+        {
+            self.immutable.insert(addr, data.value);
+        }
+    }
+}
+
+impl<M: Memory> Memory for OverlayMemory<'_, M> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        if let Some(cell) = self.destructible.get(&addr) {
+            return Some(*cell);
+        }
+        if self.spent.contains(&addr) {
+            return None;
+        }
+        self.memory.destructible(addr)
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.immutable
+            .get(&addr)
+            .copied()
+            .or_else(|| self.memory.immutable(addr))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    //! Memory and library repository stubs shared by the tests of all modules.
+
+    use std::collections::HashMap;
+
+    use aluvm::alu::{Lib, LibId};
+
+    use super::*;
+    use crate::LibRepo;
+
+    #[derive(Clone, Eq, PartialEq, Debug, Default)]
+    pub struct DumbMemory {
+        pub destructible: HashMap<CellAddr, StateCell>,
+        pub immutable: HashMap<CellAddr, StateValue>,
+    }
+
+    impl Memory for DumbMemory {
+        fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+            self.destructible.get(&addr).copied()
+        }
+
+        fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+            self.immutable.get(&addr).copied()
+        }
+    }
+
+    impl LibRepo for Lib {
+        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+            if lib_id == self.lib_id() {
+                Some(self)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl LibRepo for HashMap<LibId, Lib> {
+        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> { self.get(&lib_id) }
+    }
+}
@@ -28,13 +28,18 @@ use core::hash::{Hash, Hasher};
 pub use _baid64::ParseAddrError;
 use aluvm::fe256;
 use amplify::confinement::SmallVec;
-use amplify::{ByteArray, Bytes32};
+use amplify::num::u256;
+use amplify::{ByteArray, Bytes32, WriteCounter};
 use commit_verify::{
     CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, MerkleHash, ReservedBytes,
     Sha256,
 };
+use strict_encoding::{StreamWriter, StrictDeserialize, StrictEncode, StrictSerialize};
 
-use crate::{CallId, CodexId, ContractId, StateCell, StateData, StateValue, LIB_NAME_ULTRASONIC};
+use crate::{
+    CallError, CallId, Codex, CodexId, ContractId, ElementLocation, LibRepo, Memory, StateCell,
+    StateData, StateValue, LIB_NAME_ULTRASONIC,
+};
 
 /// Unique operation (genesis, extensions & state transition) identifier
 /// equivalent to the commitment hash
@@ -148,7 +153,7 @@ mod _baid64 {
     }
 
     /// Errors during parsing [`CellAddr`] from a string representation.
-    #[derive(Debug, Display, From, Error)]
+    #[derive(Debug, Display, From)]
     #[display(doc_comments)]
     pub enum ParseAddrError {
         /// malformed string representation of cell address '{0}' lacking separator ':'
@@ -163,6 +168,16 @@ mod _baid64 {
         InvalidOpid(Baid64ParseError),
     }
 
+    impl std::error::Error for ParseAddrError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                ParseAddrError::MalformedSeparator(_) => None,
+                ParseAddrError::InvalidOut(err) => Some(err),
+                ParseAddrError::InvalidOpid(err) => Some(err),
+            }
+        }
+    }
+
     impl FromStr for CellAddr {
         type Err = ParseAddrError;
 
@@ -373,6 +388,13 @@ pub struct Operation {
     pub destructible_in: SmallVec<Input>,
     /// A list of append-only immutable memory cells which this operation may read.
     ///
+    /// The immutable inputs are independent of [`Self::destructible_in`]: they may reference any
+    /// immutable memory cell of the contract, including the cells defined by operations none of
+    /// which destructible cells are spent, allowing contracts to consult a shared state. The cells
+    /// are only read and not consumed, and their values are provided to the verifier in the order
+    /// of this list (see [`crate::VmContext::immutable_input`]), separately from the destructible
+    /// inputs.
+    ///
     /// The list may be empty.
     pub immutable_in: SmallVec<CellAddr>,
     /// A list of the state for the new destructible memory cells which are created by the
@@ -400,6 +422,9 @@ impl Hash for Operation {
     fn hash<H: Hasher>(&self, state: &mut H) { state.write(&self.opid().to_byte_array()); }
 }
 
+impl StrictSerialize for Operation {}
+impl StrictDeserialize for Operation {}
+
 impl CommitEncode for Operation {
     type CommitmentId = Opid;
 
@@ -422,6 +447,157 @@ impl Operation {
     /// returned by [`Self::commit_id`].
     #[inline]
     pub fn opid(&self) -> Opid { self.commit_id() }
+
+    /// Sorts the operation outputs into the canonical order, eliminating the malleability of the
+    /// operation id from the order of the outputs.
+    ///
+    /// Both destructible and immutable outputs are sorted by their commitment hashes (the same
+    /// `MerkleHash` values which are merklized into the operation id). Callers run the method
+    /// before computing the operation id, such that operations with the same set of outputs
+    /// share the same id. The method is idempotent.
+    ///
+    /// The inputs are not sorted: their order is semantically meaningful, since it defines the
+    /// order of the values in the verifier context and the bridged values (see
+    /// [`Codex::input_bridge`]). Since the method changes the output positions, and thus the
+    /// addresses of the memory cells created by the operation, it must not be used with the
+    /// contracts which verifiers depend on the order of the outputs.
+    pub fn canonicalize(&mut self) {
+        self.destructible_out
+            .as_mut()
+            .sort_by_cached_key(|cell| cell.commit_id());
+        self.immutable_out
+            .as_mut()
+            .sort_by_cached_key(|data| data.commit_id());
+    }
+
+    /// Iterates over the addresses of all memory cells read by the operation: first, its
+    /// destructible inputs, followed by the immutable ones.
+    pub fn input_addrs(&self) -> impl Iterator<Item = CellAddr> + '_ {
+        self.destructible_in
+            .iter()
+            .map(|input| input.addr)
+            .chain(self.immutable_in.iter().copied())
+    }
+
+    /// Iterates over the witnesses of the destructible inputs, pairing each of them with the input
+    /// index in [`Self::destructible_in`] and the address of the destroyed memory cell.
+    ///
+    /// Inputs without a witness are also included, with [`StateValue::None`] witness.
+    pub fn witnesses(&self) -> impl Iterator<Item = (u16, CellAddr, &StateValue)> + '_ {
+        self.destructible_in
+            .iter()
+            .enumerate()
+            .map(|(no, input)| (no as u16, input.addr, &input.witness))
+    }
+
+    /// Iterates over the addresses of all memory cells referenced by the operation: its inputs (see
+    /// [`Self::input_addrs`]), followed by the addresses of the destructible and then immutable
+    /// memory cells defined by the operation outputs.
+    ///
+    /// Since destructible and immutable memory cells are kept in different memories, an output
+    /// address may be repeated, pointing to a cell in each of the memories.
+    pub fn all_addrs(&self) -> impl Iterator<Item = CellAddr> + '_ {
+        let opid = self.opid();
+        let destructible = self.destructible_out.len() as u16;
+        let immutable = self.immutable_out.len() as u16;
+        self.input_addrs().chain(
+            (0..destructible)
+                .chain(0..immutable)
+                .map(move |pos| CellAddr::new(opid, pos)),
+        )
+    }
+
+    /// Detects whether the operation is a burn, i.e. destroys some destructible memory cells
+    /// without creating any new ones.
+    ///
+    /// Burns have destructible inputs, but no destructible outputs; they still may define
+    /// immutable memory cells (for instance, recording the reason of the burn). See
+    /// [`crate::VerifyPolicy::burn_call`] for the verification of the burns.
+    pub fn is_burn(&self) -> bool {
+        !self.destructible_in.is_empty() && self.destructible_out.is_empty()
+    }
+
+    /// Checks that all the field elements of the destructible input witnesses and of the output
+    /// values are members of the field with the `field_order`, i.e. are below the field order.
+    ///
+    /// This is a cheap pre-pass catching malformed operation data before running any VM; it is
+    /// performed by [`Codex::verify_with_policy`] if
+    /// [`crate::VerifyPolicy::reject_non_field_elements`] is set. The witnesses of the locked
+    /// inputs are always checked by the consensus verification before running their lock scripts,
+    /// reporting the same error. The raw data of the immutable
+    /// outputs ([`StateData::raw`]) are not field elements and are not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::FieldMembership`] for the first element which is not a member of the
+    /// field, checking the witnesses first, then the destructible and immutable outputs.
+    pub fn validate_field_membership(&self, field_order: u256) -> Result<(), CallError> {
+        for (no, input) in self.destructible_in.iter().enumerate() {
+            if let Some(index) = input.witness.non_member(field_order) {
+                let location = ElementLocation::Witness(no as u16, index);
+                return Err(CallError::FieldMembership { location });
+            }
+        }
+        for (no, cell) in self.destructible_out.iter().enumerate() {
+            if let Some(index) = cell.data.non_member(field_order) {
+                let location = ElementLocation::DestructibleOut(no as u16, index);
+                return Err(CallError::FieldMembership { location });
+            }
+        }
+        for (no, data) in self.immutable_out.iter().enumerate() {
+            if let Some(index) = data.value.non_member(field_order) {
+                let location = ElementLocation::ImmutableOut(no as u16, index);
+                return Err(CallError::FieldMembership { location });
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes a plain (untagged) SHA256 digest of the strict-encoded operation data.
+    ///
+    /// Unlike [`Self::opid`], which is a domain-separated commitment used for the identification
+    /// of the operation across the systems, the digest is intended for the local use only, for
+    /// instance for the deduplication of operations in a mempool. Since the strict encoding is
+    /// canonical, equal operations always have the same digest. Operations differing only in the
+    /// order of their outputs have different digests, unless they are canonicalized first (see
+    /// [`Self::canonicalize`]).
+    ///
+    /// The operation data are streamed into the hasher without serializing them into memory.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::default();
+        let ok = self
+            .strict_write(StreamWriter::new::<{ usize::MAX }>(&mut hasher))
+            .is_ok();
+        debug_assert!(ok, "hashing can't fail");
+        hasher.finish()
+    }
+
+    /// Computes the exact length of the strict-encoded operation data, including the input
+    /// witnesses, without serializing the operation into memory.
+    ///
+    /// The length equals to the number of bytes transmitted when the operation is sent to other
+    /// parties, and can be used by fee models which weigh operations by their size.
+    pub fn strict_size(&self) -> usize {
+        let mut counter = WriteCounter::default();
+        let ok = self
+            .strict_write(StreamWriter::new::<{ usize::MAX }>(&mut counter))
+            .is_ok();
+        debug_assert!(ok, "counting can't fail");
+        counter.count
+    }
+
+    /// Verifies the operation against a `codex`; a convenience method forwarding to
+    /// [`Codex::verify`], see it for the details on the arguments and errors.
+    #[inline]
+    pub fn verify_against(
+        self,
+        codex: &Codex,
+        contract_id: ContractId,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        codex.verify(contract_id, self, memory, repo)
+    }
 }
 
 /// Provably verified operation, which can be constructed only by running [`Codex::verify`] method.
@@ -455,6 +631,45 @@ impl VerifiedOperation {
     /// Release the operation, discarding the verification status and cached opid.
     #[inline]
     pub fn into_operation(self) -> Operation { self.1 }
+
+    /// Computes the changes to the contract memory produced by the operation.
+    pub fn effects(&self) -> StateEffects {
+        let opid = self.0;
+        StateEffects {
+            consumed: self
+                .1
+                .destructible_in
+                .iter()
+                .map(|input| input.addr)
+                .collect(),
+            created_destructible: (0..self.1.destructible_out.len() as u16)
+                .map(|pos| CellAddr::new(opid, pos))
+                .collect(),
+            created_immutable: (0..self.1.immutable_out.len() as u16)
+                .map(|pos| CellAddr::new(opid, pos))
+                .collect(),
+        }
+    }
+}
+
+/// Changes to the contract memory produced by a verified operation (see
+/// [`VerifiedOperation::effects`]).
+///
+/// A state manager applies the effects by removing the consumed destructible memory cells and
+/// adding the created ones, using the data from the corresponding operation outputs. Immutable
+/// inputs are only read and are not a part of the effects.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct StateEffects {
+    /// Addresses of the destructible memory cells consumed by the operation, in the order of
+    /// [`Operation::destructible_in`].
+    pub consumed: Vec<CellAddr>,
+    /// Addresses of the destructible memory cells created by the operation, in the order of
+    /// [`Operation::destructible_out`].
+    pub created_destructible: Vec<CellAddr>,
+    /// Addresses of the immutable memory cells created by the operation, in the order of
+    /// [`Operation::immutable_out`].
+    pub created_immutable: Vec<CellAddr>,
 }
 
 #[cfg(test)]
@@ -468,6 +683,7 @@ mod test {
     use strict_encoding::StrictDumb;
 
     use super::*;
+    use crate::OPERATION_MAX_LEN;
 
     #[test]
     fn opid_display() {
@@ -497,6 +713,20 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg(feature = "baid64")]
+    fn cell_addr_parse_error_source() {
+        use std::error::Error;
+
+        let err = CellAddr::from_str("n4bQgYhMfWWaL_qgxVrQFaO~TxsrC4Is0V1sFbDwCgg").unwrap_err();
+        assert!(err.source().is_none());
+        let err = CellAddr::from_str("n4bQgYhMfWWaL_qgxVrQFaO~TxsrC4Is0V1sFbDwCgg:x").unwrap_err();
+        assert_eq!(err.source().unwrap().to_string(), u16::from_str("x").unwrap_err().to_string());
+        let err = CellAddr::from_str("usop:n4bQg:1").unwrap_err();
+        assert!(matches!(err, ParseAddrError::InvalidOpid(_)));
+        assert!(err.source().is_some());
+    }
+
     #[test]
     #[cfg(all(feature = "serde", feature = "baid64"))]
     fn opid_serde() {
@@ -533,4 +763,207 @@ mod test {
         let other_contract_id = ContractId::from_byte_array(Sha256::digest(b"test"));
         assert_ne!(genesis.opid(contract_id), genesis.to_operation(other_contract_id).opid())
     }
+
+    #[test]
+    fn operation_addrs() {
+        let mut operation = Genesis::strict_dumb().to_operation(ContractId::strict_dumb());
+        assert_eq!(operation.input_addrs().count(), 0);
+        assert_eq!(operation.all_addrs().count(), 0);
+
+        let prev = Opid::from_byte_array([1u8; 32]);
+        operation
+            .destructible_in
+            .push(Input { addr: CellAddr::new(prev, 1), witness: none!() })
+            .unwrap();
+        operation.immutable_in.push(CellAddr::new(prev, 0)).unwrap();
+        operation
+            .destructible_out
+            .push(StateCell::strict_dumb())
+            .unwrap();
+        operation
+            .immutable_out
+            .push(StateData::strict_dumb())
+            .unwrap();
+        operation
+            .immutable_out
+            .push(StateData::strict_dumb())
+            .unwrap();
+
+        assert_eq!(operation.input_addrs().collect::<Vec<_>>(), vec![
+            CellAddr::new(prev, 1),
+            CellAddr::new(prev, 0)
+        ]);
+        let opid = operation.opid();
+        assert_eq!(operation.all_addrs().collect::<Vec<_>>(), vec![
+            CellAddr::new(prev, 1),
+            CellAddr::new(prev, 0),
+            CellAddr::new(opid, 0),
+            CellAddr::new(opid, 0),
+            CellAddr::new(opid, 1),
+        ]);
+
+        let verified = VerifiedOperation::new_unchecked(opid, operation);
+        assert_eq!(verified.effects(), StateEffects {
+            consumed: vec![CellAddr::new(prev, 1)],
+            created_destructible: vec![CellAddr::new(opid, 0)],
+            created_immutable: vec![CellAddr::new(opid, 0), CellAddr::new(opid, 1)],
+        });
+    }
+
+    #[test]
+    fn canonicalize() {
+        let mut operation = Genesis::strict_dumb().to_operation(ContractId::strict_dumb());
+        for val in [3u8, 1, 2] {
+            let data = StateValue::Single { first: fe256::from(val) };
+            operation
+                .destructible_out
+                .push(StateCell { data, auth: strict_dumb!(), lock: None })
+                .unwrap();
+            operation
+                .immutable_out
+                .push(StateData { value: data, raw: None })
+                .unwrap();
+        }
+        let mut reordered = operation.clone();
+        reordered.destructible_out.as_mut().reverse();
+        reordered.immutable_out.as_mut().swap(0, 1);
+        assert_ne!(reordered.opid(), operation.opid());
+
+        operation.canonicalize();
+        reordered.canonicalize();
+        assert_eq!(reordered.opid(), operation.opid());
+        assert_eq!(reordered.destructible_out, operation.destructible_out);
+
+        let opid = operation.opid();
+        operation.canonicalize();
+        assert_eq!(operation.opid(), opid);
+    }
+
+    #[test]
+    fn operation_digest() {
+        let operation = Genesis::strict_dumb().to_operation(ContractId::strict_dumb());
+        let data = operation
+            .to_strict_serialized::<OPERATION_MAX_LEN>()
+            .unwrap();
+        let decoded = Operation::from_strict_serialized::<OPERATION_MAX_LEN>(data.clone()).unwrap();
+        assert_eq!(operation.digest(), decoded.digest());
+        assert_eq!(operation.digest(), <[u8; 32]>::from(Sha256::digest(data.as_slice())));
+        assert_ne!(operation.digest(), operation.opid().to_byte_array());
+
+        let other = Genesis::strict_dumb().to_operation(ContractId::from_byte_array([1u8; 32]));
+        assert_ne!(operation.digest(), other.digest());
+    }
+
+    #[test]
+    fn operation_digest_canonical() {
+        let mut operation = Genesis::strict_dumb().to_operation(ContractId::strict_dumb());
+        for val in [3u8, 1, 2] {
+            let data = StateValue::Single { first: fe256::from(val) };
+            operation
+                .destructible_out
+                .push(StateCell { data, auth: strict_dumb!(), lock: None })
+                .unwrap();
+            operation
+                .immutable_out
+                .push(StateData { value: data, raw: None })
+                .unwrap();
+        }
+        let mut reordered = operation.clone();
+        reordered.destructible_out.as_mut().reverse();
+        reordered.immutable_out.as_mut().swap(0, 1);
+        assert_ne!(reordered.digest(), operation.digest());
+
+        operation.canonicalize();
+        reordered.canonicalize();
+        assert_eq!(reordered.digest(), operation.digest());
+    }
+
+    #[test]
+    fn is_burn() {
+        let mut operation = Operation::strict_dumb();
+        assert!(!operation.is_burn());
+        operation.destructible_in = small_vec![Input::strict_dumb()];
+        assert!(operation.is_burn());
+        operation.immutable_out = small_vec![StateData::strict_dumb()];
+        assert!(operation.is_burn());
+        operation.destructible_out = small_vec![StateCell::strict_dumb()];
+        assert!(!operation.is_burn());
+    }
+
+    #[test]
+    fn strict_size() {
+        let mut operation = Operation::strict_dumb();
+        let size = operation.strict_size();
+        assert_eq!(
+            size,
+            operation
+                .to_strict_serialized::<OPERATION_MAX_LEN>()
+                .unwrap()
+                .len()
+        );
+
+        operation.destructible_in = small_vec![Input {
+            addr: CellAddr::strict_dumb(),
+            witness: StateValue::new(1u8, 2u8)
+        }];
+        assert_eq!(
+            operation.strict_size(),
+            operation
+                .to_strict_serialized::<OPERATION_MAX_LEN>()
+                .unwrap()
+                .len()
+        );
+        assert!(operation.strict_size() > size);
+    }
+
+    #[test]
+    fn validate_field_membership() {
+        let order = u256::from(7u8);
+        let mut operation = Operation::strict_dumb();
+        assert!(operation.validate_field_membership(order).is_ok());
+
+        let value = StateValue::Double { first: fe256::from(6u8), second: fe256::from(7u8) };
+        operation.immutable_out = small_vec![StateData { value, raw: None }];
+        assert_eq!(
+            operation.validate_field_membership(order),
+            Err(CallError::FieldMembership { location: ElementLocation::ImmutableOut(0, 1) })
+        );
+        assert!(operation.validate_field_membership(u256::from(8u8)).is_ok());
+
+        operation.destructible_out =
+            small_vec![StateCell::strict_dumb(), StateCell { data: value, ..strict_dumb!() }];
+        assert_eq!(
+            operation.validate_field_membership(order),
+            Err(CallError::FieldMembership { location: ElementLocation::DestructibleOut(1, 1) })
+        );
+
+        operation.destructible_in = small_vec![Input {
+            addr: strict_dumb!(),
+            witness: StateValue::Single { first: fe256::from(9u8) }
+        }];
+        let err = operation.validate_field_membership(order).unwrap_err();
+        assert_eq!(err, CallError::FieldMembership { location: ElementLocation::Witness(0, 0) });
+        assert_eq!(
+            err.to_string(),
+            "element 0 of the witness of the destructible input #0 is not a member of the codex \
+             field."
+        );
+    }
+
+    #[test]
+    fn witnesses() {
+        let mut operation = Operation::strict_dumb();
+        assert_eq!(operation.witnesses().count(), 0);
+
+        let addr1 = CellAddr::new(Opid::from_byte_array([1u8; 32]), 0);
+        let addr2 = CellAddr::new(Opid::from_byte_array([2u8; 32]), 3);
+        let witness = StateValue::Single { first: fe256::from(5u8) };
+        operation.destructible_in =
+            small_vec![Input { addr: addr1, witness }, Input { addr: addr2, witness: none!() }];
+        operation.immutable_in = small_vec![addr1];
+        assert_eq!(operation.witnesses().collect::<Vec<_>>(), vec![
+            (0, addr1, &witness),
+            (1, addr2, &StateValue::None)
+        ]);
+    }
 }
@@ -0,0 +1,366 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeSet;
+use std::sync::atomic::AtomicBool;
+
+use crate::{CallError, CallId, Codex, LibRepo, Operation, StateData, StateValue};
+
+/// Options of the operation verification with [`crate::Codex::verify_with_options`].
+///
+/// The options can be freely combined; [`VerifyOptions::default`] matches the behavior of
+/// [`crate::Codex::verify`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct VerifyOptions<'a> {
+    /// Policy for the additional checks of the operation, which are not a part of the consensus.
+    pub policy: VerifyPolicy,
+    /// Height/time value provided to the lock scripts of the destructible inputs (see
+    /// [`crate::Codex::verify_timed`]).
+    pub time: Option<u64>,
+    /// Contract-global parameters provided to the operation verification script (see
+    /// [`crate::Codex::verify_with_globals`]).
+    pub globals: &'a [StateData],
+    /// Witnesses supplied separately from the operation, which are used instead of the
+    /// [`crate::Input::witness`] values (see [`crate::Codex::verify_with_witnesses`]).
+    pub witnesses: Option<&'a [StateValue]>,
+    /// Flag allowing to cancel the verification from another thread (see
+    /// [`crate::Codex::verify_cancellable`]).
+    pub cancel: Option<&'a AtomicBool>,
+    /// Call ids which the host considers deprecated, but still accepts.
+    ///
+    /// The deprecation is informational and doesn't affect the verification: operations using a
+    /// deprecated call are verified as usual, and [`crate::Codex::verify_metered`] reports the use
+    /// of such a call with [`crate::VerificationReport::deprecated_call`], warning operators to
+    /// migrate.
+    pub deprecated: &'a [CallId],
+}
+
+/// Policy for the additional checks performed during the operation verification with
+/// [`crate::Codex::verify_with_policy`].
+///
+/// The checks defined by the policy are not a part of the consensus and are disabled by default,
+/// such that [`VerifyPolicy::default`] matches the behavior of [`crate::Codex::verify`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct VerifyPolicy {
+    /// Reject operations providing a witness for a destructible input which memory cell has no
+    /// lock (see [`crate::CallError::UnexpectedWitness`]).
+    pub reject_unexpected_witness: bool,
+    /// Reject operations which witnesses or output values contain elements which are not members
+    /// of the codex field, before running any VM (see
+    /// [`crate::Operation::validate_field_membership`] and
+    /// [`crate::CallError::FieldMembership`]).
+    ///
+    /// The consensus verification rejects such witness elements only for the inputs having a lock
+    /// script; the policy extends the check to the inputs which have no lock and thus do not read
+    /// their witnesses. This prevents malleability of the operations, where different encodings
+    /// of the same witness produce different operation data.
+    pub reject_non_field_elements: bool,
+    /// Reject operations which have neither destructible inputs nor any outputs (see
+    /// [`crate::CallError::EmptyOperation`]).
+    pub reject_empty: bool,
+    /// Reject operations defining immutable memory cells which are already present in the memory
+    /// (see [`crate::CallError::ImmutableRedefined`]), enforcing the immutable state to be
+    /// append-only.
+    ///
+    /// The check is not enabled by default, since some contracts legitimately overwrite the
+    /// immutable cells (for instance, when re-verifying already accepted operations).
+    pub reject_immutable_redefinition: bool,
+    /// Call id of the operations burning destructible memory cells, if any.
+    ///
+    /// If set, operations which are burns (see [`crate::Operation::is_burn`]) are accepted only
+    /// if they use this call id, and are rejected with [`crate::CallError::UnexpectedBurn`]
+    /// otherwise. This distinguishes an intended destruction of the cells from a malformed
+    /// operation which has lost its outputs.
+    pub burn_call: Option<CallId>,
+    /// Maximum depth of the nested calls (both to the library routines and to other libraries)
+    /// the operation verification script may perform (see [`crate::VmContext::max_call_depth`]).
+    ///
+    /// This is a hardening against the verifiers exhausting the host resources with deeply nested
+    /// calls, which is independent of the complexity limit. Since the limit is not committed to
+    /// by the codex, it is not applied by default (and by [`crate::Codex::verify`]), and is set to
+    /// [`Self::DEFAULT_MAX_CALL_DEPTH`] by [`Self::strict`].
+    pub max_call_depth: Option<u16>,
+}
+
+impl Default for VerifyPolicy {
+    fn default() -> Self { Self::lenient() }
+}
+
+impl VerifyPolicy {
+    /// Value of [`Self::max_call_depth`] used by [`Self::strict`].
+    pub const DEFAULT_MAX_CALL_DEPTH: u16 = 64;
+
+    /// Policy enabling all the additional checks, used by [`crate::Codex::verify_strict`].
+    ///
+    /// The policy rejects operations:
+    /// - providing a witness for an unlocked input ([`Self::reject_unexpected_witness`]);
+    /// - having witness or output elements which are not members of the codex field
+    ///   ([`Self::reject_non_field_elements`]);
+    /// - having neither destructible inputs nor any outputs ([`Self::reject_empty`]);
+    /// - redefining already present immutable memory cells
+    ///   ([`Self::reject_immutable_redefinition`]);
+    /// - performing calls nested deeper than [`Self::DEFAULT_MAX_CALL_DEPTH`]
+    ///   ([`Self::max_call_depth`]).
+    ///
+    /// No [`Self::burn_call`] is set, since it is specific to a contract.
+    pub const fn strict() -> Self {
+        Self {
+            reject_unexpected_witness: true,
+            reject_non_field_elements: true,
+            reject_empty: true,
+            reject_immutable_redefinition: true,
+            burn_call: None,
+            max_call_depth: Some(Self::DEFAULT_MAX_CALL_DEPTH),
+        }
+    }
+
+    /// Policy disabling all the additional checks, matching the behavior of
+    /// [`crate::Codex::verify`]; the same as [`VerifyPolicy::default`].
+    pub const fn lenient() -> Self {
+        Self {
+            reject_unexpected_witness: false,
+            reject_non_field_elements: false,
+            reject_empty: false,
+            reject_immutable_redefinition: false,
+            burn_call: None,
+            max_call_depth: None,
+        }
+    }
+}
+
+/// Policy for the additional checks performed during the codex validation with
+/// [`crate::Codex::validate_with_policy`].
+///
+/// By default, no limits are imposed, such that [`CodexPolicy::default`] matches the behavior of
+/// [`crate::Codex::validate`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct CodexPolicy {
+    /// Maximum number of distinct libraries the codex may use (see
+    /// [`crate::Codex::lib_dependencies`]).
+    pub max_libs: Option<usize>,
+}
+
+/// Declarative pre-condition for the operation, checked by the host before running any VM with
+/// [`Codex::verify_with_preconditions`].
+///
+/// Pre-conditions keep cheap structural checks out of the (more expensive) verification scripts.
+/// Like the other verification policies, they are not a part of the consensus: a contract must not
+/// rely on them being checked by all the nodes.
+pub trait PreCondition {
+    /// Checks the pre-condition for the `operation` verified by the `codex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error with which the operation verification must fail.
+    fn check(&self, codex: &Codex, operation: &Operation) -> Result<(), CallError>;
+}
+
+/// Pre-condition requiring the operation to have a minimal number of inputs.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct MinInputs {
+    /// Call id of the operations the pre-condition applies to, or `None` if it applies to all
+    /// operations.
+    pub call_id: Option<CallId>,
+    /// Minimal number of destructible inputs ([`Operation::destructible_in`]).
+    pub destructible: usize,
+    /// Minimal number of immutable inputs ([`Operation::immutable_in`]).
+    pub immutable: usize,
+}
+
+impl PreCondition for MinInputs {
+    fn check(&self, _: &Codex, operation: &Operation) -> Result<(), CallError> {
+        if self
+            .call_id
+            .is_some_and(|call_id| call_id != operation.call_id)
+        {
+            return Ok(());
+        }
+        if operation.destructible_in.len() < self.destructible
+            || operation.immutable_in.len() < self.immutable
+        {
+            return Err(CallError::TooFewInputs);
+        }
+        Ok(())
+    }
+}
+
+/// Pre-condition allowing only operations with one of the listed call ids.
+///
+/// This allows a host to restrict the set of contract methods it accepts without modifying the
+/// codex.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RequiredCallIds(pub BTreeSet<CallId>);
+
+impl PreCondition for RequiredCallIds {
+    fn check(&self, _: &Codex, operation: &Operation) -> Result<(), CallError> {
+        if !self.0.contains(&operation.call_id) {
+            return Err(CallError::CallNotAllowed(operation.call_id));
+        }
+        Ok(())
+    }
+}
+
+/// Declarative post-condition for the operation, checked by the host after a successful run of the
+/// operation verification script with [`Codex::verify_with_postconditions`].
+///
+/// Post-conditions allow a host to enforce invariants of the operation outputs which the codex
+/// verifiers do not check. Like the pre-conditions, they are not a part of the consensus: a
+/// contract must not rely on them being checked by all the nodes.
+pub trait PostCondition {
+    /// Checks the post-condition for the `operation` verified by the `codex`, using the `repo`
+    /// for resolving the libraries.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error with which the operation verification must fail.
+    fn check(
+        &self,
+        codex: &Codex,
+        operation: &Operation,
+        repo: &dyn LibRepo,
+    ) -> Result<(), CallError>;
+}
+
+/// Post-condition requiring the libraries of all the destructible output locks to be known to the
+/// library repository, such that the created memory cells can be spent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct OutputLocksResolve;
+
+impl PostCondition for OutputLocksResolve {
+    fn check(&self, _: &Codex, operation: &Operation, repo: &dyn LibRepo) -> Result<(), CallError> {
+        for (pos, cell) in operation.destructible_out.iter().enumerate() {
+            if let Some(lock) = cell.lock {
+                if repo.get_lib(lock.lib_id).is_none() {
+                    return Err(CallError::OutputLockUnresolved(pos as u16));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Post-condition requiring all the destructible outputs of the operation to have distinct tokens
+/// of authority ([`crate::StateCell::auth`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct UniqueOutputTokens;
+
+impl PostCondition for UniqueOutputTokens {
+    fn check(&self, _: &Codex, operation: &Operation, _: &dyn LibRepo) -> Result<(), CallError> {
+        let mut tokens = BTreeSet::new();
+        for (pos, cell) in operation.destructible_out.iter().enumerate() {
+            if !tokens.insert(cell.auth.to_fe256().to_u256()) {
+                return Err(CallError::DuplicateOutputToken(pos as u16));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use aluvm::alu::{Lib, LibId, LibSite};
+    use amplify::confinement::SmallVec;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{fe256, uasm, AuthToken, CellAddr, Input, StateCell};
+
+    #[test]
+    fn verify_policy_presets() {
+        assert_eq!(VerifyPolicy::lenient(), VerifyPolicy::default());
+        let strict = VerifyPolicy::strict();
+        assert!(strict.reject_unexpected_witness);
+        assert!(strict.reject_non_field_elements);
+        assert!(strict.reject_empty);
+        assert!(strict.reject_immutable_redefinition);
+        assert_eq!(strict.max_call_depth, Some(VerifyPolicy::DEFAULT_MAX_CALL_DEPTH));
+        assert_eq!(VerifyPolicy::lenient().max_call_depth, None);
+    }
+
+    #[test]
+    fn min_inputs() {
+        let codex = Codex::strict_dumb();
+        let mut operation = Operation::strict_dumb();
+        let min = MinInputs { call_id: None, destructible: 0, immutable: 1 };
+        assert_eq!(min.check(&codex, &operation), Err(CallError::TooFewInputs));
+        let other_call = MinInputs { call_id: Some(1), ..min };
+        assert_eq!(other_call.check(&codex, &operation), Ok(()));
+
+        operation.immutable_in = SmallVec::from_checked(vec![CellAddr::strict_dumb()]);
+        assert_eq!(min.check(&codex, &operation), Ok(()));
+        let destructible = MinInputs { destructible: 1, ..min };
+        assert_eq!(destructible.check(&codex, &operation), Err(CallError::TooFewInputs));
+        operation.destructible_in = SmallVec::from_checked(vec![Input::strict_dumb()]);
+        assert_eq!(destructible.check(&codex, &operation), Ok(()));
+    }
+
+    #[test]
+    fn output_locks_resolve() {
+        let codex = Codex::strict_dumb();
+        let lib = Lib::assemble(&uasm! { stop; }).unwrap();
+        let mut operation = Operation::strict_dumb();
+        let mut cell = StateCell::strict_dumb();
+        operation.destructible_out = small_vec![cell];
+        assert_eq!(OutputLocksResolve.check(&codex, &operation, &lib), Ok(()));
+
+        cell.lock = Some(LibSite::new(lib.lib_id(), 0));
+        operation.destructible_out = small_vec![StateCell::strict_dumb(), cell];
+        assert_eq!(OutputLocksResolve.check(&codex, &operation, &lib), Ok(()));
+        cell.lock = Some(LibSite::new(LibId::strict_dumb(), 0));
+        operation.destructible_out = small_vec![StateCell::strict_dumb(), cell];
+        assert_eq!(
+            OutputLocksResolve.check(&codex, &operation, &lib),
+            Err(CallError::OutputLockUnresolved(1))
+        );
+    }
+
+    #[test]
+    fn unique_output_tokens() {
+        let codex = Codex::strict_dumb();
+        let lib = Lib::assemble(&uasm! { stop; }).unwrap();
+        let mut operation = Operation::strict_dumb();
+        let other = StateCell {
+            auth: AuthToken::from(fe256::from(1u8)),
+            ..StateCell::strict_dumb()
+        };
+        operation.destructible_out = small_vec![StateCell::strict_dumb(), other];
+        assert_eq!(UniqueOutputTokens.check(&codex, &operation, &lib), Ok(()));
+        operation.destructible_out = small_vec![other, StateCell::strict_dumb(), other];
+        assert_eq!(
+            UniqueOutputTokens.check(&codex, &operation, &lib),
+            Err(CallError::DuplicateOutputToken(2))
+        );
+    }
+
+    #[test]
+    fn required_call_ids() {
+        let codex = Codex::strict_dumb();
+        let mut operation = Operation::strict_dumb();
+        let required = RequiredCallIds(bset![1, 2]);
+        assert_eq!(required.check(&codex, &operation), Err(CallError::CallNotAllowed(0)));
+        operation.call_id = 2;
+        assert_eq!(required.check(&codex, &operation), Ok(()));
+    }
+}
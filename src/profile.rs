@@ -0,0 +1,148 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use core::fmt::Display;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Profiler recording the cycles spent by the VM on each of the executed instructions, used by
+/// [`crate::Codex::verify_profile`].
+///
+/// The profiler is attached to the VM with [`crate::VmContext::profiler`]; the VM records each of
+/// the executed instructions with the complexity it has added, together with the chain of the
+/// routines (called with `call` or `fn` instructions) leading to the instruction.
+///
+/// # Overhead
+///
+/// Profiling is meant for the verifier performance tuning, and not for the production use: each
+/// of the executed instructions formats its site as a string and updates a map, which slows down
+/// the execution by an order of magnitude comparing to the non-profiled execution. Without the
+/// `profiling` feature the profiler is not compiled, and the VM has no overhead.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Profiler(RefCell<Recording>);
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+struct Recording {
+    /// Entry sites of the routines on the call stack, starting with the VM entry point.
+    frames: Vec<String>,
+    /// Cycles accumulated by the routine on the top of the call stack since the last change of the
+    /// stack.
+    pending: u64,
+    profile: Profile,
+}
+
+impl Profiler {
+    /// Constructs a profiler with no recorded execution.
+    pub fn new() -> Self { Self::default() }
+
+    /// Completes the profiling, returning the collected profile.
+    pub fn into_profile(self) -> Profile {
+        let mut recording = self.0.into_inner();
+        recording.flush();
+        recording.profile
+    }
+
+    /// Records the execution of an instruction at `site`, which has consumed `cycles`.
+    ///
+    /// The `depth` is the depth of the VM call stack before the instruction execution. If the
+    /// instruction has transferred the execution to another routine, the `target` provides the
+    /// routine entry site, and `nested` indicates whether the routine was called (and will return
+    /// to the current routine) or was jumped to.
+    pub(crate) fn record(
+        &self,
+        site: impl Display,
+        depth: u16,
+        cycles: u64,
+        target: Option<(impl Display, bool)>,
+    ) {
+        let mut recording = self.0.borrow_mut();
+        let site = site.to_string();
+        if recording.frames.is_empty() {
+            recording.frames.push(site.clone());
+        }
+        if recording.frames.len() > depth as usize + 1 {
+            recording.flush();
+            recording.frames.truncate(depth as usize + 1);
+        }
+        recording.pending += cycles;
+        recording.profile.total += cycles;
+        *recording.profile.sites.entry(site).or_default() += cycles;
+
+        if let Some((target, nested)) = target {
+            recording.flush();
+            if !nested {
+                recording.frames.pop();
+            }
+            recording.frames.push(target.to_string());
+        }
+    }
+}
+
+impl Recording {
+    fn flush(&mut self) {
+        if self.pending == 0 {
+            return;
+        }
+        *self.profile.stacks.entry(self.frames.clone()).or_default() += self.pending;
+        self.pending = 0;
+    }
+}
+
+/// Profile of a verifier execution, collected by a [`Profiler`].
+///
+/// VM sites are represented in the `<lib_id>@<offset>` form, as displayed by
+/// [`aluvm::alu::LibSite`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Profile {
+    total: u64,
+    sites: BTreeMap<String, u64>,
+    stacks: BTreeMap<Vec<String>, u64>,
+}
+
+impl Profile {
+    /// Returns the total number of cycles spent on the execution.
+    pub fn cycles(&self) -> u64 { self.total }
+
+    /// Returns the cycles spent on each of the executed instructions, indexed by the instruction
+    /// site.
+    pub fn sites(&self) -> &BTreeMap<String, u64> { &self.sites }
+
+    /// Returns the cycles spent on each of the executed routines, indexed by the call stack (a
+    /// list of the routine entry sites, starting with the verifier entry point), excluding the
+    /// cycles spent on the routines called by them.
+    pub fn stacks(&self) -> &BTreeMap<Vec<String>, u64> { &self.stacks }
+
+    /// Dumps the call stack profile (see [`Self::stacks`]) in the folded-stack format, accepted by
+    /// the flamegraph tools.
+    ///
+    /// Each of the lines contains the routine entry sites of a call stack, separated by `;`, and
+    /// the number of cycles spent on it.
+    pub fn to_folded(&self) -> String {
+        let mut folded = String::new();
+        for (stack, cycles) in &self.stacks {
+            folded.push_str(&stack.join(";"));
+            folded.push_str(&format!(" {cycles}\n"));
+        }
+        folded
+    }
+}
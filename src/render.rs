@@ -0,0 +1,177 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeMap;
+
+use aluvm::fe256;
+
+use crate::CallError;
+
+/// Renderer of the operation verification errors into the user-facing messages.
+///
+/// The trait decouples the presentation of the errors from the error type, allowing applications
+/// to localize the messages or to describe the contract-specific error codes returned by the
+/// scripts ([`CallError::Script`] and [`CallError::Lock`]). [`EnglishRenderer`] provides messages
+/// matching the [`Display`](core::fmt::Display) implementation of [`CallError`].
+pub trait ErrorRenderer {
+    /// Renders the error into a user-facing message.
+    fn render(&self, err: &CallError) -> String;
+}
+
+/// The default [`ErrorRenderer`], producing English messages matching the
+/// [`Display`](core::fmt::Display) implementation of [`CallError`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct EnglishRenderer;
+
+impl ErrorRenderer for EnglishRenderer {
+    fn render(&self, err: &CallError) -> String { err.to_string() }
+}
+
+/// Catalog of the messages for the error codes returned by the scripts ([`CallError::Script`] and
+/// [`CallError::Lock`]), allowing to share the error codes across multiple codices.
+///
+/// The catalog is maintained by the host and is not a part of the codices. A product suite may
+/// define a global catalog, and overlay it with the tables specific to a codex using
+/// [`Self::overlay`]. When used as an [`ErrorRenderer`], the errors without a code or with a code
+/// absent from the catalog (including the codes not fitting into `u128`) are rendered with
+/// [`EnglishRenderer`].
+#[derive(Clone, Eq, PartialEq, Debug, Default, From)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct ErrorCatalog(BTreeMap<u128, String>);
+
+impl ErrorCatalog {
+    /// Constructs an empty catalog.
+    pub fn new() -> Self { Self::default() }
+
+    /// Adds a message for the error `code`, returning a previously set one.
+    pub fn insert(&mut self, code: u128, message: impl Into<String>) -> Option<String> {
+        self.0.insert(code, message.into())
+    }
+
+    /// Returns the message for the error `code`, if any.
+    pub fn get(&self, code: u128) -> Option<&str> { self.0.get(&code).map(String::as_str) }
+
+    /// Returns the message for the error code returned by a script, if it fits into `u128` and
+    /// is present in the catalog.
+    pub fn message(&self, code: fe256) -> Option<&str> {
+        let bytes = code.to_u256().to_le_bytes();
+        let (low, high) = bytes.split_at(16);
+        if high.iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        let low = <[u8; 16]>::try_from(low).expect("fixed length");
+        self.get(u128::from_le_bytes(low))
+    }
+
+    /// Constructs a catalog combining this (global) catalog with a `local` one, where the messages
+    /// from the `local` catalog take precedence.
+    pub fn overlay(&self, local: &ErrorCatalog) -> ErrorCatalog {
+        let mut catalog = self.clone();
+        catalog
+            .0
+            .extend(local.0.iter().map(|(code, msg)| (*code, msg.clone())));
+        catalog
+    }
+
+    /// Returns the number of the messages in the catalog.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Detects whether the catalog is empty.
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+impl ErrorRenderer for ErrorCatalog {
+    fn render(&self, err: &CallError) -> String {
+        let msg = match err {
+            CallError::Script(code) | CallError::Lock(Some(code)) => self.message(*code),
+            _ => None,
+        };
+        msg.map(str::to_owned)
+            .unwrap_or_else(|| EnglishRenderer.render(err))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::num::u256;
+
+    use super::*;
+
+    #[test]
+    fn error_renderer() {
+        struct CodeRenderer;
+        impl ErrorRenderer for CodeRenderer {
+            fn render(&self, err: &CallError) -> String {
+                match err {
+                    CallError::Script(code) if *code == fe256::from(1u8) => {
+                        s!("insufficient funds")
+                    }
+                    err => EnglishRenderer.render(err),
+                }
+            }
+        }
+
+        let err = CallError::NotFound(5);
+        assert_eq!(EnglishRenderer.render(&err), err.to_string());
+        assert_eq!(CodeRenderer.render(&err), err.to_string());
+        let err = CallError::Script(fe256::from(1u8));
+        assert_eq!(
+            EnglishRenderer.render(&err),
+            "verification script failure with status code 1.fe."
+        );
+        assert_eq!(CodeRenderer.render(&err), "insufficient funds");
+    }
+
+    #[test]
+    fn error_catalog() {
+        let mut global = ErrorCatalog::new();
+        global.insert(1, "insufficient funds");
+        global.insert(2, "expired");
+        let mut local = ErrorCatalog::new();
+        local.insert(2, "offer expired");
+        local.insert(u128::MAX, "overflow");
+        let catalog = global.overlay(&local);
+        assert_eq!(catalog.len(), 3);
+        assert_eq!(catalog.get(1), Some("insufficient funds"));
+        assert_eq!(catalog.get(2), Some("offer expired"));
+
+        assert_eq!(catalog.render(&CallError::Script(fe256::from(1u8))), "insufficient funds");
+        assert_eq!(catalog.render(&CallError::Lock(Some(fe256::from(2u8)))), "offer expired");
+        assert_eq!(catalog.render(&CallError::Script(fe256::from(u128::MAX))), "overflow");
+        let err = CallError::Script(fe256::from(u256::from(u128::MAX) + u256::ONE));
+        assert_eq!(catalog.render(&err), err.to_string());
+        let err = CallError::Script(fe256::from(3u8));
+        assert_eq!(catalog.render(&err), err.to_string());
+        let err = CallError::NotFound(1);
+        assert_eq!(catalog.render(&err), err.to_string());
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&catalog).unwrap();
+            let decoded: ErrorCatalog = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, catalog);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeMap;
+
+use aluvm::alu::{LibId, LibSite};
+use amplify::confinement::{SmallOrdMap, SmallOrdSet};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{CallError, CellAddr, LockOutcome, Opid, LIB_NAME_ULTRASONIC};
+
+/// Report on the resources consumed by the operation verification, produced by
+/// [`crate::Codex::verify_metered`].
+///
+/// The report has a deterministic strict encoding (and also serde serialization, if the `serde`
+/// feature is enabled), such that the reports produced by different nodes for the same operation
+/// can be logged and compared to detect a divergence. The verification failure and the lock script
+/// results are serialized with serde only, since the errors have no strict encoding.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct VerificationReport {
+    /// Total complexity of the verification script execution, measured in the VM complexity
+    /// units (the same as used by [`aluvm::alu::CoreConfig::complexity_lim`]).
+    pub cycles: u64,
+    /// Complexity of the verification script execution attributed to each of the libraries which
+    /// code was executed.
+    ///
+    /// The sum of all values equals [`Self::cycles`], unless the verification has executed more
+    /// than `u16::MAX` distinct libraries; in this case, the libraries exceeding the limit (in the
+    /// order of their ids) are omitted from the report.
+    pub lib_cycles: SmallOrdMap<LibId, u64>,
+    /// Whether the operation has used a deprecated call (see
+    /// [`crate::VerifyOptions::deprecated`]).
+    pub deprecated_call: bool,
+    /// Entry point of the verifier which was executed for the operation.
+    ///
+    /// The value is `None` if the verification has failed before running the verifier, for
+    /// instance due to a missing input or an absent verifier for the operation call id.
+    pub executed_verifier: Option<LibSite>,
+    /// Immutable memory cells which values were loaded by the verifier.
+    ///
+    /// Immutable inputs not listed here didn't influence the verification result, and can be
+    /// omitted from the proofs provided to light clients. Since the verifier loads the immutable
+    /// inputs sequentially, these are always the first inputs of
+    /// [`crate::Operation::immutable_in`].
+    pub consulted_immutable: SmallOrdSet<CellAddr>,
+    /// Error with which the verification has failed, or `None` if the operation is valid.
+    #[strict_type(skip)]
+    pub failure: Option<CallError>,
+    /// Results of the lock scripts of the destructible inputs, in the order of the locked inputs.
+    ///
+    /// Since the verification stops on the first unsatisfied lock, only the last of the results
+    /// may be a failure.
+    #[strict_type(skip)]
+    pub locks: Vec<LockOutcome>,
+}
+
+impl StrictSerialize for VerificationReport {}
+impl StrictDeserialize for VerificationReport {}
+
+impl VerificationReport {
+    pub(crate) fn with(cycles: u64, lib_cycles: BTreeMap<LibId, u64>) -> Self {
+        let lib_cycles =
+            SmallOrdMap::from_iter_checked(lib_cycles.into_iter().take(u16::MAX as usize));
+        Self {
+            cycles,
+            lib_cycles,
+            deprecated_call: false,
+            executed_verifier: None,
+            consulted_immutable: none!(),
+            failure: None,
+            locks: none!(),
+        }
+    }
+
+    /// Computes the differences between this report and the `other` one (for instance, a reference
+    /// report produced by another implementation), which is useful for debugging consensus
+    /// divergences.
+    ///
+    /// In each of the differences, the value from this report goes first, followed by the value
+    /// from the `other` one.
+    pub fn diff(&self, other: &VerificationReport) -> ReportDiff {
+        let lib_cycles = self
+            .lib_cycles
+            .keys()
+            .chain(other.lib_cycles.keys())
+            .filter_map(|lib_id| {
+                let ours = self.lib_cycles.get(lib_id).copied();
+                let theirs = other.lib_cycles.get(lib_id).copied();
+                (ours != theirs).then_some((*lib_id, (ours, theirs)))
+            })
+            .collect();
+        let ours = BTreeMap::from_iter(self.locks.iter().copied());
+        let theirs = BTreeMap::from_iter(other.locks.iter().copied());
+        let locks = ours
+            .keys()
+            .chain(theirs.keys())
+            .filter_map(|addr| {
+                let ours = ours.get(addr).copied();
+                let theirs = theirs.get(addr).copied();
+                (ours != theirs).then_some((*addr, (ours, theirs)))
+            })
+            .collect();
+        ReportDiff {
+            cycles: (self.cycles != other.cycles).then_some((self.cycles, other.cycles)),
+            lib_cycles,
+            deprecated_call: (self.deprecated_call != other.deprecated_call)
+                .then_some((self.deprecated_call, other.deprecated_call)),
+            executed_verifier: (self.executed_verifier != other.executed_verifier)
+                .then_some((self.executed_verifier, other.executed_verifier)),
+            consulted_immutable: (self.consulted_immutable != other.consulted_immutable)
+                .then(|| (self.consulted_immutable.clone(), other.consulted_immutable.clone())),
+            failure: (self.failure != other.failure).then_some((self.failure, other.failure)),
+            locks,
+        }
+    }
+}
+
+/// Differences between two [`VerificationReport`]s, computed with [`VerificationReport::diff`].
+///
+/// Each of the fields holds a pair of the differing values, the first one coming from the report
+/// on which the diff was called.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct ReportDiff {
+    /// Total complexity, if it differs.
+    pub cycles: Option<(u64, u64)>,
+    /// Complexity attributed to the libraries which differs between the reports; a library not
+    /// executed according to one of the reports has `None` value for it.
+    pub lib_cycles: BTreeMap<LibId, (Option<u64>, Option<u64>)>,
+    /// Use of a deprecated call, if it differs.
+    pub deprecated_call: Option<(bool, bool)>,
+    /// Executed verifier entry point, if it differs.
+    pub executed_verifier: Option<(Option<LibSite>, Option<LibSite>)>,
+    /// Consulted immutable memory cells, if they differ.
+    pub consulted_immutable: Option<(SmallOrdSet<CellAddr>, SmallOrdSet<CellAddr>)>,
+    /// Verification failure, if it differs.
+    pub failure: Option<(Option<CallError>, Option<CallError>)>,
+    /// Lock script results of the destructible inputs which differ between the reports; an input
+    /// which lock wasn't run according to one of the reports has `None` value for it.
+    pub locks: BTreeMap<CellAddr, LockDiff>,
+}
+
+/// Pair of the differing lock script results of a destructible input, reported in
+/// [`ReportDiff::locks`].
+pub type LockDiff = (Option<Result<(), CallError>>, Option<Result<(), CallError>>);
+
+impl ReportDiff {
+    /// Detects whether the reports are equal.
+    pub fn is_empty(&self) -> bool {
+        self.cycles.is_none()
+            && self.lib_cycles.is_empty()
+            && self.deprecated_call.is_none()
+            && self.executed_verifier.is_none()
+            && self.consulted_immutable.is_none()
+            && self.failure.is_none()
+            && self.locks.is_empty()
+    }
+}
+
+/// Report on the compatibility of a codex upgrade, produced by [`crate::Codex::check_upgrade`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct UpgradeReport {
+    /// Number of the checked operations.
+    pub checked: usize,
+    /// Operations which verification results differ between the codices, in the order of the
+    /// checked operations.
+    pub divergences: Vec<UpgradeDivergence>,
+}
+
+impl UpgradeReport {
+    /// Detects whether the upgrade is compatible, i.e. all the checked operations valid under the
+    /// older codex remain valid under the newer one.
+    ///
+    /// Operations which become valid, or which fail with a different error, don't break the
+    /// compatibility, but are still reported as [`Self::divergences`].
+    pub fn is_compatible(&self) -> bool { self.regressions().next().is_none() }
+
+    /// Iterates over the divergences where an operation valid under the older codex is invalid
+    /// under the newer one.
+    pub fn regressions(&self) -> impl Iterator<Item = &UpgradeDivergence> {
+        self.divergences
+            .iter()
+            .filter(|divergence| divergence.older.is_ok() && divergence.newer.is_err())
+    }
+}
+
+/// Operation which verification results differ between two codices (see [`UpgradeReport`]).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct UpgradeDivergence {
+    /// Index of the operation in the checked corpus.
+    pub index: usize,
+    /// Id of the operation.
+    pub opid: Opid,
+    /// Verification result under the older codex.
+    pub older: Result<(), CallError>,
+    /// Verification result under the newer codex.
+    pub newer: Result<(), CallError>,
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::ByteArray;
+
+    use super::*;
+
+    fn report() -> VerificationReport {
+        VerificationReport::with(30, bmap! {
+            LibId::from_byte_array([0xAA; 32]) => 10,
+            LibId::from_byte_array([0xBB; 32]) => 20,
+        })
+    }
+
+    #[test]
+    fn strict_roundtrip() {
+        let mut report = report();
+        let data = report.to_strict_serialized::<0xFFFF>().unwrap();
+        assert_eq!(data.len(), 8 + 2 + 2 * (32 + 8) + 1 + 1 + 2);
+        let decoded = VerificationReport::from_strict_serialized::<0xFFFF>(data.clone()).unwrap();
+        assert_eq!(decoded, report);
+
+        // The failure and the lock results are not strict-encoded
+        report.failure = Some(CallError::Lock(None));
+        report.locks = vec![(CellAddr::new(strict_dumb!(), 0), Err(CallError::Lock(None)))];
+        assert_eq!(report.to_strict_serialized::<0xFFFF>().unwrap(), data);
+    }
+
+    #[test]
+    fn diff() {
+        let mut report = report();
+        assert!(report.diff(&report).is_empty());
+
+        let mut other = VerificationReport::with(35, bmap! {
+            LibId::from_byte_array([0xAA; 32]) => 10,
+            LibId::from_byte_array([0xBB; 32]) => 15,
+            LibId::from_byte_array([0xCC; 32]) => 10,
+        });
+        other.deprecated_call = true;
+        other.executed_verifier = Some(LibSite::new(LibId::from_byte_array([0xAA; 32]), 0));
+        let addr = CellAddr::new(strict_dumb!(), 1);
+        other.consulted_immutable = small_bset![addr];
+        other.failure = Some(CallError::Lock(None));
+        let (first, second) = (CellAddr::new(strict_dumb!(), 2), CellAddr::new(strict_dumb!(), 3));
+        report.locks = vec![(first, Ok(())), (second, Ok(()))];
+        other.locks = vec![(first, Ok(())), (second, Err(CallError::Lock(None)))];
+        let diff = report.diff(&other);
+        assert!(!diff.is_empty());
+        assert_eq!(diff, ReportDiff {
+            cycles: Some((30, 35)),
+            lib_cycles: bmap! {
+                LibId::from_byte_array([0xBB; 32]) => (Some(20), Some(15)),
+                LibId::from_byte_array([0xCC; 32]) => (None, Some(10)),
+            },
+            deprecated_call: Some((false, true)),
+            executed_verifier: Some((
+                None,
+                Some(LibSite::new(LibId::from_byte_array([0xAA; 32]), 0))
+            )),
+            consulted_immutable: Some((none!(), small_bset![addr])),
+            failure: Some((None, Some(CallError::Lock(None)))),
+            locks: bmap! {
+                second => (Some(Ok(())), Some(Err(CallError::Lock(None)))),
+            },
+        });
+        assert_eq!(other.diff(&report).cycles, Some((35, 30)));
+
+        other.locks.pop();
+        let diff = report.diff(&other);
+        assert_eq!(diff.locks, bmap! { second => (Some(Ok(())), None) });
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&diff).unwrap();
+            let decoded: ReportDiff = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, diff);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_roundtrip() {
+        let mut report = report();
+        report.failure = Some(CallError::Lock(None));
+        report.locks = vec![(CellAddr::new(strict_dumb!(), 0), Err(CallError::Lock(None)))];
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.starts_with(r#"{"cycles":30,"libCycles":{"#));
+        let decoded: VerificationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, report);
+    }
+}
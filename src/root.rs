@@ -0,0 +1,194 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Commitment to the contract memory in the form of a Merkle root.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::LargeVec;
+use amplify::Bytes32;
+use commit_verify::{
+    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, MerkleHash, Sha256,
+};
+
+use crate::{CellAddr, StateCell, StateValue, LIB_NAME_ULTRASONIC};
+
+/// Root of the contract memory, committing to all its memory cells.
+///
+/// The root allows light clients to verify operations against a memory state they don't hold in
+/// full (see [`crate::Memory::root`]), and can be used as a key for the verification cache (see
+/// [`crate::Codex::verify_cached`]).
+///
+/// # Construction
+///
+/// The root is computed with [`StateRoot::merklize`] as follows:
+/// 1. Each of the destructible memory cells produces a [`DestructibleLeaf`], and each of the
+///    immutable memory cells - an [`ImmutableLeaf`]; the leaves are committed with the strict
+///    commitment procedure into a `MerkleHash`.
+/// 2. Leaves of each of the memory parts are sorted by [`CellAddr`] (in the order of the operation
+///    ids, and then of the output numbers), and then merklized into two separate trees using the
+///    LNPBP-81 merklization procedure. Separate trees are required since destructible and immutable
+///    outputs of the same operation use the same addresses.
+/// 3. The root is a tagged hash (with [`StateRoot::TAG`]) of the destructible tree root followed by
+///    the immutable tree root.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(AsSlice, Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct StateRoot(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for StateRoot {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for StateRoot {
+    const TAG: &'static str = "urn:ubideco:ultrasonic:memory#2026-10-14";
+}
+
+impl StateRoot {
+    /// Computes the memory root out of the destructible and immutable memory cells, provided in
+    /// any order.
+    ///
+    /// If the same address is provided more than once within one of the memory parts, the last
+    /// provided cell is used.
+    pub fn merklize(
+        destructible: impl IntoIterator<Item = (CellAddr, StateCell)>,
+        immutable: impl IntoIterator<Item = (CellAddr, StateValue)>,
+    ) -> Self {
+        let destructible = destructible
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(addr, cell)| DestructibleLeaf { addr, cell });
+        let immutable = immutable
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(addr, value)| ImmutableLeaf { addr, value });
+        MemoryLeaves {
+            destructible: LargeVec::from_iter_checked(destructible),
+            immutable: LargeVec::from_iter_checked(immutable),
+        }
+        .commit_id()
+    }
+}
+
+/// Leaf of the memory Merkle tree for a destructible memory cell (see [`StateRoot`]).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = MerkleHash)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+pub struct DestructibleLeaf {
+    /// Address of the memory cell.
+    pub addr: CellAddr,
+    /// The memory cell.
+    pub cell: StateCell,
+}
+
+/// Leaf of the memory Merkle tree for an immutable memory cell (see [`StateRoot`]).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = MerkleHash)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+pub struct ImmutableLeaf {
+    /// Address of the memory cell.
+    pub addr: CellAddr,
+    /// The verifiable part of the memory cell data.
+    pub value: StateValue,
+}
+
+/// Sorted leaves of both memory parts.
+struct MemoryLeaves {
+    destructible: LargeVec<DestructibleLeaf>,
+    immutable: LargeVec<ImmutableLeaf>,
+}
+
+impl CommitEncode for MemoryLeaves {
+    type CommitmentId = StateRoot;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        e.commit_to_merkle(&self.destructible);
+        e.commit_to_merkle(&self.immutable);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use aluvm::fe256;
+    use amplify::ByteArray;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AuthToken, Opid};
+
+    fn cell(val: u8) -> StateCell {
+        StateCell {
+            data: StateValue::Single { first: fe256::from(val) },
+            auth: AuthToken::strict_dumb(),
+            lock: None,
+        }
+    }
+
+    #[test]
+    fn merklize() {
+        let addr1 = CellAddr::new(Opid::from_byte_array([1; 32]), 0);
+        let addr2 = CellAddr::new(Opid::from_byte_array([1; 32]), 1);
+        let addr3 = CellAddr::new(Opid::from_byte_array([2; 32]), 0);
+        let value = StateValue::Single { first: fe256::from(7u8) };
+
+        let root = StateRoot::merklize([(addr1, cell(1)), (addr2, cell(2))], [(addr3, value)]);
+        assert_eq!(
+            StateRoot::merklize([(addr2, cell(2)), (addr1, cell(1))], [(addr3, value)]),
+            root
+        );
+        assert_eq!(
+            StateRoot::merklize([(addr1, cell(9)), (addr2, cell(2)), (addr1, cell(1))], [(
+                addr3, value
+            )]),
+            root
+        );
+        assert_ne!(
+            StateRoot::merklize([(addr1, cell(1)), (addr2, cell(3))], [(addr3, value)]),
+            root
+        );
+        assert_ne!(StateRoot::merklize([(addr1, cell(1)), (addr2, cell(2))], []), root);
+
+        // Destructible and immutable cells with the same address are committed separately
+        let empty = StateRoot::merklize([], []);
+        assert_eq!(StateRoot::merklize([], []), empty);
+        assert_ne!(StateRoot::merklize([(addr1, cell(7))], []), empty);
+        assert_ne!(
+            StateRoot::merklize([(addr1, cell(7))], []),
+            StateRoot::merklize([], [(addr1, cell(7).data)])
+        );
+    }
+}
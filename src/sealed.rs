@@ -0,0 +1,252 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeMap;
+
+use aluvm::alu::{Lib, LibId};
+
+use crate::codex::lib_closure;
+use crate::{
+    CallError, Codex, CodexError, CodexId, ContractId, LibRepo, Memory, Operation,
+    VerifiedOperation,
+};
+
+/// Codex sealed for the repeated verification of operations, owning the libraries used by its
+/// verifiers and caching the derived data.
+///
+/// Sealing validates the codex (see [`Codex::validate`]), computes its id and copies out of the
+/// library repository all the libraries used by the codex verifiers and by the lock scripts with
+/// the registered witness banks (see [`Codex::witness_banks`]), together with all the libraries
+/// they call. This supports the "load once, verify many" pattern of the long-running servers,
+/// where the operations are verified with [`Self::verify`] without providing a [`LibRepo`].
+///
+/// The sealed libraries are a snapshot of the repository at the moment of sealing: the libraries
+/// added to the repository later are not seen by the sealed codex. Since the destructible inputs
+/// may be locked by arbitrary libraries, which are not known at the moment of sealing, the
+/// libraries which are not sealed may be provided with [`Self::verify_with_fallback`]; otherwise,
+/// such inputs fail the verification in the same way as if the library is absent from the
+/// repository. The sealed codex is itself a [`LibRepo`], so it may be used with any of the
+/// [`Codex`] verification methods.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SealedCodex {
+    codex: Codex,
+    codex_id: CodexId,
+    libs: BTreeMap<LibId, Lib>,
+}
+
+impl SealedCodex {
+    /// Seals the `codex`, validating it and copying the used libraries out of the `repo`.
+    ///
+    /// # Errors
+    ///
+    /// The same as for [`Codex::validate`]; additionally, [`CodexError::LibUnresolved`] if some of
+    /// the libraries called by the verifiers or the witness bank lock scripts are absent from the
+    /// `repo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `repo` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn seal(codex: Codex, repo: &impl LibRepo) -> Result<Self, CodexError> {
+        codex.validate(repo)?;
+        let roots = codex
+            .verifiers
+            .values()
+            .map(|site| site.lib_id)
+            .chain(codex.witness_banks.keys().copied());
+        let libs = lib_closure(roots, repo)
+            .into_iter()
+            .map(|lib_id| {
+                repo.get_lib(lib_id)
+                    .map(|lib| (lib_id, lib.clone()))
+                    .ok_or(CodexError::LibUnresolved(lib_id))
+            })
+            .collect::<Result<_, _>>()?;
+        let codex_id = codex.codex_id();
+        Ok(Self { codex, codex_id, libs })
+    }
+
+    /// Returns the sealed codex.
+    #[inline]
+    pub fn codex(&self) -> &Codex { &self.codex }
+
+    /// Returns the id of the sealed codex.
+    ///
+    /// The method uses cached value, thus running it is inexpensive.
+    #[inline]
+    pub fn codex_id(&self) -> CodexId { self.codex_id }
+
+    /// Returns the ids of the sealed libraries.
+    pub fn lib_ids(&self) -> impl Iterator<Item = LibId> + '_ { self.libs.keys().copied() }
+
+    /// Releases the codex, discarding the sealed libraries.
+    #[inline]
+    pub fn into_codex(self) -> Codex { self.codex }
+
+    /// Verifies the operation with the sealed libraries; see [`Codex::verify`] for the details on
+    /// the arguments and errors.
+    #[inline]
+    pub fn verify(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+    ) -> Result<VerifiedOperation, CallError> {
+        self.codex.verify(contract_id, operation, memory, self)
+    }
+
+    /// Verifies the operation with the sealed libraries, resolving the libraries which are not
+    /// sealed (like the ones used by the input lock scripts) with the `fallback` repository; see
+    /// [`Codex::verify`] for the details on the arguments and errors.
+    ///
+    /// The sealed libraries always take precedence over the ones provided by the `fallback`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `fallback` (library resolver) returns a library which id doesn't match the
+    /// requested one.
+    pub fn verify_with_fallback(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        fallback: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        let repo = FallbackRepo { sealed: self, fallback };
+        self.codex.verify(contract_id, operation, memory, &repo)
+    }
+}
+
+impl LibRepo for SealedCodex {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> { self.libs.get(&lib_id) }
+}
+
+/// Library repository resolving the sealed libraries first, and the rest with the fallback
+/// repository.
+struct FallbackRepo<'s, R: LibRepo> {
+    sealed: &'s SealedCodex,
+    fallback: &'s R,
+}
+
+impl<R: LibRepo> LibRepo for FallbackRepo<'_, R> {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        self.sealed
+            .get_lib(lib_id)
+            .or_else(|| self.fallback.get_lib(lib_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use std::collections::{BTreeSet, HashMap};
+
+    use aluvm::alu::LibSite;
+    use aluvm::FIELD_ORDER_SECP;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::memory::test_helpers::DumbMemory;
+    use crate::{uasm, AuthToken, CellAddr, Input, StateCell, StateValue};
+
+    #[test]
+    fn seal() {
+        let leaf = Lib::assemble(&uasm! { ret; }).unwrap();
+        let leaf_id = leaf.lib_id();
+        let root = Lib::assemble(&uasm! {
+            call    leaf_id, 0;
+            stop;
+        })
+        .unwrap();
+        let root_id = root.lib_id();
+        let other = Lib::assemble(&uasm! { stop; }).unwrap();
+
+        let mut codex = Codex::strict_dumb();
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(root_id, 0) };
+        let repo = HashMap::from([
+            (root_id, root.clone()),
+            (leaf_id, leaf),
+            (other.lib_id(), other.clone()),
+        ]);
+
+        let sealed = SealedCodex::seal(codex.clone(), &repo).unwrap();
+        assert_eq!(sealed.codex_id(), codex.codex_id());
+        assert_eq!(sealed.lib_ids().collect::<BTreeSet<_>>(), bset![root_id, leaf_id]);
+        assert!(sealed.get_lib(other.lib_id()).is_none());
+        let contract_id = ContractId::strict_dumb();
+        assert!(sealed
+            .verify(contract_id, Operation::strict_dumb(), &DumbMemory::default())
+            .is_ok());
+        assert_eq!(sealed.into_codex(), codex);
+
+        let repo = HashMap::from([(root_id, root)]);
+        assert_eq!(
+            SealedCodex::seal(codex.clone(), &repo),
+            Err(CodexError::LibUnresolved(leaf_id))
+        );
+        assert_eq!(
+            SealedCodex::seal(codex, &HashMap::new()),
+            Err(CodexError::LibAbsent { call_id: 0, lib: root_id })
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        struct LockedMemory(LibId);
+        impl Memory for LockedMemory {
+            fn destructible(&self, _: CellAddr) -> Option<StateCell> {
+                Some(StateCell {
+                    data: StateValue::None,
+                    auth: AuthToken::strict_dumb(),
+                    lock: Some(LibSite::new(self.0, 0)),
+                })
+            }
+            fn immutable(&self, _: CellAddr) -> Option<StateValue> { None }
+        }
+
+        let verifier = Lib::assemble(&uasm! { stop; }).unwrap();
+        let lock = Lib::assemble(&uasm! { nop; stop; }).unwrap();
+        let mut codex = Codex::strict_dumb();
+        codex.field_order = FIELD_ORDER_SECP;
+        codex.verifiers = tiny_bmap! { 0 => LibSite::new(verifier.lib_id(), 0) };
+        let repo = HashMap::from([(verifier.lib_id(), verifier)]);
+        let sealed = SealedCodex::seal(codex, &repo).unwrap();
+
+        let contract_id = ContractId::strict_dumb();
+        let mut operation = Operation::strict_dumb();
+        operation.destructible_in =
+            small_vec![Input { addr: CellAddr::strict_dumb(), witness: StateValue::None }];
+        let memory = LockedMemory(lock.lib_id());
+        let fallback = HashMap::from([(lock.lib_id(), lock.clone())]);
+        assert!(sealed
+            .verify(contract_id, operation.clone(), &memory)
+            .is_err());
+        assert!(sealed
+            .verify_with_fallback(contract_id, operation.clone(), &memory, &fallback)
+            .is_ok());
+        assert!(sealed
+            .verify_with_fallback(contract_id, operation, &memory, &HashMap::new())
+            .is_err());
+    }
+}
@@ -31,7 +31,8 @@ use amplify::confinement::SmallBlob;
 use amplify::hex::FromHex;
 use amplify::num::u256;
 use amplify::{hex, Bytes};
-use commit_verify::{CommitEncode, CommitEngine, MerkleHash, StrictHash};
+use commit_verify::{CommitEncode, CommitEngine, DigestExt, MerkleHash, Sha256, StrictHash};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
 
 use crate::LIB_NAME_ULTRASONIC;
 
@@ -248,6 +249,34 @@ impl StateValue {
         StateValue::Double { first: ty.into(), second: val.into() }
     }
 
+    /// Tag used in computing [`Self::field_commitment`].
+    pub const COMMITMENT_TAG: &'static str = "urn:ubideco:ultrasonic:value-commitment#2026-10-14";
+
+    /// Computes a commitment to the state value, represented as a single element of the field
+    /// with the provided order.
+    ///
+    /// The commitment is a SHA256 hash (tagged with [`Self::COMMITMENT_TAG`]) of the number of
+    /// the value elements followed by their 256-bit little-endian representations, reduced modulo
+    /// the `field_order`. It is used by the light clients holding only commitments to the
+    /// immutable memory cells (see [`crate::CommitmentMemory`]).
+    pub fn field_commitment(&self, field_order: u256) -> fe256 {
+        let mut engine = Sha256::from_tag(Self::COMMITMENT_TAG);
+        let elements = self.into_iter().collect::<Vec<_>>();
+        engine.input_raw(&[elements.len() as u8]);
+        for el in elements {
+            engine.input_raw(&el.to_u256().to_le_bytes());
+        }
+        fe256::from(u256::from_le_bytes(engine.finish()) % field_order)
+    }
+
+    /// Returns the index of the first element which is not a member of the field with the
+    /// `field_order` (i.e. is not below the field order), if any.
+    pub(crate) fn non_member(&self, field_order: u256) -> Option<u8> {
+        self.into_iter()
+            .position(|el| el.to_u256() >= field_order)
+            .map(|index| index as u8)
+    }
+
     /// Retrieve a field element with a provided index if the element is present in the state
     /// value.
     pub const fn get(&self, pos: u8) -> Option<fe256> {
@@ -302,6 +331,20 @@ pub struct StateCell {
     pub lock: Option<LibSite>,
 }
 
+impl StrictSerialize for StateCell {}
+impl StrictDeserialize for StateCell {}
+
+impl StateCell {
+    /// Checks whether the memory cell has additional locking conditions, requiring a witness to
+    /// satisfy the lock script in order to access the cell.
+    #[inline]
+    pub fn is_locked(&self) -> bool { self.lock.is_some() }
+
+    /// Returns the location of the lock script, if the cell is locked (see [`Self::lock`]).
+    #[inline]
+    pub fn lock_site(&self) -> Option<LibSite> { self.lock }
+}
+
 /// The raw data for the immutable (read-only) memory cells.
 ///
 /// The raw data cannot be accessed by the verification scripts and zk-AluVM, and are not
@@ -340,6 +383,9 @@ pub struct StateData {
     pub raw: Option<RawData>,
 }
 
+impl StrictSerialize for StateData {}
+impl StrictDeserialize for StateData {}
+
 impl CommitEncode for StateData {
     type CommitmentId = MerkleHash;
 
@@ -370,17 +416,72 @@ impl StateData {
     pub fn with_raw(ty: impl Into<fe256>, val: impl Into<fe256>, raw: impl Into<RawData>) -> Self {
         Self { value: StateValue::new(ty, val), raw: Some(raw.into()) }
     }
+
+    /// Tag used in computing [`Self::content_hash`].
+    pub const CONTENT_HASH_TAG: &'static str = "urn:ubideco:ultrasonic:state-data#2026-10-15";
+
+    /// Computes a hash of the state data content, including the raw data, which can be used by
+    /// the state stores to deduplicate identical immutable memory cells.
+    ///
+    /// The hash is a SHA256 hash (tagged with [`Self::CONTENT_HASH_TAG`]) of the strict-encoded
+    /// state data. Since the strict encoding is canonical, structurally equal state data always
+    /// have the same hash. Unlike the commitment to the state data (produced with
+    /// [`CommitEncode`]), the hash covers the raw data bytes directly and is not a part of the
+    /// consensus.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let data = self
+            .to_strict_serialized::<{ u32::MAX as usize }>()
+            .expect("raw data are limited to 64kiB");
+        let mut engine = Sha256::from_tag(Self::CONTENT_HASH_TAG);
+        engine.input_raw(data.as_slice());
+        engine.finish()
+    }
 }
 
 #[cfg(test)]
 mod test {
     #![cfg_attr(coverage_nightly, coverage(off))]
 
+    use aluvm::alu::LibId;
+    use amplify::ByteArray;
+    #[cfg(feature = "serde")]
     use strict_encoding::StrictDumb;
 
-    #[cfg(feature = "baid64")]
     use super::*;
 
+    #[test]
+    fn field_commitment() {
+        use aluvm::FIELD_ORDER_SECP;
+
+        let none = StateValue::None.field_commitment(FIELD_ORDER_SECP);
+        let zero =
+            StateValue::Single { first: fe256::from(0u8) }.field_commitment(FIELD_ORDER_SECP);
+        let double = StateValue::new(0u8, 0u8).field_commitment(FIELD_ORDER_SECP);
+        assert_ne!(none, zero);
+        assert_ne!(zero, double);
+        assert!(double.to_u256() < FIELD_ORDER_SECP);
+        assert_eq!(StateValue::new(0u8, 0u8).field_commitment(FIELD_ORDER_SECP), double);
+
+        let small = u256::from(17u8);
+        assert!(StateValue::new(1u8, 2u8).field_commitment(small).to_u256() < small);
+    }
+
+    #[test]
+    fn cell_lock() {
+        let mut cell = StateCell {
+            data: StateValue::None,
+            auth: AuthToken::from(fe256::from(1u8)),
+            lock: None,
+        };
+        assert!(!cell.is_locked());
+        assert_eq!(cell.lock_site(), None);
+
+        let site = LibSite::new(LibId::from_byte_array([0xAA; 32]), 4);
+        cell.lock = Some(site);
+        assert!(cell.is_locked());
+        assert_eq!(cell.lock_site(), Some(site));
+    }
+
     #[test]
     #[cfg(all(feature = "serde", feature = "baid64"))]
     fn auth_baid64() {
@@ -412,6 +513,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn content_hash() {
+        let data = StateData::with_raw(1u8, 2u8, RawData::from(small_blob!(0xAB, 0xCD)));
+        let same = StateData::with_raw(1u8, 2u8, RawData::from(small_blob!(0xAB, 0xCD)));
+        assert_eq!(data.content_hash(), same.content_hash());
+
+        let other_raw = StateData::with_raw(1u8, 2u8, RawData::from(small_blob!(0xAB)));
+        assert_ne!(data.content_hash(), other_raw.content_hash());
+        assert_ne!(data.content_hash(), StateData::new(1u8, 2u8).content_hash());
+        assert_ne!(
+            StateData::new(1u8, 2u8).content_hash(),
+            StateData::new(1u8, 3u8).content_hash()
+        );
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn raw_data_serde() {
@@ -21,6 +21,7 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use amplify::Bytes32;
 use commit_verify::StrictHash;
 use strict_encoding::stl::AsciiPrintable;
 use strict_encoding::RString;
@@ -58,3 +59,55 @@ impl Identity {
     /// Tests whether the identity is anonymous.
     pub fn is_anonymous(&self) -> bool { self == &default!() }
 }
+
+/// Signature scheme of a codex developer key (see [`DeveloperKey`]).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(lowercase)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[repr(u8)]
+pub enum SigScheme {
+    /// BIP-340 Schnorr signatures over secp256k1, with x-only public keys.
+    #[strict_type(dumb)]
+    Bip340 = 0,
+
+    /// Ed25519 signatures.
+    Ed25519 = 1,
+}
+
+/// Public key of a codex developer (see [`crate::Codex::developer_key`]).
+///
+/// The codex commits both to the signature scheme and to the 32-byte public key of that scheme,
+/// such that the key bytes can't be interpreted under a different scheme.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct DeveloperKey {
+    /// Signature scheme of the key.
+    pub scheme: SigScheme,
+    /// Serialized public key of the scheme.
+    pub key: Bytes32,
+}
+
+impl DeveloperKey {
+    /// Constructs a developer key of a given signature scheme.
+    pub fn new(scheme: SigScheme, key: impl Into<Bytes32>) -> Self {
+        Self { scheme, key: key.into() }
+    }
+}
+
+/// Signature of a codex developer, verified with [`crate::Codex::verify_developer_signature`].
+///
+/// The crate doesn't depend on any signature scheme implementation; applications implement the
+/// trait for the signature types of the schemes listed in [`SigScheme`] which they support.
+pub trait DeveloperSig {
+    /// Signature scheme of the signature.
+    ///
+    /// Signatures are verified only against developer keys of the same scheme.
+    fn scheme(&self) -> SigScheme;
+
+    /// Verifies the signature over the `msg` with the public `key` of the [`Self::scheme`].
+    fn verify(&self, key: &Bytes32, msg: &[u8; 32]) -> bool;
+}
@@ -0,0 +1,170 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Benchmark of the heap allocations performed by the operation verification.
+//!
+//! The test binary counts the allocations with a custom global allocator, so the numbers are
+//! exact and do not depend on the machine. Run with `cargo test --test allocations -- --nocapture`
+//! to see the figures.
+//!
+//! The numbers of the verifications running scripts are dominated by the execution trace, which
+//! zk-AluVM prints in the test builds, and depend on whether the test output is captured. Thus the
+//! exact comparison is made for the operations calling a method without a verifier, which are
+//! rejected right after loading their inputs, before running any script.
+
+#![cfg_attr(coverage_nightly, feature(coverage_attribute), coverage(off))]
+
+#[macro_use]
+extern crate amplify;
+
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use amplify::ByteArray;
+use common::{codex, contract_id, lock_lib, locked_addr, locked_cell, verifier_lib};
+use strict_encoding::StrictDumb;
+use ultrasonic::{
+    AuthToken, CellAddr, Input, LibRepo, Memory, Operation, Opid, StateCell, StateValue,
+};
+use zkaluvm::alu::{Lib, LibId, Vm};
+use zkaluvm::{fe256, gfa, GfaConfig, FIELD_ORDER_SECP};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { System.dealloc(ptr, layout) }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Returns the number of the heap allocations performed by the current thread within `f`.
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    let res = f();
+    let count = ALLOCATIONS.with(Cell::get) - before;
+    drop(res);
+    count
+}
+
+/// Memory holding the locked cell and an unlocked cell at any other address.
+struct Stand {
+    libs: Vec<Lib>,
+    locked: StateCell,
+}
+
+impl Memory for Stand {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        match addr == locked_addr() {
+            true => Some(self.locked),
+            false => Some(StateCell { lock: None, ..self.locked }),
+        }
+    }
+
+    fn immutable(&self, _: CellAddr) -> Option<StateValue> { None }
+}
+
+impl LibRepo for Stand {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        self.libs.iter().find(|lib| lib.lib_id() == lib_id)
+    }
+}
+
+#[test]
+fn verify_allocations() {
+    let lock = lock_lib();
+    let verifier = verifier_lib();
+    let codex = codex(&verifier);
+    let stand = Stand { locked: locked_cell(&lock), libs: vec![lock, verifier] };
+
+    let contract_id = contract_id();
+    let mut no_inputs = common::operation();
+    no_inputs
+        .destructible_out
+        .push(StateCell {
+            data: StateValue::Single { first: fe256::from(1u8) },
+            auth: AuthToken::strict_dumb(),
+            lock: None,
+        })
+        .unwrap();
+    let mut unlocked_input = no_inputs.clone();
+    unlocked_input
+        .destructible_in
+        .push(Input {
+            addr: CellAddr::new(Opid::from_byte_array([0x0B; 32]), 0),
+            witness: none!(),
+        })
+        .unwrap();
+    let mut locked_input = no_inputs.clone();
+    locked_input
+        .destructible_in
+        .push(common::locked_input())
+        .unwrap();
+
+    let vm = |config| {
+        allocations(|| {
+            Vm::<gfa::Instr<LibId>>::with(config, GfaConfig { field_order: FIELD_ORDER_SECP })
+        })
+    };
+    let input_vm = vm(codex.input_config);
+    let main_vm = vm(codex.verification_config);
+    let verify = |operation: &Operation| {
+        let operation = operation.clone();
+        allocations(|| codex.verify(contract_id, operation, &stand, &stand))
+    };
+    let load = |operation: &Operation| {
+        let mut operation = operation.clone();
+        operation.call_id = 1;
+        verify(&operation)
+    };
+    let loaded_no_inputs = load(&no_inputs);
+    let loaded_unlocked_input = load(&unlocked_input);
+    let no_inputs = verify(&no_inputs);
+    let unlocked_input = verify(&unlocked_input);
+    let locked_input = verify(&locked_input);
+
+    println!("input VM construction:              {input_vm:>4} allocations");
+    println!("verification VM construction:       {main_vm:>4} allocations");
+    println!("loading no inputs:                  {loaded_no_inputs:>4} allocations");
+    println!("loading one input:                  {loaded_unlocked_input:>4} allocations");
+    println!("verification without inputs:        {no_inputs:>4} allocations");
+    println!("verification with one input:        {unlocked_input:>4} allocations");
+    println!("verification with one locked input: {locked_input:>4} allocations");
+
+    // The operation without inputs allocates nothing before running the verifier. The only
+    // allocation for an input without a lock script is the storage for its value: the VM checking
+    // the input access conditions is not constructed.
+    assert_eq!(loaded_no_inputs, 0);
+    assert_eq!(loaded_unlocked_input, 1);
+}
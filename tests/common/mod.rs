@@ -0,0 +1,95 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Fixture shared by the integration tests: a codex with a verifier accepting any operation, and a
+//! memory cell protected by a lock script which requires a secret to be provided in the witness.
+
+#![allow(dead_code)]
+
+// Used by `uasm!` unless the `std` feature is enabled
+extern crate alloc;
+
+use amplify::ByteArray;
+use strict_encoding::StrictDumb;
+use ultrasonic::{
+    uasm, AuthToken, CellAddr, Codex, ContractId, Input, Operation, Opid, StateCell, StateValue,
+};
+use zkaluvm::alu::{CoreConfig, Lib, LibSite};
+use zkaluvm::{fe256, FIELD_ORDER_SECP};
+
+/// Secret which must be provided in the witness of the locked input.
+pub const SECRET: u8 = 48;
+
+/// Lock script failing with the code 1 unless the witness starts with the [`SECRET`].
+pub fn lock_lib() -> Lib {
+    Lib::assemble(&uasm! {
+        put     EA, SECRET;
+        put     E8, 1;
+        eq      EA, E2;
+        chk     CO;
+    })
+    .unwrap()
+}
+
+/// Verifier accepting any operation.
+pub fn verifier_lib() -> Lib { Lib::assemble(&uasm! { stop; }).unwrap() }
+
+/// Codex running the `verifier` for the call 0.
+pub fn codex(verifier: &Lib) -> Codex {
+    let mut codex = Codex::strict_dumb();
+    codex.field_order = FIELD_ORDER_SECP;
+    codex.input_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+    codex.verification_config = CoreConfig { halt: true, complexity_lim: Some(10_000_000) };
+    codex.verifiers = tiny_bmap! { 0 => LibSite::new(verifier.lib_id(), 0) };
+    codex
+}
+
+/// Id of the contract of the test operations.
+pub fn contract_id() -> ContractId { ContractId::from_byte_array([0xC0; 32]) }
+
+/// Operation of the [`contract_id`] contract calling the method 0, without inputs and outputs.
+pub fn operation() -> Operation {
+    let mut operation = Operation::strict_dumb();
+    operation.contract_id = contract_id();
+    operation
+}
+
+/// Address of the memory cell protected by the [`lock_lib`].
+pub fn locked_addr() -> CellAddr { CellAddr::new(Opid::from_byte_array([0x0A; 32]), 0) }
+
+/// Memory cell protected by the `lock` script.
+pub fn locked_cell(lock: &Lib) -> StateCell {
+    StateCell {
+        data: StateValue::None,
+        auth: AuthToken::strict_dumb(),
+        lock: Some(LibSite::new(lock.lib_id(), 0)),
+    }
+}
+
+/// Input spending the [`locked_cell`] with a valid witness.
+pub fn locked_input() -> Input {
+    Input {
+        addr: locked_addr(),
+        witness: StateValue::Single { first: fe256::from(SECRET) },
+    }
+}
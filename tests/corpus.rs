@@ -0,0 +1,191 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Regression tests running the operation verification over the corpus of recorded cases.
+//!
+//! Each file in the `tests/corpus/` directory with the `.case` extension is a strict-serialized
+//! [`CorpusCase`], holding a codex, an operation, the contract memory and the libraries required
+//! for the verification, together with the recorded verification result: either `ok` or the
+//! `Debug` representation of the returned [`CallError`].
+//!
+//! To turn a verification issue (for instance, found by fuzzing) into a durable test, serialize
+//! it as a [`CorpusCase`] into a new file in the corpus directory. The seed cases are produced
+//! with `cargo test --test corpus -- --ignored`; they must be regenerated each time the
+//! encoding of the consensus data structures changes.
+
+#![cfg_attr(coverage_nightly, feature(coverage_attribute), coverage(off))]
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate strict_encoding;
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use amplify::confinement::{Confined, TinyOrdMap, TinyString, TinyVec};
+use strict_encoding::{
+    StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize, StrictType,
+};
+use ultrasonic::{
+    CallError, CellAddr, Codex, ContractId, LibRepo, Memory, Operation, StateCell, StateValue,
+};
+use zkaluvm::alu::{Lib, LibId};
+use zkaluvm::fe256;
+
+const CORPUS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+const CASE_MAX_LEN: usize = 0xFF_FFFF;
+
+/// Recorded verification case.
+#[derive(Clone, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = "UltraSONICCorpus")]
+struct CorpusCase {
+    contract_id: ContractId,
+    codex: Codex,
+    operation: Operation,
+    libs: TinyVec<Lib>,
+    destructible: TinyOrdMap<CellAddr, StateCell>,
+    immutable: TinyOrdMap<CellAddr, StateValue>,
+    /// Either `ok` or the `Debug` representation of the [`CallError`].
+    expected: TinyString,
+}
+
+impl StrictDumb for CorpusCase {
+    fn strict_dumb() -> Self {
+        Self {
+            contract_id: strict_dumb!(),
+            codex: strict_dumb!(),
+            operation: strict_dumb!(),
+            libs: none!(),
+            destructible: none!(),
+            immutable: none!(),
+            expected: none!(),
+        }
+    }
+}
+
+impl StrictSerialize for CorpusCase {}
+impl StrictDeserialize for CorpusCase {}
+
+impl Memory for CorpusCase {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        self.destructible.get(&addr).copied()
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.immutable.get(&addr).copied() }
+}
+
+impl LibRepo for CorpusCase {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        self.libs.iter().find(|lib| lib.lib_id() == lib_id)
+    }
+}
+
+impl CorpusCase {
+    fn outcome(result: Result<(), CallError>) -> String {
+        match result {
+            Ok(()) => s!("ok"),
+            Err(err) => format!("{err:?}"),
+        }
+    }
+
+    fn run(&self) -> String {
+        let result = self
+            .codex
+            .verify(self.contract_id, self.operation.clone(), self, self)
+            .map(|_| ());
+        Self::outcome(result)
+    }
+}
+
+#[test]
+fn corpus() {
+    let mut count = 0usize;
+    for entry in fs::read_dir(CORPUS_DIR).expect("missing corpus directory") {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("case") {
+            continue;
+        }
+        let data = fs::read(&path).unwrap();
+        let case = CorpusCase::from_strict_serialized::<CASE_MAX_LEN>(Confined::from_checked(data))
+            .unwrap_or_else(|err| panic!("invalid corpus case {}: {err}", path.display()));
+        assert_eq!(case.run(), case.expected.as_str(), "corpus case {}", path.display());
+        count += 1;
+    }
+    assert!(count > 0, "the corpus is empty");
+}
+
+fn seed_cases() -> BTreeMap<&'static str, CorpusCase> {
+    let lock = common::lock_lib();
+    let verifier = common::verifier_lib();
+    let mut operation = common::operation();
+    operation
+        .destructible_in
+        .push(common::locked_input())
+        .unwrap();
+
+    let valid = CorpusCase {
+        contract_id: common::contract_id(),
+        codex: common::codex(&verifier),
+        operation,
+        destructible: tiny_bmap! { common::locked_addr() => common::locked_cell(&lock) },
+        libs: tiny_vec![lock.clone(), verifier],
+        immutable: none!(),
+        expected: none!(),
+    };
+
+    let mut wrong_witness = valid.clone();
+    wrong_witness.operation.destructible_in[0].witness =
+        StateValue::Single { first: fe256::from(common::SECRET + 1) };
+
+    let mut missing_lib = valid.clone();
+    missing_lib.libs = TinyVec::from_iter_checked(
+        valid
+            .libs
+            .iter()
+            .filter(|lib| lib.lib_id() != lock.lib_id())
+            .cloned(),
+    );
+
+    bmap! {
+        "seed-valid" => valid,
+        "seed-wrong-witness" => wrong_witness,
+        "seed-missing-lock-lib" => missing_lib,
+    }
+}
+
+#[test]
+#[ignore = "regenerates the seed corpus cases"]
+fn generate_seed_corpus() {
+    let dir = PathBuf::from(CORPUS_DIR);
+    fs::create_dir_all(&dir).unwrap();
+    for (name, mut case) in seed_cases() {
+        case.expected = TinyString::from_checked(case.run());
+        let data = case.to_strict_serialized::<CASE_MAX_LEN>().unwrap();
+        fs::write(dir.join(format!("{name}.case")), data.as_slice()).unwrap();
+    }
+}